@@ -0,0 +1,440 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    content::ContentMatcher,
+    decompress::Codec,
+    filter::{FilterType, Stat},
+    query::Expr,
+    result::FileKind,
+    search::Matcher,
+    utils::{days_from_civil, path_from_bytes, path_to_bytes},
+    LineMatch,
+};
+
+/// What separates the path of an archive from the path of a member inside it.
+const SEPARATOR: &[u8] = b"!/";
+
+/// The largest nested archive that is read into memory to look inside it.
+const MAX_NESTED_LEN: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Zip,
+    Tar(Option<Codec>),
+}
+
+/// The archive format of a file, told by its name.
+fn format_of(path: &Path) -> Option<Format> {
+    const SUFFIXES: [(&str, Format); 12] = [
+        (".zip", Format::Zip),
+        (".tar", Format::Tar(None)),
+        (".tar.gz", Format::Tar(Some(Codec::Gzip))),
+        (".tgz", Format::Tar(Some(Codec::Gzip))),
+        (".tar.xz", Format::Tar(Some(Codec::Xz))),
+        (".txz", Format::Tar(Some(Codec::Xz))),
+        (".tar.zst", Format::Tar(Some(Codec::Zstd))),
+        (".tar.zstd", Format::Tar(Some(Codec::Zstd))),
+        (".tzst", Format::Tar(Some(Codec::Zstd))),
+        (".tar.bz2", Format::Tar(Some(Codec::Bzip2))),
+        (".tbz", Format::Tar(Some(Codec::Bzip2))),
+        (".tbz2", Format::Tar(Some(Codec::Bzip2))),
+    ];
+    let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+    SUFFIXES
+        .iter()
+        .find(|(suffix, _)| name.ends_with(suffix))
+        .map(|&(_, format)| format)
+}
+
+/// Whether the name of `path` is that of an archive that can be searched.
+pub fn is_archive(path: &Path) -> bool {
+    format_of(path).is_some()
+}
+
+/// A file, directory or link inside an archive.
+pub struct Member<'a> {
+    /// The virtual path, e.g. `backup.tar.gz!/etc/hosts`.
+    pub path: PathBuf,
+    /// The path inside the innermost archive, e.g. `etc/hosts`.
+    pub inner: PathBuf,
+    pub kind: FileKind,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// How many components below the archive on the disk, counting nested archives.
+    pub depth: usize,
+    reader: &'a mut dyn Read,
+}
+
+impl Member<'_> {
+    /// The contents of the member, which can only be read once.
+    pub fn reader(&mut self) -> &mut dyn Read {
+        self.reader
+    }
+}
+
+impl Stat for Member<'_> {
+    fn len(&self) -> u64 {
+        self.size
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    fn created(&self) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// Calls `visit` for every member of the archive at `path`, and of the archives inside
+/// it up to `max_depth` levels of archives, while it returns `true`.
+///
+/// The virtual paths of the members start with `shown`, the archive path as the search
+/// writes it. Returns `false` if `visit` asked to stop. Members of nested archives that
+/// can't be read are skipped, and nested archives larger than [`MAX_NESTED_LEN`] are
+/// visited like other members without looking inside them.
+/// ### Errors
+/// Returns an error if the archive at `path` can't be opened or read.
+pub fn scan_file(
+    path: &Path,
+    shown: &Path,
+    max_depth: usize,
+    visit: &mut dyn FnMut(&mut Member<'_>) -> bool,
+) -> io::Result<bool> {
+    let Some(format) = format_of(path) else {
+        return Ok(true);
+    };
+    let mut scan = Scan {
+        max_depth,
+        max_nested_len: MAX_NESTED_LEN,
+        visit,
+    };
+    scan.members(
+        format,
+        BufReader::new(File::open(path)?),
+        &path_to_bytes(shown),
+        1,
+        0,
+    )
+}
+
+struct Scan<'v> {
+    max_depth: usize,
+    max_nested_len: u64,
+    visit: &'v mut dyn FnMut(&mut Member<'_>) -> bool,
+}
+
+impl Scan<'_> {
+    fn members<R: Read + Seek>(
+        &mut self,
+        format: Format,
+        reader: R,
+        prefix: &[u8],
+        level: usize,
+        base: usize,
+    ) -> io::Result<bool> {
+        match format {
+            Format::Zip => {
+                let mut zip = zip::ZipArchive::new(reader).map_err(io::Error::other)?;
+                for i in 0..zip.len() {
+                    let mut file = zip.by_index(i).map_err(io::Error::other)?;
+                    let kind = if file.is_dir() {
+                        FileKind::Dir
+                    } else if file.is_symlink() {
+                        FileKind::Symlink
+                    } else {
+                        FileKind::File
+                    };
+                    let name = file.name_raw().to_vec();
+                    let (size, modified) = (file.size(), file.last_modified().and_then(zip_time));
+                    if !self.member(
+                        prefix,
+                        &name,
+                        kind,
+                        size,
+                        modified,
+                        (level, base),
+                        &mut file,
+                    )? {
+                        return Ok(false);
+                    }
+                }
+            }
+            Format::Tar(codec) => {
+                let reader: Box<dyn Read> = match codec {
+                    Some(codec) => codec.decoder(reader)?,
+                    None => Box::new(reader),
+                };
+                let mut tar = tar::Archive::new(reader);
+                for entry in tar.entries()? {
+                    let mut entry = entry?;
+                    let header = entry.header();
+                    let entry_type = header.entry_type();
+                    let kind = if entry_type.is_dir() {
+                        FileKind::Dir
+                    } else if entry_type.is_symlink() {
+                        FileKind::Symlink
+                    } else if entry_type.is_file() {
+                        FileKind::File
+                    } else {
+                        FileKind::Other
+                    };
+                    let size = header.size().unwrap_or(0);
+                    let modified = header.mtime().ok().and_then(|secs| {
+                        SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+                    });
+                    let name = entry.path_bytes().into_owned();
+                    if !self.member(
+                        prefix,
+                        &name,
+                        kind,
+                        size,
+                        modified,
+                        (level, base),
+                        &mut entry,
+                    )? {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Visit one member, then the members inside it if it is an archive. `level` counts
+    /// the archives the member is in and `base` is the depth of the innermost one.
+    #[allow(clippy::too_many_arguments)]
+    fn member(
+        &mut self,
+        prefix: &[u8],
+        name: &[u8],
+        kind: FileKind,
+        size: u64,
+        modified: Option<SystemTime>,
+        (level, base): (usize, usize),
+        reader: &mut dyn Read,
+    ) -> io::Result<bool> {
+        let inner = clean_name(name);
+        if inner.is_empty() {
+            return Ok(true);
+        }
+        let mut path = prefix.to_vec();
+        path.extend_from_slice(SEPARATOR);
+        path.extend_from_slice(inner);
+        let path = path_from_bytes(path);
+        let inner = path_from_bytes(inner.to_vec());
+        let depth = base + inner.components().count();
+
+        let nested =
+            (kind == FileKind::File && level < self.max_depth && size <= self.max_nested_len)
+                .then(|| format_of(&inner))
+                .flatten();
+        let Some(format) = nested else {
+            let mut member = Member {
+                path,
+                inner,
+                kind,
+                size,
+                modified,
+                depth,
+                reader,
+            };
+            return Ok((self.visit)(&mut member));
+        };
+
+        // The size in the header may be wrong, so the read is limited too, and an archive
+        // that turns out larger is visited without looking inside it.
+        let mut data = Vec::new();
+        reader
+            .take(self.max_nested_len + 1)
+            .read_to_end(&mut data)?;
+        if data.len() as u64 > self.max_nested_len {
+            let mut member = Member {
+                path,
+                inner,
+                kind,
+                size,
+                modified,
+                depth,
+                reader: &mut Cursor::new(data).chain(reader),
+            };
+            return Ok((self.visit)(&mut member));
+        }
+        let prefix = path_to_bytes(&path);
+        let mut member = Member {
+            path,
+            inner,
+            kind,
+            size,
+            modified,
+            depth,
+            reader: &mut Cursor::new(&data),
+        };
+        if !(self.visit)(&mut member) {
+            return Ok(false);
+        }
+        // A nested archive that can't be read is skipped like any other unreadable file.
+        Ok(self
+            .members(format, Cursor::new(data), &prefix, level + 1, depth)
+            .unwrap_or(true))
+    }
+}
+
+/// The name of a member without a leading `./` or `/` and a trailing `/`.
+fn clean_name(mut name: &[u8]) -> &[u8] {
+    loop {
+        if let Some(rest) = name.strip_prefix(b"./") {
+            name = rest;
+        } else if let Some(rest) = name.strip_prefix(b"/") {
+            name = rest;
+        } else {
+            break;
+        }
+    }
+    while let Some(rest) = name.strip_suffix(b"/") {
+        name = rest;
+    }
+    if name == b"." {
+        return &[];
+    }
+    name
+}
+
+/// Zip times have no time zone, they are taken as UTC.
+fn zip_time(time: zip::DateTime) -> Option<SystemTime> {
+    let days = days_from_civil(
+        i64::from(time.year()),
+        i64::from(time.month()),
+        i64::from(time.day()),
+    );
+    let secs = days * 86_400
+        + i64::from(time.hour()) * 3600
+        + i64::from(time.minute()) * 60
+        + i64::from(time.second());
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+/// The rules of the search that members are matched with.
+pub struct MemberRules<'a> {
+    pub matcher: &'a Matcher,
+    pub query: Option<&'a Expr>,
+    pub content: Option<&'a ContentMatcher>,
+    pub filters: &'a [FilterType],
+    pub hidden: bool,
+}
+
+impl MemberRules<'_> {
+    /// Match a member like a file found on the disk.
+    ///
+    /// Returns `None` if it doesn't match, otherwise the matching lines, which are only
+    /// collected when asked for. Custom filters, which need an entry on the disk, are not
+    /// applied to members.
+    pub fn matches(&self, member: &mut Member<'_>) -> Option<Vec<LineMatch>> {
+        let hidden = member
+            .inner
+            .components()
+            .any(|c| c.as_os_str().as_encoded_bytes().starts_with(b"."));
+        if (hidden && !self.hidden)
+            || !self
+                .matcher
+                .is_match_path(&member.path, member.kind == FileKind::Dir)
+            || !self
+                .filters
+                .iter()
+                .all(|filter| filter.apply_stat(&*member).unwrap_or(true))
+            || self
+                .query
+                .is_some_and(|query| !query.matches_with(&member.path, &|| Some(&*member)))
+        {
+            return None;
+        }
+        match self.content {
+            Some(content) if member.kind == FileKind::File => {
+                let (path, size) = (member.inner.clone(), member.size);
+                content.search_read(&path, member.reader(), size)
+            }
+            Some(_) => None,
+            None => Some(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn member_names_are_cleaned() {
+        assert_eq!(clean_name(b"./etc/hosts"), b"etc/hosts");
+        assert_eq!(clean_name(b"/etc/"), b"etc");
+        assert_eq!(clean_name(b"./"), b"");
+        assert_eq!(clean_name(b"."), b"");
+        assert!(is_archive(Path::new("backup.TAR.GZ")));
+        assert!(is_archive(Path::new("logs.tar.xz")));
+        assert!(!is_archive(Path::new("notes.gz")));
+    }
+
+    fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *contents).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn large_nested_archives_are_not_opened() {
+        let inner = tar(&[("a.txt", b"a")]);
+        let len = inner.len() as u64;
+        // `size` is the size in the header, which can be smaller than the member.
+        let scan = |max_nested_len: u64, size: u64| {
+            let mut found = Vec::new();
+            let mut visit = |member: &mut Member<'_>| {
+                let mut data = Vec::new();
+                member.reader().read_to_end(&mut data).unwrap();
+                found.push((member.path.clone(), data.len()));
+                true
+            };
+            let mut scan = Scan {
+                max_depth: 2,
+                max_nested_len,
+                visit: &mut visit,
+            };
+            let mut reader = Cursor::new(&inner);
+            scan.member(
+                b"x.tar",
+                b"inner.tar",
+                FileKind::File,
+                size,
+                None,
+                (1, 0),
+                &mut reader,
+            )
+            .unwrap();
+            found
+        };
+
+        assert_eq!(
+            scan(MAX_NESTED_LEN, len),
+            [
+                (PathBuf::from("x.tar!/inner.tar"), inner.len()),
+                (PathBuf::from("x.tar!/inner.tar!/a.txt"), 1)
+            ]
+        );
+        for size in [len, 0] {
+            assert_eq!(
+                scan(len - 1, size),
+                [(PathBuf::from("x.tar!/inner.tar"), inner.len())]
+            );
+        }
+    }
+}
@@ -0,0 +1,465 @@
+//! Command line interface for `rust_search`, built with the `cli` feature.
+//!
+//! ```text
+//! rsearch [OPTIONS] [PATTERN] [LOCATION]...
+//! ```
+
+use std::{
+    io::{self, BufWriter, IsTerminal, Write},
+    path::PathBuf,
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use clap::{Parser, ValueEnum};
+use rust_search::{
+    similarity_sort_results, write_csv, write_json, write_ndjson, Acronym, Column,
+    DamerauLevenshtein, Exec, FzfV2, JaroWinkler, Levenshtein, PathStyle, ResultFormat,
+    SavedSearches, ScoreTarget, SearchBuilder, SearchResult, SearchSpec, SearchStats, SorensenDice,
+};
+
+/// Blazingly fast file search
+#[derive(Parser, Debug)]
+#[command(name = "rsearch", version, about)]
+struct Args {
+    /// Search input matched against file names, as a regular expression
+    pattern: Option<String>,
+
+    /// Locations to search in, defaults to the current directory
+    location: Vec<PathBuf>,
+
+    /// Only find files with one of these extensions
+    #[arg(short, long, value_delimiter = ',')]
+    ext: Vec<String>,
+
+    /// How many directories deep to search
+    #[arg(short, long)]
+    depth: Option<usize>,
+
+    /// Stop after this many results
+    #[arg(short, long)]
+    limit: Option<usize>,
+
+    /// Require the file name to end with the search input, right before its extension
+    #[arg(short, long)]
+    strict: bool,
+
+    /// Match file names regardless of case
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Search hidden files and directories too
+    #[arg(short = 'H', long)]
+    hidden: bool,
+
+    /// Filter by size, e.g. `>2MB`, `<10k` or `=0`
+    #[arg(long, value_name = "COND")]
+    size: Vec<String>,
+
+    /// Filter by modification time, e.g. `<30d` (newer than 30 days) or `>2024-01-31`
+    #[arg(long, value_name = "COND")]
+    modified: Vec<String>,
+
+    /// Filter by creation time, e.g. `>1w` (older than a week) or `<2024-01-31`
+    #[arg(long, value_name = "COND")]
+    created: Vec<String>,
+
+    /// Additional one-line query, e.g. `ext:pdf,docx -path:archive`
+    #[arg(short, long)]
+    query: Option<String>,
+
+    /// Only keep files containing this text
+    #[arg(long, value_name = "TEXT", conflicts_with = "contains_regex")]
+    contains: Option<String>,
+
+    /// Only keep files with a line matching this regular expression
+    #[arg(long, value_name = "REGEX")]
+    contains_regex: Option<String>,
+
+    /// Search the contents of binary files too
+    #[arg(long)]
+    include_binary: bool,
+
+    /// Skip the contents of files larger than this, e.g. `10MB`
+    #[arg(long, value_name = "SIZE")]
+    max_file_size: Option<String>,
+
+    /// Print every matching line as `path:line:text`
+    #[arg(long)]
+    lines: bool,
+
+    /// Decompress gzip, xz, zstd and bzip2 files when searching contents, like `zgrep`
+    #[cfg(feature = "compressed")]
+    #[arg(short = 'z', long)]
+    decompress: bool,
+
+    /// Search inside zip and tar archives, and archives nested in them up to this depth
+    #[cfg(feature = "archives")]
+    #[arg(long, value_name = "DEPTH", num_args = 0..=1, default_missing_value = "1")]
+    archives: Option<usize>,
+
+    /// How to write the found paths
+    #[arg(long, value_enum, default_value_t = Style::AsGiven)]
+    paths: Style,
+
+    /// Write the found paths relative to this directory
+    #[arg(long, value_name = "DIR", conflicts_with = "paths")]
+    relative_to: Option<PathBuf>,
+
+    /// Sort the results by similarity with the pattern
+    #[arg(long)]
+    sort_similarity: bool,
+
+    /// The scoring algorithm used by `--sort-similarity`
+    #[arg(long, value_enum, default_value_t = ScorerKind::JaroWinkler)]
+    scorer: ScorerKind,
+
+    /// Separate results with NUL instead of newline, for `xargs -0`
+    #[arg(short = '0', long)]
+    print0: bool,
+
+    /// Start from the search in this TOML or JSON file
+    #[arg(long, value_name = "FILE")]
+    spec: Option<PathBuf>,
+
+    /// Start from a search saved in the config file
+    #[arg(long, value_name = "NAME")]
+    saved: Option<String>,
+
+    /// Save the search given by the options under this name in the config file
+    #[arg(long, value_name = "NAME")]
+    save: Option<String>,
+
+    /// How to write the results
+    #[arg(short, long, value_enum, default_value_t = Output::Plain)]
+    output: Output,
+
+    /// Write each result following a template, e.g. `{size:h}\t{mtime:%Y-%m-%d}\t{relpath}`
+    #[arg(short, long, value_name = "TEMPLATE", value_parser = parse_format, conflicts_with_all = ["output", "lines"])]
+    format: Option<ResultFormat>,
+
+    /// The columns of the CSV output, e.g. `path,size,mtime`
+    #[arg(long, value_delimiter = ',', value_parser = parse_column)]
+    columns: Option<Vec<Column>>,
+
+    /// Run a command for every result instead of printing it, e.g. `gzip {}`
+    #[arg(short = 'x', long, value_name = "CMD", value_parser = parse_exec, conflicts_with_all = ["output", "format", "lines"])]
+    exec: Option<Exec>,
+
+    /// Run a command once for as many results as fit, e.g. `tar czf logs.tgz {}`
+    #[arg(short = 'X', long, value_name = "CMD", value_parser = parse_exec, conflicts_with_all = ["exec", "output", "format", "lines"])]
+    exec_batch: Option<Exec>,
+
+    /// How many commands to run at once, defaults to the number of CPUs
+    #[arg(short, long)]
+    jobs: Option<usize>,
+
+    /// Start no more commands once one fails
+    #[arg(long)]
+    stop_on_failure: bool,
+
+    /// Show a spinner with the number of files scanned on stderr while searching
+    #[arg(long)]
+    progress: bool,
+
+    /// Write a summary of the search to stderr at the end, e.g. entries scanned and time taken
+    #[arg(long)]
+    stats: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Output {
+    /// One path per line, or per NUL with `-0`
+    Plain,
+    /// A single JSON array
+    Json,
+    /// One JSON object per line, written as the results are found
+    Ndjson,
+    /// CSV with a header row
+    Csv,
+}
+
+fn parse_format(template: &str) -> Result<ResultFormat, String> {
+    ResultFormat::parse(template).map_err(|e| e.to_string())
+}
+
+fn parse_exec(template: &str) -> Result<Exec, String> {
+    Exec::new(template).map_err(|e| e.to_string())
+}
+
+fn parse_column(name: &str) -> Result<Column, String> {
+    Column::parse(name).ok_or_else(|| {
+        format!("unknown column `{name}`, expected path, root, size, mtime, kind, depth or score")
+    })
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Style {
+    AsGiven,
+    Relative,
+    Absolute,
+    Canonical,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ScorerKind {
+    JaroWinkler,
+    Levenshtein,
+    DamerauLevenshtein,
+    SorensenDice,
+    Acronym,
+    Fzf,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let mut builder = match builder(&args) {
+        Ok(builder) => builder,
+        Err(message) => {
+            eprintln!("rsearch: {message}");
+            return ExitCode::from(2);
+        }
+    };
+    let latest = Arc::new(Mutex::new(None));
+    if args.progress || args.stats {
+        builder = progress(&args, builder, Arc::clone(&latest));
+    }
+
+    let code = if let Some(exec) = exec(&args) {
+        run(&args, &builder, &exec)
+    } else {
+        match print(&args, &builder) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("rsearch: {e}");
+                ExitCode::FAILURE
+            }
+        }
+    };
+    if args.stats {
+        if let Some(stats) = *latest.lock().unwrap() {
+            eprintln!("rsearch: {stats}");
+        }
+    }
+    code
+}
+
+/// Show a spinner on stderr while searching, if it is a terminal, and keep the latest
+/// stats for `--stats`.
+fn progress(
+    args: &Args,
+    builder: SearchBuilder,
+    latest: Arc<Mutex<Option<SearchStats>>>,
+) -> SearchBuilder {
+    const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+    let spinner = args.progress && io::stderr().is_terminal();
+    let ticks = AtomicUsize::new(0);
+    let builder = if args.stats {
+        builder.detailed_stats()
+    } else {
+        builder
+    };
+    builder.progress(Duration::from_millis(100), move |stats| {
+        *latest.lock().unwrap() = Some(*stats);
+        if !spinner {
+            return;
+        }
+        if stats.is_finished() {
+            eprint!("\r\x1b[K");
+        } else {
+            let frame = FRAMES[ticks.fetch_add(1, Ordering::Relaxed) % FRAMES.len()];
+            eprint!("\r\x1b[K{frame} {} files scanned", stats.entries());
+        }
+    })
+}
+
+/// The search described by the options, without the output settings.
+fn spec(args: &Args) -> SearchSpec {
+    SearchSpec {
+        locations: args.location.clone(),
+        input: args.pattern.clone(),
+        extensions: args.ext.clone(),
+        depth: args.depth,
+        limit: args.limit,
+        strict: args.strict,
+        ignore_case: args.ignore_case,
+        hidden: args.hidden,
+        size: args.size.clone(),
+        modified: args.modified.clone(),
+        created: args.created.clone(),
+        query: args.query.clone(),
+        contains: args.contains.clone(),
+        contains_regex: args.contains_regex.clone(),
+        include_binary: args.include_binary,
+        max_file_size: args.max_file_size.clone(),
+    }
+}
+
+fn builder(args: &Args) -> Result<SearchBuilder, String> {
+    let mut builder = SearchBuilder::default();
+    if let Some(path) = &args.spec {
+        let spec = SearchSpec::load(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        builder = spec.apply(builder).map_err(|e| e.to_string())?;
+    }
+    if args.saved.is_some() || args.save.is_some() {
+        let path = SavedSearches::default_path().ok_or("no config directory for saved searches")?;
+        let mut saved =
+            SavedSearches::load(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        if let Some(name) = &args.saved {
+            let spec = saved
+                .get(name)
+                .ok_or_else(|| format!("no saved search `{name}` in {}", path.display()))?;
+            builder = spec.clone().apply(builder).map_err(|e| e.to_string())?;
+        }
+        if let Some(name) = &args.save {
+            saved.insert(name, spec(args));
+            saved
+                .save(&path)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+            eprintln!("rsearch: saved search `{name}` to {}", path.display());
+        }
+    }
+    builder = spec(args).apply(builder).map_err(|e| e.to_string())?;
+    if args.lines {
+        builder = builder.line_matches();
+    }
+    #[cfg(feature = "compressed")]
+    if args.decompress {
+        builder = builder.decompress();
+    }
+    #[cfg(feature = "archives")]
+    if let Some(depth) = args.archives {
+        builder = builder.archives(depth);
+    }
+
+    let style = match (&args.relative_to, args.paths) {
+        (Some(base), _) => PathStyle::RelativeTo(base.clone()),
+        (None, Style::AsGiven) => PathStyle::AsGiven,
+        (None, Style::Relative) => PathStyle::Relative,
+        (None, Style::Absolute) => PathStyle::Absolute,
+        (None, Style::Canonical) => PathStyle::Canonical,
+    };
+    Ok(builder.output_paths(style))
+}
+
+fn exec(args: &Args) -> Option<Exec> {
+    let mut exec = match (&args.exec, &args.exec_batch) {
+        (Some(exec), _) => exec.clone(),
+        (None, Some(exec)) => exec.clone().batch(),
+        (None, None) => return None,
+    };
+    if let Some(jobs) = args.jobs {
+        exec = exec.jobs(jobs);
+    }
+    if args.stop_on_failure {
+        exec = exec.stop_on_failure();
+    }
+    Some(exec)
+}
+
+/// Run the command for the results, passing its output through in the order of the results.
+fn run(args: &Args, builder: &SearchBuilder, exec: &Exec) -> ExitCode {
+    let report = exec.run(results(args, builder));
+    let (mut stdout, mut stderr) = (io::stdout().lock(), io::stderr().lock());
+    for command in report.commands() {
+        let _ = stdout.write_all(command.stdout());
+        let _ = stderr.write_all(command.stderr());
+        if let Err(e) = command.status() {
+            let path = command.paths()[0].display();
+            let _ = writeln!(stderr, "rsearch: can't run the command for {path}: {e}");
+        }
+    }
+    if report.success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn print(args: &Args, builder: &SearchBuilder) -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    if let Some(format) = &args.format {
+        for result in results(args, builder) {
+            out.write_all(format.render(&result).as_bytes())?;
+            out.write_all(&[separator(args)])?;
+        }
+        return out.flush();
+    }
+    match args.output {
+        Output::Plain if args.lines => {
+            for result in results(args, builder) {
+                for line in result.lines() {
+                    out.write_all(result.path().as_os_str().as_encoded_bytes())?;
+                    write!(out, ":{}:{}", line.line_number(), line.line())?;
+                    out.write_all(&[separator(args)])?;
+                }
+            }
+            out.flush()
+        }
+        Output::Plain if args.sort_similarity => print_paths(
+            out,
+            results(args, builder).map(SearchResult::into_path),
+            separator(args),
+        ),
+        Output::Plain => print_paths(out, builder.build_paths(), separator(args)),
+        Output::Json => write_json(out, results(args, builder)),
+        Output::Ndjson => write_ndjson(out, results(args, builder)),
+        Output::Csv => {
+            let columns = args.columns.as_deref().unwrap_or(&Column::DEFAULT);
+            write_csv(out, results(args, builder), columns)
+        }
+    }
+}
+
+const fn separator(args: &Args) -> u8 {
+    if args.print0 {
+        b'\0'
+    } else {
+        b'\n'
+    }
+}
+
+fn print_paths(
+    mut out: impl Write,
+    paths: impl Iterator<Item = PathBuf>,
+    separator: u8,
+) -> io::Result<()> {
+    for path in paths {
+        // Write the raw bytes so paths that are not valid UTF-8 survive the pipe.
+        out.write_all(path.as_os_str().as_encoded_bytes())?;
+        out.write_all(&[separator])?;
+    }
+    out.flush()
+}
+
+/// The results as they are found, or all of them sorted by similarity with the pattern.
+fn results(args: &Args, builder: &SearchBuilder) -> Box<dyn Iterator<Item = SearchResult>> {
+    if !args.sort_similarity {
+        return Box::new(builder.build_results());
+    }
+    let mut results: Vec<SearchResult> = builder.build_results().collect();
+    let input = args.pattern.as_deref().unwrap_or_default();
+    let target = ScoreTarget::FileName;
+    match args.scorer {
+        ScorerKind::JaroWinkler => {
+            similarity_sort_results(&mut results, input, &JaroWinkler, target)
+        }
+        ScorerKind::Levenshtein => {
+            similarity_sort_results(&mut results, input, &Levenshtein, target)
+        }
+        ScorerKind::DamerauLevenshtein => {
+            similarity_sort_results(&mut results, input, &DamerauLevenshtein, target);
+        }
+        ScorerKind::SorensenDice => {
+            similarity_sort_results(&mut results, input, &SorensenDice, target);
+        }
+        ScorerKind::Acronym => similarity_sort_results(&mut results, input, &Acronym, target),
+        ScorerKind::Fzf => similarity_sort_results(&mut results, input, &FzfV2, target),
+    }
+    Box::new(results.into_iter())
+}
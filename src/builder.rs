@@ -1,48 +1,191 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-use crate::filter::FilterType;
-use crate::{utils::replace_tilde_with_home_dir, Search};
+use crate::content::ContentPattern;
+use crate::filter::{FileSize, FilterType};
+use crate::query::Expr;
+use crate::stats::Progress;
+use crate::{
+    utils::replace_tilde_with_home_dir, DiskUsage, FileSystem, PathStyle, Search, SearchResult,
+    SearchStats, Watch,
+};
 
 /// Builder for a [`Search`] instance, allowing for more complex searches.
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct SearchBuilder {
     /// The location to search in, defaults to the current directory.
-    search_location: PathBuf,
+    pub(crate) search_location: PathBuf,
     /// Additional locations to search in.
-    more_locations: Option<Vec<PathBuf>>,
+    pub(crate) more_locations: Option<Vec<PathBuf>>,
     /// The search input, default will get all files from locations.
-    search_input: Option<String>,
+    pub(crate) search_input: Option<String>,
     /// The file extension to search for, defaults to get all extensions.
-    file_ext: Option<String>,
+    pub(crate) file_ext: Option<String>,
     /// The depth to search to, defaults to no limit.
-    depth: Option<usize>,
+    pub(crate) depth: Option<usize>,
     /// The limit of results to return, defaults to no limit.
-    limit: Option<usize>,
+    pub(crate) limit: Option<usize>,
     /// When set to true, Searches for exact match, defaults to false.
-    strict: bool,
+    pub(crate) strict: bool,
     /// Set search option to be case insensitive, defaults to false.
-    ignore_case: bool,
+    pub(crate) ignore_case: bool,
     /// Search for hidden files, defaults to false.
-    hidden: bool,
+    pub(crate) hidden: bool,
     /// Filters Vector, defaults to empty vec
-    filters: Vec<FilterType>,
+    pub(crate) filters: Vec<FilterType>,
+    /// How the found paths are written, defaults to [`PathStyle::AsGiven`].
+    pub(crate) path_style: PathStyle,
+    /// Pattern the contents of the files must match, defaults to no content search.
+    pub(crate) content: Option<ContentPattern>,
+    /// Search the contents of binary files too, defaults to false.
+    pub(crate) include_binary: bool,
+    /// Skip the contents of files larger than this many bytes, defaults to no limit.
+    pub(crate) max_file_size: Option<u64>,
+    /// Collect the matching lines of every file, defaults to false.
+    pub(crate) line_matches: bool,
+    /// Terms of a [`Query`](crate::Query) that every match must satisfy, defaults to none.
+    pub(crate) query: Option<Expr>,
+    /// Decompress gzip, xz, zstd and bzip2 files when searching contents, defaults to false.
+    #[cfg(feature = "compressed")]
+    pub(crate) decompress: bool,
+    /// How many levels of nested archives to search inside, defaults to 0 for none.
+    #[cfg(feature = "archives")]
+    pub(crate) archive_depth: usize,
+    /// The files to walk instead of the disk, defaults to none for the disk.
+    pub(crate) file_system: Option<Arc<dyn FileSystem>>,
+    /// Count the bytes matched and the entries ignored too, defaults to false.
+    pub(crate) detailed_stats: bool,
+    /// Callback given the stats of the search as it runs, defaults to none.
+    pub(crate) progress: Option<Progress>,
 }
 
 impl SearchBuilder {
     /// Build a new [`Search`] instance.
-    #[allow(deprecated)]
+    ///
+    /// Paths that are not valid UTF-8 are converted lossily, use [`build_paths`](Self::build_paths)
+    /// to get paths that can always be opened.
     pub fn build(&self) -> Search {
-        Search::new(
-            &self.search_location,
-            self.more_locations.clone(),
-            self.search_input.as_deref(),
-            self.file_ext.as_deref(),
-            self.depth,
-            self.limit,
-            self.strict,
-            self.ignore_case,
-            self.hidden,
-            self.filters.clone(),
-        )
+        Search::from_paths(Search::from_results(Search::new(self, false, false)))
+    }
+
+    /// Build a new [`Search`] instance that yields [`PathBuf`]s.
+    ///
+    /// File names are matched on their raw bytes, so paths that are not valid UTF-8
+    /// are found and returned unchanged.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    /// use std::path::PathBuf;
+    ///
+    /// let search: Vec<PathBuf> = SearchBuilder::default()
+    ///     .location("src")
+    ///     .search_input("search")
+    ///     .build_paths()
+    ///     .collect();
+    /// ```
+    pub fn build_paths(&self) -> Search<PathBuf> {
+        Search::from_results(Search::new(self, true, false))
+    }
+
+    /// Build a new [`Search`] instance that yields [`SearchResult`]s.
+    ///
+    /// Each result records the location it was found in, its kind, depth, size and
+    /// modification time. Like [`build_paths`](Self::build_paths), file names are matched
+    /// on their raw bytes.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::{PathStyle, SearchBuilder};
+    ///
+    /// for result in SearchBuilder::default()
+    ///     .location("src")
+    ///     .more_locations(vec!["tests"])
+    ///     .ext("rs")
+    ///     .output_paths(PathStyle::Relative)
+    ///     .build_results()
+    /// {
+    ///     println!("{} in {}", result.path().display(), result.root().display());
+    /// }
+    /// ```
+    pub fn build_results(&self) -> Search<SearchResult> {
+        Search::new(self, true, true)
+    }
+
+    /// Add up the size and number of the matching files per directory, extension and
+    /// owner instead of returning them, like `du --max-depth`.
+    ///
+    /// Only regular files are counted. The hidden, ignore and filter settings apply as
+    /// for [`build`](Self::build).
+    /// ### Arguments
+    /// * `max_depth` - How many directories below the locations to report on, deeper
+    ///   directories are counted in their parent at that depth.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    ///
+    /// let usage = SearchBuilder::default().location("src").disk_usage(1);
+    /// for dir in usage.dirs() {
+    ///     println!("{}\t{} files\t{}", dir.size(), dir.files(), dir.path().display());
+    /// }
+    /// assert!(usage.by_extension()["rs"].files() > 0);
+    /// ```
+    pub fn disk_usage(&self, max_depth: usize) -> DiskUsage {
+        DiskUsage::new(self, max_depth)
+    }
+
+    /// Search once, then keep watching the locations for paths that start or stop
+    /// matching, or are modified.
+    ///
+    /// Uses inotify on Linux and falls back to searching again every
+    /// [`DEFAULT_POLL_INTERVAL`](crate::DEFAULT_POLL_INTERVAL) where it is not available,
+    /// e.g. on other systems or when the limit on inotify watches is reached.
+    /// ### Examples
+    /// ```rust,no_run
+    /// use rust_search::{SearchBuilder, WatchEvent};
+    ///
+    /// let watch = SearchBuilder::default().location("/var/log").ext("log").watch();
+    /// for path in watch.initial() {
+    ///     println!("{}", path.display());
+    /// }
+    /// for event in watch {
+    ///     match event {
+    ///         WatchEvent::Added(path) => println!("+ {}", path.display()),
+    ///         WatchEvent::Removed(path) => println!("- {}", path.display()),
+    ///         WatchEvent::Modified(path) => println!("~ {}", path.display()),
+    ///     }
+    /// }
+    /// ```
+    pub fn watch(&self) -> Watch {
+        Watch::new(self, None)
+    }
+
+    /// Like [`watch`](Self::watch), but always searching again every `interval`
+    /// instead of using inotify, e.g. on network file systems where inotify doesn't
+    /// see changes made by other machines.
+    /// ### Arguments
+    /// * `interval` - How long to wait between searches.
+    pub fn watch_polling(&self, interval: Duration) -> Watch {
+        Watch::new(self, Some(interval))
+    }
+
+    /// Require every match to also satisfy `expr`, on top of earlier expressions.
+    pub(crate) fn match_expr(mut self, expr: Expr) -> Self {
+        self.query = Some(match self.query.take() {
+            Some(Expr::And(mut exprs)) => {
+                exprs.push(expr);
+                Expr::And(exprs)
+            }
+            Some(previous) => Expr::And(vec![previous, expr]),
+            None => expr,
+        });
+        self
+    }
+
+    /// All locations of the search, the main location first.
+    pub(crate) fn locations(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self.search_location.as_path())
+            .chain(self.more_locations.iter().flatten().map(PathBuf::as_path))
     }
 
     /// Set the search location to search in.
@@ -213,6 +356,270 @@ impl SearchBuilder {
         self
     }
 
+    /// Set how the found paths are written, see [`PathStyle`].
+    /// ### Arguments
+    /// * `style` - The style of the returned paths.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::{PathStyle, SearchBuilder};
+    ///
+    /// let search: Vec<String> = SearchBuilder::default()
+    ///     .location("src")
+    ///     .output_paths(PathStyle::Canonical)
+    ///     .build()
+    ///     .collect();
+    /// ```
+    pub fn output_paths(mut self, style: PathStyle) -> Self {
+        self.path_style = style;
+        self
+    }
+
+    /// Only keep files whose contents contain the given text.
+    ///
+    /// The contents are read line by line after the name and extension matched,
+    /// binary files are skipped unless [`include_binary`](Self::include_binary) is set.
+    /// ### Arguments
+    /// * `text` - The literal text to look for.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    ///
+    /// let search: Vec<String> = SearchBuilder::default()
+    ///     .location("src")
+    ///     .ext("rs")
+    ///     .contains("SearchBuilder")
+    ///     .build()
+    ///     .collect();
+    /// ```
+    pub fn contains(mut self, text: impl Into<String>) -> Self {
+        self.content = Some(ContentPattern::literal(&text.into()));
+        self
+    }
+
+    /// Only keep files with a line matching the given regular expression.
+    ///
+    /// Works like [`contains`](Self::contains), use `(?i)` to match regardless of case.
+    /// ### Arguments
+    /// * `pattern` - The regular expression to look for.
+    /// ### Errors
+    /// Returns an error if `pattern` is not a valid regular expression.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    ///
+    /// let search: Vec<String> = SearchBuilder::default()
+    ///     .location("src")
+    ///     .ext("rs")
+    ///     .contains_regex(r"(?i)fn\s+build")
+    ///     .unwrap()
+    ///     .build()
+    ///     .collect();
+    /// ```
+    pub fn contains_regex(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.content = Some(ContentPattern::regex(pattern)?);
+        Ok(self)
+    }
+
+    /// Search the contents of binary files too, by default files with a NUL byte
+    /// near the start are skipped by [`contains`](Self::contains).
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    ///
+    /// let search: Vec<String> = SearchBuilder::default()
+    ///     .location("/usr/bin")
+    ///     .depth(1)
+    ///     .contains("GLIBC")
+    ///     .include_binary()
+    ///     .build()
+    ///     .collect();
+    /// ```
+    pub const fn include_binary(mut self) -> Self {
+        self.include_binary = true;
+        self
+    }
+
+    /// Skip files larger than `size` when searching contents with [`contains`](Self::contains).
+    /// ### Arguments
+    /// * `size` - The largest file size to read.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::{FileSize, SearchBuilder};
+    ///
+    /// let search: Vec<String> = SearchBuilder::default()
+    ///     .location("src")
+    ///     .contains("TODO")
+    ///     .max_file_size(FileSize::Megabyte(1.0))
+    ///     .build()
+    ///     .collect();
+    /// ```
+    pub fn max_file_size(mut self, size: FileSize) -> Self {
+        self.max_file_size = Some(size.into());
+        self
+    }
+
+    /// Collect the line numbers and lines matching [`contains`](Self::contains),
+    /// available through [`SearchResult::lines`].
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    ///
+    /// for result in SearchBuilder::default()
+    ///     .location("src")
+    ///     .contains("pub fn")
+    ///     .line_matches()
+    ///     .build_results()
+    /// {
+    ///     for line in result.lines() {
+    ///         println!("{}:{}: {}", result.path().display(), line.line_number(), line.line());
+    ///     }
+    /// }
+    /// ```
+    pub const fn line_matches(mut self) -> Self {
+        self.line_matches = true;
+        self
+    }
+
+    /// Decompress gzip, xz, zstd and bzip2 files on the fly when searching their contents
+    /// with [`contains`](Self::contains), like `zgrep`.
+    ///
+    /// Compressed files are told by their extension, e.g. `.gz`, `.xz`, `.zst` or `.bz2`,
+    /// or else by the magic bytes they start with. [`max_file_size`](Self::max_file_size)
+    /// applies to the compressed size, and the binary check to the decompressed contents.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    ///
+    /// let search: Vec<String> = SearchBuilder::default()
+    ///     .location("/var/log")
+    ///     .search_input("^syslog")
+    ///     .contains("segfault")
+    ///     .decompress()
+    ///     .build()
+    ///     .collect();
+    /// ```
+    #[cfg(feature = "compressed")]
+    pub const fn decompress(mut self) -> Self {
+        self.decompress = true;
+        self
+    }
+
+    /// Search inside the zip and tar archives found, optionally compressed with gzip, xz,
+    /// zstd or bzip2, matching their members like files on the disk.
+    ///
+    /// Members are found as virtual paths such as `backup.tar.gz!/etc/hosts`, with the
+    /// size and modification time recorded in the archive. The search input, extension,
+    /// hidden setting, size and time filters, query and content pattern apply to them,
+    /// but not the depth limit or custom filters. Archives are told by their names, e.g.
+    /// `.zip`, `.tar`, `.tar.gz`, `.tgz`, `.tar.xz`, `.tar.zst` and `.tar.bz2`, and are
+    /// still found themselves when they match.
+    /// ### Arguments
+    /// * `max_depth` - How many levels of archives to open: 1 for the archives on the
+    ///   disk, 2 for the archives inside those too, and so on. 0 turns it off.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    ///
+    /// let search: Vec<String> = SearchBuilder::default()
+    ///     .location("~/backups")
+    ///     .search_input("^hosts$")
+    ///     .archives(2)
+    ///     .build()
+    ///     .collect();
+    /// ```
+    #[cfg(feature = "archives")]
+    pub const fn archives(mut self, max_depth: usize) -> Self {
+        self.archive_depth = max_depth;
+        self
+    }
+
+    /// Walk a [`FileSystem`] instead of the disk, e.g. a [`MemoryFs`](crate::MemoryFs) in
+    /// tests or a snapshot image.
+    ///
+    /// The locations are paths in that file system. The search input, extension, depth,
+    /// limit, hidden setting, `.ignore` and `.gitignore` files, size and time filters,
+    /// query and content pattern apply as on the disk, but not custom filters, which need
+    /// an entry on the disk, and archives are not searched inside. Entries are walked one
+    /// directory at a time in the order of their names. Only [`build`](Self::build),
+    /// [`build_paths`](Self::build_paths) and [`build_results`](Self::build_results) use it.
+    /// ### Arguments
+    /// * `fs` - The file system to walk.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::{MemoryFs, SearchBuilder};
+    ///
+    /// let fs = MemoryFs::default()
+    ///     .file("/etc/hosts", "127.0.0.1 localhost")
+    ///     .file("/etc/hostname", "box");
+    /// let search: Vec<String> = SearchBuilder::default()
+    ///     .location("/etc")
+    ///     .search_input("^hosts$")
+    ///     .file_system(fs)
+    ///     .build()
+    ///     .collect();
+    /// assert_eq!(search, ["/etc/hosts"]);
+    /// ```
+    pub fn file_system(mut self, fs: impl FileSystem + 'static) -> Self {
+        self.file_system = Some(Arc::new(fs));
+        self
+    }
+
+    /// Also count the bytes matched and the entries left out by ignore rules in the
+    /// [`SearchStats`].
+    ///
+    /// On the disk this reads the metadata of every file found, and lists every directory
+    /// a second time to estimate the ignored entries once the search is finished, see
+    /// [`SearchStats::ignored`].
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    ///
+    /// let mut search = SearchBuilder::default().location("src").detailed_stats().build();
+    /// search.by_ref().for_each(drop);
+    /// assert!(search.stats().bytes_matched() > 0);
+    /// ```
+    pub const fn detailed_stats(mut self) -> Self {
+        self.detailed_stats = true;
+        self
+    }
+
+    /// Call `callback` with the [`SearchStats`] of the search every `interval` while it
+    /// runs, and once more with the final stats when it is done, e.g. to show a spinner.
+    ///
+    /// The callback is called on a thread of its own, the final call comes before the
+    /// search yields its last result.
+    /// ### Arguments
+    /// * `interval` - How long to wait between calls.
+    /// * `callback` - Given the stats so far.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let search: Vec<String> = SearchBuilder::default()
+    ///     .location("src")
+    ///     .progress(Duration::from_millis(100), |stats| {
+    ///         if stats.is_finished() {
+    ///             eprintln!("{stats}");
+    ///         } else {
+    ///             eprint!("\r{} files scanned", stats.entries());
+    ///         }
+    ///     })
+    ///     .build()
+    ///     .collect();
+    /// ```
+    pub fn progress(
+        mut self,
+        interval: Duration,
+        callback: impl Fn(&SearchStats) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Progress {
+            interval,
+            callback: Arc::new(callback),
+        });
+        self
+    }
+
     /// Add extra locations to search in, in addition to the main location.
     /// ## Notes
     /// - Will replace `~` with [home directory](https://en.wikipedia.org/wiki/Home_directory)
@@ -252,6 +659,19 @@ impl Default for SearchBuilder {
             ignore_case: false,
             hidden: false,
             filters: vec![],
+            path_style: PathStyle::AsGiven,
+            content: None,
+            include_binary: false,
+            max_file_size: None,
+            line_matches: false,
+            query: None,
+            #[cfg(feature = "compressed")]
+            decompress: false,
+            #[cfg(feature = "archives")]
+            archive_depth: 0,
+            file_system: None,
+            detailed_stats: false,
+            progress: None,
         }
     }
 }
@@ -0,0 +1,432 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fs::{self, File},
+    io::{self, ErrorKind, Read},
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    utils::{path_from_bytes, path_to_bytes, sha256_reader},
+    FileKind, SearchResult,
+};
+
+/// A hash function for [`hash_results`] and [`Manifest`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    /// BLAKE3 with 256-bit output, as written by `b3sum`
+    Blake3,
+    /// SHA-256, as written by `sha256sum`
+    Sha256,
+    /// The 64-bit XXH3 of xxHash, fast but not meant to resist tampering
+    Xxh3,
+}
+
+impl HashAlgorithm {
+    /// How many hex digits a hash has.
+    const fn hex_len(self) -> usize {
+        match self {
+            Self::Blake3 | Self::Sha256 => 64,
+            Self::Xxh3 => 16,
+        }
+    }
+
+    /// Hash the contents of a file
+    /// ### Arguments
+    /// * `path` - The file to hash.
+    /// ### Errors
+    /// Returns an error if the file can't be opened or read.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::HashAlgorithm;
+    ///
+    /// let hash = HashAlgorithm::Sha256.hash_file("Cargo.toml").unwrap();
+    /// assert_eq!(hash.len(), 64);
+    /// ```
+    pub fn hash_file(self, path: impl AsRef<Path>) -> io::Result<String> {
+        self.hash_reader(File::open(path)?)
+    }
+
+    /// Hash everything a reader yields, as lowercase hex
+    /// ### Arguments
+    /// * `reader` - The bytes to hash.
+    /// ### Errors
+    /// Returns an error if reading fails.
+    pub fn hash_reader(self, mut reader: impl Read) -> io::Result<String> {
+        match self {
+            Self::Sha256 => sha256_reader(reader),
+            Self::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                io::copy(&mut reader, &mut hasher)?;
+                Ok(hasher.finalize().to_hex().to_string())
+            }
+            Self::Xxh3 => {
+                let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+                let mut buffer = vec![0; 64 * 1024];
+                loop {
+                    match reader.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(n) => hasher.update(&buffer[..n]),
+                        Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(format!("{:016x}", hasher.digest()))
+            }
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Blake3 => "BLAKE3",
+            Self::Sha256 => "SHA-256",
+            Self::Xxh3 => "XXH3",
+        })
+    }
+}
+
+/// Hash every regular file among the results in parallel, see [`SearchResult::hash`]
+///
+/// Directories, symlinks and files that can't be read are left without a hash.
+/// ### Arguments
+/// * `results` - The results to hash.
+/// * `algorithm` - The hash function to use.
+/// ### Examples
+/// ```rust
+/// use rust_search::{hash_results, HashAlgorithm, SearchBuilder};
+///
+/// let mut results: Vec<_> = SearchBuilder::default()
+///     .location("src")
+///     .ext("rs")
+///     .build_results()
+///     .collect();
+/// hash_results(&mut results, HashAlgorithm::Blake3);
+/// for result in &results {
+///     println!("{}  {}", result.hash().unwrap(), result.path().display());
+/// }
+/// ```
+pub fn hash_results(results: &mut [SearchResult], algorithm: HashAlgorithm) {
+    results.par_iter_mut().for_each(|result| {
+        if result.kind() == FileKind::File {
+            let hash = algorithm.hash_file(result.disk_path()).ok();
+            result.set_hash(hash);
+        }
+    });
+}
+
+/// The path of a result as written in a manifest, relative to its location.
+fn manifest_path(result: &SearchResult) -> PathBuf {
+    let path = result.disk_path();
+    match path.strip_prefix(result.root()) {
+        Ok(relative) if relative.as_os_str().is_empty() => path
+            .file_name()
+            .map_or_else(|| path.to_path_buf(), PathBuf::from),
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// The hashes of a set of files, read and written in the format of `sha256sum`
+///
+/// Every line holds a hash in lowercase hex, two spaces and a path relative to the
+/// location the file was found in, so `sha256sum -c` (or `b3sum -c` for BLAKE3) can
+/// check the manifest from that location. Names holding a backslash or a newline are
+/// escaped the way `sha256sum` does.
+/// ### Examples
+/// ```rust,no_run
+/// use rust_search::{HashAlgorithm, Manifest, SearchBuilder};
+///
+/// let artifacts = SearchBuilder::default().location("dist").ext("tar.gz");
+/// Manifest::create(artifacts.build_results(), HashAlgorithm::Sha256)
+///     .save("dist/SHA256SUMS")
+///     .unwrap();
+///
+/// // Later, under the same search
+/// let manifest = Manifest::load("dist/SHA256SUMS", HashAlgorithm::Sha256).unwrap();
+/// let verification = manifest.verify(artifacts.build_results());
+/// for path in verification.changed() {
+///     eprintln!("changed: {}", path.display());
+/// }
+/// assert!(verification.is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    algorithm: HashAlgorithm,
+    entries: BTreeMap<PathBuf, String>,
+}
+
+impl Manifest {
+    /// Hash the regular files among the results in parallel
+    /// ### Arguments
+    /// * `results` - The results to hash, usually from
+    ///   [`SearchBuilder::build_results`](crate::SearchBuilder::build_results).
+    /// * `algorithm` - The hash function to use.
+    pub fn create(
+        results: impl IntoIterator<Item = SearchResult>,
+        algorithm: HashAlgorithm,
+    ) -> Self {
+        let entries = hashed(results, algorithm)
+            .into_iter()
+            .filter_map(|(path, hash)| Some((path, hash.ok()?)))
+            .collect();
+        Self { algorithm, entries }
+    }
+
+    /// The hash function of the manifest
+    pub const fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// How many files the manifest holds
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the manifest holds no files
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The hash of a file, given relative to its location
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&str> {
+        self.entries.get(path.as_ref()).map(String::as_str)
+    }
+
+    /// Every file with its hash, in order of the paths
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &str)> {
+        self.entries
+            .iter()
+            .map(|(path, hash)| (path.as_path(), hash.as_str()))
+    }
+
+    /// Hash the files found again and compare them with the manifest
+    /// ### Arguments
+    /// * `results` - The results of the same search the manifest was created from.
+    pub fn verify(&self, results: impl IntoIterator<Item = SearchResult>) -> Verification {
+        let mut verification = Verification::default();
+        let mut found = BTreeSet::new();
+        for (path, hash) in hashed(results, self.algorithm) {
+            match (self.entries.get(&path), hash) {
+                (None, _) => verification.extra.push(path.clone()),
+                (Some(_), Err(error)) => verification.unreadable.push((path.clone(), error)),
+                (Some(expected), Ok(hash)) if *expected == hash => {
+                    verification.ok.push(path.clone());
+                }
+                (Some(_), Ok(_)) => verification.changed.push(path.clone()),
+            }
+            found.insert(path);
+        }
+        verification.missing = self
+            .entries
+            .keys()
+            .filter(|path| !found.contains(*path))
+            .cloned()
+            .collect();
+        verification
+    }
+
+    /// Read a manifest
+    /// ### Arguments
+    /// * `path` - The manifest file.
+    /// * `algorithm` - The hash function it was written with.
+    /// ### Errors
+    /// Returns an error if the file can't be read, or a line is not a hash of the right
+    /// length followed by a path.
+    pub fn load(path: impl AsRef<Path>, algorithm: HashAlgorithm) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let mut entries = BTreeMap::new();
+        for (i, line) in bytes.split(|&b| b == b'\n').enumerate() {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.is_empty() {
+                continue;
+            }
+            let (hash, path) = parse_line(line, algorithm).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid {algorithm} manifest on line {}", i + 1),
+                )
+            })?;
+            entries.insert(path, hash);
+        }
+        Ok(Self { algorithm, entries })
+    }
+
+    /// Write the manifest
+    /// ### Arguments
+    /// * `path` - The file to write, replaced if it exists.
+    /// ### Errors
+    /// Returns an error if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = Vec::new();
+        for (path, hash) in &self.entries {
+            let name = path_to_bytes(path);
+            let escape = name.iter().any(|&b| b == b'\\' || b == b'\n');
+            if escape {
+                out.push(b'\\');
+            }
+            out.extend_from_slice(hash.as_bytes());
+            out.extend_from_slice(b"  ");
+            for &b in &name {
+                match b {
+                    b'\\' if escape => out.extend_from_slice(b"\\\\"),
+                    b'\n' => out.extend_from_slice(b"\\n"),
+                    b => out.push(b),
+                }
+            }
+            out.push(b'\n');
+        }
+        fs::write(path, out)
+    }
+}
+
+/// Hash the regular files among the results in parallel, keyed by their manifest path.
+fn hashed(
+    results: impl IntoIterator<Item = SearchResult>,
+    algorithm: HashAlgorithm,
+) -> Vec<(PathBuf, io::Result<String>)> {
+    let files: Vec<SearchResult> = results
+        .into_iter()
+        .filter(|result| result.kind() == FileKind::File)
+        .collect();
+    files
+        .par_iter()
+        .map(|result| {
+            (
+                manifest_path(result),
+                algorithm.hash_file(result.disk_path()),
+            )
+        })
+        .collect()
+}
+
+/// Split a line of a manifest into the hash and the path.
+fn parse_line(line: &[u8], algorithm: HashAlgorithm) -> Option<(String, PathBuf)> {
+    let (escaped, line) = line
+        .strip_prefix(b"\\")
+        .map_or((false, line), |line| (true, line));
+    let len = algorithm.hex_len();
+    let hash = line.get(..len)?;
+    if !hash.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    // Two spaces in text mode, a space and `*` in binary mode.
+    let name = match line.get(len..len + 2)? {
+        b"  " | b" *" => &line[len + 2..],
+        _ => return None,
+    };
+    if name.is_empty() {
+        return None;
+    }
+    let name = if escaped {
+        let mut out = Vec::with_capacity(name.len());
+        let mut i = 0;
+        while i < name.len() {
+            match (name[i], name.get(i + 1)) {
+                (b'\\', Some(b'\\')) => out.push(b'\\'),
+                (b'\\', Some(b'n')) => out.push(b'\n'),
+                (b'\\', _) => return None,
+                (b, _) => {
+                    out.push(b);
+                    i += 1;
+                    continue;
+                }
+            }
+            i += 2;
+        }
+        out
+    } else {
+        name.to_vec()
+    };
+    let hash = String::from_utf8(hash.to_ascii_lowercase()).ok()?;
+    Some((hash, path_from_bytes(name)))
+}
+
+/// The outcome of [`Manifest::verify`]
+#[derive(Debug, Default)]
+pub struct Verification {
+    ok: Vec<PathBuf>,
+    changed: Vec<PathBuf>,
+    missing: Vec<PathBuf>,
+    extra: Vec<PathBuf>,
+    unreadable: Vec<(PathBuf, io::Error)>,
+}
+
+impl Verification {
+    /// Whether every file in the manifest was found unchanged and no other file was found
+    pub const fn is_ok(&self) -> bool {
+        self.changed.is_empty()
+            && self.missing.is_empty()
+            && self.extra.is_empty()
+            && self.unreadable.is_empty()
+    }
+
+    /// The files whose hash matches the manifest
+    pub fn ok(&self) -> &[PathBuf] {
+        &self.ok
+    }
+
+    /// The files whose hash differs from the manifest
+    pub fn changed(&self) -> &[PathBuf] {
+        &self.changed
+    }
+
+    /// The files in the manifest that were not found
+    pub fn missing(&self) -> &[PathBuf] {
+        &self.missing
+    }
+
+    /// The files found that are not in the manifest
+    pub fn extra(&self) -> &[PathBuf] {
+        &self.extra
+    }
+
+    /// The files in the manifest that were found but couldn't be read
+    pub fn unreadable(&self) -> &[(PathBuf, io::Error)] {
+        &self.unreadable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_match_known_values() {
+        let hash = |algorithm: HashAlgorithm| algorithm.hash_reader(&b"abc"[..]).unwrap();
+        assert_eq!(
+            hash(HashAlgorithm::Sha256),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hash(HashAlgorithm::Blake3),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        assert_eq!(hash(HashAlgorithm::Xxh3), "78af5f94892f3950");
+    }
+
+    #[test]
+    fn manifest_lines_parse_like_sha256sum() {
+        let hash = "0123456789abcdef";
+        let parse = |line: &str| parse_line(line.as_bytes(), HashAlgorithm::Xxh3);
+        assert_eq!(
+            parse(&format!("{hash}  a b.txt")),
+            Some((hash.to_owned(), PathBuf::from("a b.txt")))
+        );
+        assert_eq!(
+            parse(&format!("{} *bin", hash.to_uppercase())),
+            Some((hash.to_owned(), PathBuf::from("bin")))
+        );
+        assert_eq!(
+            parse(&format!("\\{hash}  new\\nline\\\\x")),
+            Some((hash.to_owned(), PathBuf::from("new\nline\\x")))
+        );
+        assert_eq!(parse(&format!("{hash} a")), None);
+        assert_eq!(parse(&format!("{hash}  ")), None);
+        assert_eq!(parse("0123456789abcdeg  a"), None);
+        assert_eq!(parse(&format!("\\{hash}  a\\b")), None);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use ignore::DirEntry;
+use regex::bytes::Regex;
+
+#[cfg(feature = "compressed")]
+use crate::decompress::Codec;
+use crate::SearchBuilder;
+
+/// How many bytes at the start of a file are checked for a NUL byte, the same heuristic
+/// `grep` uses to tell binary files apart.
+const BINARY_CHECK_LEN: usize = 8 * 1024;
+
+/// Whether `contents`, the start of a file or all of it, looks like binary data.
+pub fn is_binary(contents: &[u8]) -> bool {
+    contents[..contents.len().min(BINARY_CHECK_LEN)].contains(&0)
+}
+
+/// The content pattern given to the builder, compiled as soon as it is given.
+#[derive(Debug, Clone)]
+pub struct ContentPattern(Regex);
+
+impl ContentPattern {
+    /// A pattern that matches `text` literally.
+    pub fn literal(text: &str) -> Self {
+        Self(Regex::new(&regex::escape(text)).expect("an escaped literal is a valid pattern"))
+    }
+
+    /// A pattern that matches the regular expression `pattern`.
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Self)
+    }
+}
+
+/// A line of a file that matched the content pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LineMatch {
+    line_number: u64,
+    line: String,
+}
+
+impl LineMatch {
+    /// The number of the line, starting at 1
+    pub const fn line_number(&self) -> u64 {
+        self.line_number
+    }
+
+    /// The content of the line without the line ending, lossily converted to UTF-8
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+}
+
+/// Checks the contents of the files matched by name.
+pub struct ContentMatcher {
+    regex: Regex,
+    skip_binary: bool,
+    max_file_size: Option<u64>,
+    collect_lines: bool,
+    #[cfg(feature = "compressed")]
+    decompress: bool,
+}
+
+impl ContentMatcher {
+    /// Match `pattern` with the binary, size, line and decompression settings of `options`.
+    pub fn new(pattern: &ContentPattern, options: &SearchBuilder) -> Self {
+        Self {
+            regex: pattern.0.clone(),
+            skip_binary: !options.include_binary,
+            max_file_size: options.max_file_size,
+            collect_lines: options.line_matches,
+            #[cfg(feature = "compressed")]
+            decompress: options.decompress,
+        }
+    }
+
+    /// Search the file of `entry` line by line.
+    ///
+    /// Returns `None` when nothing matched or the file was skipped, otherwise the matching
+    /// lines, which are only collected when asked for.
+    pub fn search(&self, entry: &DirEntry) -> Option<Vec<LineMatch>> {
+        if entry.file_type().is_none_or(|ft| ft.is_dir()) {
+            return None;
+        }
+        self.search_file(entry.path())
+    }
+
+    /// Search the file at `path` line by line, like [`search`](Self::search).
+    pub fn search_file(&self, path: &Path) -> Option<Vec<LineMatch>> {
+        let file = File::open(path).ok()?;
+        if let Some(max) = self.max_file_size {
+            if file.metadata().ok()?.len() > max {
+                return None;
+            }
+        }
+        self.search_contents(path, file)
+    }
+
+    /// Search the contents of a file of `size` bytes that is not on the disk, e.g. an
+    /// archive member, line by line like [`search`](Self::search).
+    pub fn search_read(
+        &self,
+        path: &Path,
+        reader: &mut dyn io::Read,
+        size: u64,
+    ) -> Option<Vec<LineMatch>> {
+        if self.max_file_size.is_some_and(|max| size > max) {
+            return None;
+        }
+        self.search_contents(path, reader)
+    }
+
+    /// Search the contents of the file at `path`, decoding them first if they are
+    /// compressed and decompression is on.
+    #[cfg(feature = "compressed")]
+    fn search_contents(&self, path: &Path, reader: impl io::Read) -> Option<Vec<LineMatch>> {
+        let mut reader = BufReader::with_capacity(BINARY_CHECK_LEN, reader);
+        if self.decompress {
+            if let Some(codec) = Codec::detect(path, &mut reader).ok()? {
+                let decoder = codec.decoder(reader).ok()?;
+                return self
+                    .search_reader(BufReader::with_capacity(BINARY_CHECK_LEN, decoder))
+                    .ok()
+                    .flatten();
+            }
+        }
+        self.search_reader(reader).ok().flatten()
+    }
+
+    #[cfg(not(feature = "compressed"))]
+    fn search_contents(&self, _: &Path, reader: impl io::Read) -> Option<Vec<LineMatch>> {
+        self.search_reader(BufReader::with_capacity(BINARY_CHECK_LEN, reader))
+            .ok()
+            .flatten()
+    }
+
+    fn search_reader(&self, mut reader: impl BufRead) -> io::Result<Option<Vec<LineMatch>>> {
+        if self.skip_binary && is_binary(reader.fill_buf()?) {
+            return Ok(None);
+        }
+
+        let mut lines = Vec::new();
+        let mut buf = Vec::new();
+        let mut line_number = 0;
+        loop {
+            buf.clear();
+            // A file that fails part-way, e.g. truncated compressed data, keeps the lines matched
+            // before the error.
+            if reader.read_until(b'\n', &mut buf).unwrap_or(0) == 0 {
+                break;
+            }
+            line_number += 1;
+            let line = buf
+                .strip_suffix(b"\n")
+                .map_or(&buf[..], |l| l.strip_suffix(b"\r").unwrap_or(l));
+            if self.regex.is_match(line) {
+                if !self.collect_lines {
+                    return Ok(Some(lines));
+                }
+                lines.push(LineMatch {
+                    line_number,
+                    line: String::from_utf8_lossy(line).into_owned(),
+                });
+            }
+        }
+        Ok((!lines.is_empty()).then_some(lines))
+    }
+}
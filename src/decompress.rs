@@ -0,0 +1,117 @@
+use std::{
+    io::{self, BufRead, Read},
+    path::Path,
+};
+
+/// A compression format files are decoded from on the fly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Xz,
+    Zstd,
+    Bzip2,
+}
+
+impl Codec {
+    /// The format of a file, told by its extension.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_string_lossy().to_ascii_lowercase();
+        match ext.as_str() {
+            "gz" | "tgz" => Some(Self::Gzip),
+            "xz" | "txz" => Some(Self::Xz),
+            "zst" | "zstd" | "tzst" => Some(Self::Zstd),
+            "bz2" | "tbz" | "tbz2" => Some(Self::Bzip2),
+            _ => None,
+        }
+    }
+
+    /// The format of data, told by the magic bytes it starts with.
+    pub fn from_magic(start: &[u8]) -> Option<Self> {
+        const MAGIC: [(&[u8], Codec); 4] = [
+            (b"\x1f\x8b", Codec::Gzip),
+            (b"\xfd7zXZ\x00", Codec::Xz),
+            (b"\x28\xb5\x2f\xfd", Codec::Zstd),
+            (b"BZh", Codec::Bzip2),
+        ];
+        MAGIC
+            .iter()
+            .find(|(magic, _)| start.starts_with(magic))
+            .map(|&(_, codec)| codec)
+            // `BZh` is followed by the block size, `1` to `9`, which text rarely is.
+            .filter(|&codec| codec != Self::Bzip2 || matches!(start.get(3), Some(b'1'..=b'9')))
+    }
+
+    /// The format of `reader`, told by the extension of `path` or else its first bytes.
+    /// ### Errors
+    /// Returns an error if the first bytes can't be read.
+    pub fn detect(path: &Path, reader: &mut impl BufRead) -> io::Result<Option<Self>> {
+        match Self::from_extension(path) {
+            Some(codec) => Ok(Some(codec)),
+            None => Ok(Self::from_magic(reader.fill_buf()?)),
+        }
+    }
+
+    /// Decode everything `reader` yields.
+    /// ### Errors
+    /// Returns an error if a zstd frame header can't be read, other formats report errors
+    /// while reading.
+    pub fn decoder<'a>(self, reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Self::Xz => Box::new(liblzma::read::XzDecoder::new_multi_decoder(reader)),
+            Self::Zstd => {
+                Box::new(ruzstd::decoding::StreamingDecoder::new(reader).map_err(io::Error::other)?)
+            }
+            Self::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn round_trip(codec: Codec, compressed: &[u8]) {
+        assert_eq!(Codec::from_magic(compressed), Some(codec));
+        let mut out = String::new();
+        codec
+            .decoder(compressed)
+            .unwrap()
+            .read_to_string(&mut out)
+            .unwrap();
+        assert_eq!(out, "hello\n");
+    }
+
+    #[test]
+    fn codecs_are_detected_and_decoded() {
+        let text = b"hello\n";
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gzip.write_all(text).unwrap();
+        round_trip(Codec::Gzip, &gzip.finish().unwrap());
+
+        let mut xz = liblzma::write::XzEncoder::new(Vec::new(), 1);
+        xz.write_all(text).unwrap();
+        round_trip(Codec::Xz, &xz.finish().unwrap());
+
+        let zstd = ruzstd::encoding::compress_to_vec(
+            &text[..],
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+        round_trip(Codec::Zstd, &zstd);
+
+        let mut bzip2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::fast());
+        bzip2.write_all(text).unwrap();
+        round_trip(Codec::Bzip2, &bzip2.finish().unwrap());
+
+        assert_eq!(
+            Codec::from_extension(Path::new("app.log.XZ")),
+            Some(Codec::Xz)
+        );
+        assert_eq!(Codec::from_extension(Path::new("app.log")), None);
+        assert_eq!(Codec::from_magic(b"plain text"), None);
+        assert_eq!(Codec::from_magic(b"BZh9"), Some(Codec::Bzip2));
+        assert_eq!(Codec::from_magic(b"BZh, a note"), None);
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, Metadata},
+    hash::Hash,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+
+use crate::utils::{sha256_file, sha256_reader};
+
+/// How much of the start and of the end of a file the partial hash reads.
+const PARTIAL_BLOCK: u64 = 4096;
+
+/// Files with the same contents, found by [`find_duplicates`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    size: u64,
+    files: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    /// The size of each file in bytes
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The copies in order, one path for each, other names of the same file are in
+    /// [`Duplicates::hard_links`]
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// The bytes freed by keeping only one copy
+    pub const fn reclaimable(&self) -> u64 {
+        self.size * (self.files.len() as u64 - 1)
+    }
+}
+
+/// Paths that are hard links to the same file, found by [`find_duplicates`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HardLinks {
+    size: u64,
+    paths: Vec<PathBuf>,
+}
+
+impl HardLinks {
+    /// The size of the file in bytes
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The names of the file in order
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+}
+
+/// The identical files among a set of paths, see [`find_duplicates`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Duplicates {
+    groups: Vec<DuplicateGroup>,
+    hard_links: Vec<HardLinks>,
+}
+
+impl Duplicates {
+    /// The groups of copies, those that free the most space first
+    pub fn groups(&self) -> &[DuplicateGroup] {
+        &self.groups
+    }
+
+    /// The files with more than one name, which take up no extra space
+    pub fn hard_links(&self) -> &[HardLinks] {
+        &self.hard_links
+    }
+
+    /// The bytes freed by keeping only one copy of every group
+    pub fn reclaimable(&self) -> u64 {
+        self.groups.iter().map(DuplicateGroup::reclaimable).sum()
+    }
+}
+
+/// A file found under one or more paths.
+struct Candidate {
+    size: u64,
+    paths: Vec<PathBuf>,
+}
+
+/// Find the files with the same contents among the results of a search
+///
+/// Files are grouped by size, then by a hash of their first and last 4 KiB, and only
+/// the files still alike are hashed in full with SHA-256. Reading and hashing run in
+/// parallel with rayon. Paths that are not regular files, are empty or can't be read
+/// are left out, as are symlinks, which are not followed.
+///
+/// Hard links to the same inode are the same file rather than copies, so each file
+/// appears in a group under its first path only and its names are listed in
+/// [`Duplicates::hard_links`].
+/// ### Arguments
+/// * `paths` - The paths to compare, e.g. a [`Search`](crate::Search) of any kind.
+/// ### Examples
+/// ```rust
+/// use rust_search::{find_duplicates, SearchBuilder};
+///
+/// let duplicates = find_duplicates(SearchBuilder::default().location("src").build());
+/// for group in duplicates.groups() {
+///     println!("{} bytes in {:?}", group.size(), group.files());
+/// }
+/// println!("{} bytes could be reclaimed", duplicates.reclaimable());
+/// ```
+pub fn find_duplicates<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Duplicates {
+    let mut paths: Vec<PathBuf> = paths
+        .into_iter()
+        .map(|path| path.as_ref().to_path_buf())
+        .collect();
+    paths.sort_unstable();
+    paths.dedup();
+    let found: Vec<(PathBuf, Metadata)> = paths
+        .into_par_iter()
+        .filter_map(|path| {
+            let metadata = fs::symlink_metadata(&path).ok()?;
+            (metadata.is_file() && metadata.len() > 0).then_some((path, metadata))
+        })
+        .collect();
+
+    let mut files: Vec<Candidate> = Vec::with_capacity(found.len());
+    let mut by_inode: HashMap<(u64, u64), usize> = HashMap::new();
+    for (path, metadata) in found {
+        let id = file_id(&metadata);
+        if let Some(&i) = id.and_then(|id| by_inode.get(&id)) {
+            files[i].paths.push(path);
+            continue;
+        }
+        if let Some(id) = id {
+            by_inode.insert(id, files.len());
+        }
+        files.push(Candidate {
+            size: metadata.len(),
+            paths: vec![path],
+        });
+    }
+
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, file) in files.iter().enumerate() {
+        by_size.entry(file.size).or_default().push(i);
+    }
+    let candidates = by_size
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    let candidates = split_by(candidates, |i| partial_hash(&files[i]).ok());
+    // The partial hash already covered all of a small file.
+    let identical = split_by(candidates, |i| {
+        let file = &files[i];
+        if file.size <= 2 * PARTIAL_BLOCK {
+            Some(String::new())
+        } else {
+            sha256_file(&file.paths[0]).ok()
+        }
+    });
+
+    let mut groups: Vec<DuplicateGroup> = identical
+        .into_iter()
+        .map(|mut group| {
+            group.sort_unstable();
+            DuplicateGroup {
+                size: files[group[0]].size,
+                files: group.iter().map(|&i| files[i].paths[0].clone()).collect(),
+            }
+        })
+        .collect();
+    groups.sort_unstable_by(|a, b| {
+        b.reclaimable()
+            .cmp(&a.reclaimable())
+            .then_with(|| a.files.cmp(&b.files))
+    });
+    let hard_links = files
+        .into_iter()
+        .filter(|file| file.paths.len() > 1)
+        .map(|file| HardLinks {
+            size: file.size,
+            paths: file.paths,
+        })
+        .collect();
+    Duplicates { groups, hard_links }
+}
+
+/// Split each group of files by a key computed in parallel, keeping the groups left
+/// with more than one file. Files without a key are dropped.
+fn split_by<K, F>(groups: Vec<Vec<usize>>, key: F) -> Vec<Vec<usize>>
+where
+    K: Eq + Hash + Send,
+    F: Fn(usize) -> Option<K> + Sync,
+{
+    let keyed: Vec<(usize, usize, K)> = groups
+        .into_iter()
+        .enumerate()
+        .flat_map(|(group, files)| files.into_iter().map(move |file| (group, file)))
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .filter_map(|(group, file)| Some((group, file, key(file)?)))
+        .collect();
+    let mut split: HashMap<(usize, K), Vec<usize>> = HashMap::new();
+    for (group, file, key) in keyed {
+        split.entry((group, key)).or_default().push(file);
+    }
+    split
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+/// The hash of the first and last [`PARTIAL_BLOCK`] bytes of a file, or of all of it
+/// when it is small.
+fn partial_hash(file: &Candidate) -> io::Result<String> {
+    let mut reader = File::open(&file.paths[0])?;
+    let mut blocks = Vec::with_capacity(2 * PARTIAL_BLOCK as usize);
+    (&mut reader).take(PARTIAL_BLOCK).read_to_end(&mut blocks)?;
+    let tail = file.size.saturating_sub(PARTIAL_BLOCK).max(PARTIAL_BLOCK);
+    reader.seek(SeekFrom::Start(tail))?;
+    reader.take(PARTIAL_BLOCK).read_to_end(&mut blocks)?;
+    sha256_reader(blocks.as_slice())
+}
+
+/// The device and inode of a file, which hard links share.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)]
+fn file_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+const fn file_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
@@ -0,0 +1,466 @@
+use std::{
+    error::Error,
+    ffi::{OsStr, OsString},
+    fmt, io,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+/// The most bytes of arguments a batch passes to one command, like the default of
+/// `xargs`. Linux allows more in total, but this leaves room for the environment.
+#[cfg(unix)]
+const MAX_BATCH_BYTES: usize = 128 * 1024;
+/// Windows limits the whole command line to 32767 UTF-16 characters.
+#[cfg(not(unix))]
+const MAX_BATCH_BYTES: usize = 30 * 1024;
+
+/// The placeholders of a command template, longest first so that `{//}` isn't read as `{/}`.
+const PLACEHOLDERS: [(&str, Part); 5] = [
+    ("{//}", Part::Parent),
+    ("{/.}", Part::Stem),
+    ("{}", Part::Path),
+    ("{/}", Part::Name),
+    ("{.}", Part::NoExtension),
+];
+
+/// Runs a command for every path of a search, like `find -exec`
+///
+/// The template is split into arguments like a shell would, honouring single and double
+/// quotes and backslashes, but is not run by a shell. These placeholders are replaced in
+/// every argument:
+///
+/// | Placeholder | Replaced with |
+/// |---|---|
+/// | `{}` | the path |
+/// | `{/}` | the file name |
+/// | `{//}` | the parent directory |
+/// | `{.}` | the path without its extension |
+/// | `{/.}` | the file name without its extension |
+///
+/// Without any placeholder the path is added as the last argument. Commands run with
+/// their output captured and stdin closed, several at once on separate threads.
+/// ### Examples
+/// ```rust,no_run
+/// use rust_search::{Exec, SearchBuilder};
+///
+/// let exec = Exec::new("gzip --best {}").unwrap().jobs(4).stop_on_failure();
+/// let report = SearchBuilder::default()
+///     .location("/var/log")
+///     .ext("log")
+///     .build()
+///     .exec(&exec);
+///
+/// for command in report.failed() {
+///     eprintln!("{:?}: {}", command.paths(), String::from_utf8_lossy(command.stderr()));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Exec {
+    args: Vec<Vec<Part>>,
+    batch: bool,
+    jobs: usize,
+    stop_on_failure: bool,
+}
+
+/// A piece of an argument of the template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Path,
+    Name,
+    Parent,
+    NoExtension,
+    Stem,
+}
+
+impl Part {
+    /// Write the piece for `path`.
+    fn expand(&self, path: &Path, out: &mut OsString) {
+        let name = || path.file_name().unwrap_or(path.as_os_str());
+        match self {
+            Self::Literal(text) => out.push(text),
+            Self::Path => out.push(path),
+            Self::Name => out.push(name()),
+            Self::Parent => out.push(match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.as_os_str(),
+                _ => OsStr::new("."),
+            }),
+            Self::NoExtension => out.push(path.with_extension("")),
+            Self::Stem => out.push(path.file_stem().unwrap_or_else(name)),
+        }
+    }
+}
+
+/// An error in a command template, see [`Exec::new`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecError {
+    position: usize,
+    message: String,
+}
+
+impl ExecError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+
+    /// The byte offset in the template where the error was found
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    /// What is wrong with the template
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Error for ExecError {}
+
+impl Exec {
+    /// Parse a command template like `"convert {} {.}.png"`
+    /// ### Errors
+    /// Returns an [`ExecError`] if the template has no command, an unclosed quote or
+    /// ends in a backslash.
+    pub fn new(template: &str) -> Result<Self, ExecError> {
+        let mut args = Vec::new();
+        let mut word: Option<Vec<Part>> = None;
+        let mut literal = String::new();
+        let mut quote: Option<(usize, char)> = None;
+        let mut chars = template.char_indices().peekable();
+        while let Some((pos, c)) = chars.next() {
+            match (quote, c) {
+                (None, c) if c.is_whitespace() => {
+                    if let Some(mut parts) = word.take() {
+                        push_literal(&mut parts, &mut literal);
+                        args.push(parts);
+                    }
+                    continue;
+                }
+                (None, '\'' | '"') => quote = Some((pos, c)),
+                (Some((_, open)), c) if c == open => quote = None,
+                (None, '\\') => match chars.next() {
+                    Some((_, escaped)) => literal.push(escaped),
+                    None => return Err(ExecError::new(pos, "trailing backslash")),
+                },
+                (Some((_, '"')), '\\') => {
+                    match chars.next_if(|&(_, next)| next == '"' || next == '\\') {
+                        Some((_, escaped)) => literal.push(escaped),
+                        None => literal.push('\\'),
+                    }
+                }
+                (_, '{') => {
+                    let placeholder = PLACEHOLDERS
+                        .iter()
+                        .find(|(text, _)| template[pos..].starts_with(text));
+                    match placeholder {
+                        Some((text, part)) => {
+                            let parts = word.get_or_insert_with(Vec::new);
+                            push_literal(parts, &mut literal);
+                            parts.push(part.clone());
+                            chars.nth(text.len() - 2);
+                        }
+                        None => literal.push('{'),
+                    }
+                }
+                (_, c) => literal.push(c),
+            }
+            word.get_or_insert_with(Vec::new);
+        }
+        if let Some((pos, c)) = quote {
+            return Err(ExecError::new(pos, format!("unclosed `{c}`")));
+        }
+        if let Some(mut parts) = word {
+            push_literal(&mut parts, &mut literal);
+            args.push(parts);
+        }
+        if args.is_empty() {
+            return Err(ExecError::new(0, "no command"));
+        }
+        if !args
+            .iter()
+            .flatten()
+            .any(|part| !matches!(part, Part::Literal(_)))
+        {
+            args.push(vec![Part::Path]);
+        }
+        Ok(Self {
+            args,
+            batch: false,
+            jobs: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            stop_on_failure: false,
+        })
+    }
+
+    /// Pass as many paths as fit to each command instead of one, like `find -exec {} +`.
+    ///
+    /// Every argument with a placeholder is repeated for each path of the batch.
+    pub const fn batch(mut self) -> Self {
+        self.batch = true;
+        self
+    }
+
+    /// Run at most `jobs` commands at once, defaults to the number of CPUs.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Start no more commands once one fails or can't be started.
+    ///
+    /// Commands already running still finish and are reported.
+    pub const fn stop_on_failure(mut self) -> Self {
+        self.stop_on_failure = true;
+        self
+    }
+
+    /// Run the command for every given path.
+    /// ### Arguments
+    /// * `paths` - The paths to run the command for, usually a [`Search`](crate::Search).
+    pub fn run<P: AsRef<Path>>(&self, paths: impl IntoIterator<Item = P>) -> ExecReport {
+        let stop = AtomicBool::new(false);
+        let (job_tx, job_rx) = crossbeam_channel::bounded::<(usize, Vec<PathBuf>)>(0);
+        let (done_tx, done_rx) = crossbeam_channel::unbounded();
+        thread::scope(|scope| {
+            for _ in 0..self.jobs {
+                let (job_rx, done_tx, stop) = (job_rx.clone(), done_tx.clone(), &stop);
+                scope.spawn(move || {
+                    for (index, paths) in job_rx {
+                        if stop.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        let output = self.run_command(paths);
+                        if self.stop_on_failure && !output.success() {
+                            stop.store(true, Ordering::Relaxed);
+                        }
+                        let _ = done_tx.send((index, output));
+                    }
+                });
+            }
+            drop((job_rx, done_tx));
+
+            let mut batch = Vec::new();
+            let mut batch_bytes = 0;
+            let mut index = 0;
+            let mut send = |paths: Vec<PathBuf>| {
+                if stop.load(Ordering::Relaxed) {
+                    return false;
+                }
+                let sent = job_tx.send((index, paths)).is_ok();
+                index += 1;
+                sent
+            };
+            for path in paths {
+                let path = path.as_ref().to_path_buf();
+                if !self.batch {
+                    if !send(vec![path]) {
+                        break;
+                    }
+                    continue;
+                }
+                let bytes = self.path_bytes(&path);
+                if !batch.is_empty() && batch_bytes + bytes > MAX_BATCH_BYTES {
+                    if !send(std::mem::take(&mut batch)) {
+                        break;
+                    }
+                    batch_bytes = 0;
+                }
+                batch.push(path);
+                batch_bytes += bytes;
+            }
+            if !batch.is_empty() {
+                send(batch);
+            }
+            drop(job_tx);
+        });
+
+        let mut commands: Vec<(usize, CommandOutput)> = done_rx.into_iter().collect();
+        commands.sort_unstable_by_key(|(index, _)| *index);
+        ExecReport {
+            commands: commands.into_iter().map(|(_, output)| output).collect(),
+            stopped: stop.into_inner(),
+        }
+    }
+
+    /// The bytes the arguments for one more path add to a batch, counting the
+    /// terminating nul and the pointer to each argument.
+    fn path_bytes(&self, path: &Path) -> usize {
+        self.args
+            .iter()
+            .filter(|parts| parts.iter().any(|part| !matches!(part, Part::Literal(_))))
+            .map(|parts| expand(parts, path).len() + 1 + size_of::<usize>())
+            .sum()
+    }
+
+    fn run_command(&self, paths: Vec<PathBuf>) -> CommandOutput {
+        let mut args = Vec::new();
+        for parts in &self.args {
+            if parts.iter().all(|part| matches!(part, Part::Literal(_))) {
+                args.push(expand(parts, Path::new("")));
+            } else {
+                args.extend(paths.iter().map(|path| expand(parts, path)));
+            }
+        }
+        let output = Command::new(&args[0])
+            .args(&args[1..])
+            .stdin(Stdio::null())
+            .output();
+        match output {
+            Ok(output) => CommandOutput {
+                paths,
+                status: Ok(output.status),
+                stdout: output.stdout,
+                stderr: output.stderr,
+            },
+            Err(error) => CommandOutput {
+                paths,
+                status: Err(error),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            },
+        }
+    }
+}
+
+fn push_literal(parts: &mut Vec<Part>, literal: &mut String) {
+    if !literal.is_empty() {
+        parts.push(Part::Literal(std::mem::take(literal)));
+    }
+}
+
+fn expand(parts: &[Part], path: &Path) -> OsString {
+    let mut arg = OsString::new();
+    for part in parts {
+        part.expand(path, &mut arg);
+    }
+    arg
+}
+
+/// The commands run by [`Exec::run`], in the order of their paths
+#[derive(Debug, Default)]
+pub struct ExecReport {
+    commands: Vec<CommandOutput>,
+    stopped: bool,
+}
+
+impl ExecReport {
+    /// Every command that was run or failed to start
+    pub fn commands(&self) -> &[CommandOutput] {
+        &self.commands
+    }
+
+    /// The commands that exited unsuccessfully or couldn't be started
+    pub fn failed(&self) -> impl Iterator<Item = &CommandOutput> {
+        self.commands.iter().filter(|command| !command.success())
+    }
+
+    /// Whether every command succeeded
+    pub fn success(&self) -> bool {
+        self.commands.iter().all(CommandOutput::success)
+    }
+
+    /// Whether a command failed with [`stop_on_failure`](Exec::stop_on_failure) set, so
+    /// the commands for later paths were not started
+    pub const fn stopped(&self) -> bool {
+        self.stopped
+    }
+}
+
+/// A command run for one path, or for a batch of paths
+#[derive(Debug)]
+pub struct CommandOutput {
+    paths: Vec<PathBuf>,
+    status: io::Result<ExitStatus>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl CommandOutput {
+    /// The paths the command was run for
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// How the command exited
+    /// ### Errors
+    /// Returns the error if the command couldn't be started, e.g. because it wasn't found.
+    pub const fn status(&self) -> Result<&ExitStatus, &io::Error> {
+        self.status.as_ref()
+    }
+
+    /// Whether the command exited successfully
+    pub fn success(&self) -> bool {
+        self.status.as_ref().is_ok_and(ExitStatus::success)
+    }
+
+    /// What the command wrote to stdout
+    pub fn stdout(&self) -> &[u8] {
+        &self.stdout
+    }
+
+    /// What the command wrote to stderr
+    pub fn stderr(&self) -> &[u8] {
+        &self.stderr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(exec: &Exec, path: &str) -> Vec<OsString> {
+        exec.args
+            .iter()
+            .map(|parts| expand(parts, Path::new(path)))
+            .collect()
+    }
+
+    #[test]
+    fn templates_split_like_a_shell() {
+        let exec = Exec::new(r#"cp "my file" 'it''s' a\ b {} {//}/{/.}.bak"#).unwrap();
+        assert_eq!(
+            args(&exec, "dir/photo.jpg"),
+            [
+                "cp",
+                "my file",
+                "its",
+                "a b",
+                "dir/photo.jpg",
+                "dir/photo.bak"
+            ]
+        );
+        let exec = Exec::new(r#"echo "a \"b\" \c" '' {/} {.} {x}"#).unwrap();
+        assert_eq!(
+            args(&exec, "a.tar.gz"),
+            ["echo", r#"a "b" \c"#, "", "a.tar.gz", "a.tar", "{x}"]
+        );
+    }
+
+    #[test]
+    fn path_is_appended_without_placeholders() {
+        let exec = Exec::new("  rm -f ").unwrap();
+        assert_eq!(args(&exec, "old.log"), ["rm", "-f", "old.log"]);
+        assert_eq!(args(&Exec::new("ls {//}").unwrap(), "top"), ["ls", "."]);
+    }
+
+    #[test]
+    fn invalid_templates() {
+        assert_eq!(Exec::new("  ").unwrap_err().message(), "no command");
+        assert_eq!(Exec::new("echo 'a").unwrap_err().position(), 5);
+        assert_eq!(
+            Exec::new("echo a\\").unwrap_err().message(),
+            "trailing backslash"
+        );
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{utils::replace_tilde_with_home_dir, SearchResult};
+
+/// What to do with each result, see [`FileOps`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Delete,
+    Trash(PathBuf),
+    Move(PathBuf),
+    Copy(PathBuf),
+    Chmod(u32),
+}
+
+/// Where moved or copied files go inside the destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// At the same path relative to the destination as to the location they were found
+    /// in. This is the default.
+    #[default]
+    Keep,
+    /// Directly in the destination, by file name
+    Flatten,
+}
+
+/// What to do when the target of a move or copy already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Conflict {
+    /// Leave the file where it is and report it as skipped. This is the default.
+    #[default]
+    Skip,
+    /// Replace the existing file, but never a directory
+    Overwrite,
+    /// Add a number to the name, `report (1).pdf`, `report (2).pdf` and so on
+    Rename,
+}
+
+/// Deletes, moves, copies or changes the permissions of the results of a search
+///
+/// Paths are handled in order and each error is reported along with its path instead of
+/// stopping. When a directory is among the results, everything below it goes with it,
+/// so results below it are left out. Targets are checked against the disk and against
+/// the earlier results, so that two files flattened to the same name conflict with each
+/// other too.
+///
+/// Results are found on the disk at their [`disk_path`](SearchResult::disk_path), so any
+/// [`PathStyle`](crate::PathStyle) can be used. A directory is never moved or copied into
+/// itself.
+///
+/// Symlinks are moved, copied and deleted as links, never followed.
+/// ### Examples
+/// ```rust
+/// use rust_search::{Conflict, FileOps, Layout, SearchBuilder};
+///
+/// let results = SearchBuilder::default().location("src").ext("rs").build_results();
+///
+/// // See what would happen without touching anything.
+/// let plan = FileOps::copy_to("/backup/src")
+///     .layout(Layout::Flatten)
+///     .conflict(Conflict::Rename)
+///     .dry_run()
+///     .run(results);
+///
+/// for entry in plan.entries() {
+///     println!("{} -> {:?}", entry.source().display(), entry.target());
+/// }
+/// assert_eq!(plan.failed().count(), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileOps {
+    action: Action,
+    layout: Layout,
+    conflict: Conflict,
+    dry_run: bool,
+}
+
+impl FileOps {
+    fn new(action: Action) -> Self {
+        Self {
+            action,
+            layout: Layout::default(),
+            conflict: Conflict::default(),
+            dry_run: false,
+        }
+    }
+
+    /// Delete the results, directories with everything in them
+    pub fn delete() -> Self {
+        Self::new(Action::Delete)
+    }
+
+    /// Delete the results by moving them into a trash directory, from where they can be
+    /// restored. Conflicts are renamed unless set otherwise.
+    /// ### Arguments
+    /// * `dir` - The trash directory, created if needed, `~` is expanded.
+    pub fn trash(dir: impl AsRef<Path>) -> Self {
+        Self::new(Action::Trash(replace_tilde_with_home_dir(dir))).conflict(Conflict::Rename)
+    }
+
+    /// Move the results into a directory
+    /// ### Arguments
+    /// * `dir` - The destination, created if needed, `~` is expanded.
+    pub fn move_to(dir: impl AsRef<Path>) -> Self {
+        Self::new(Action::Move(replace_tilde_with_home_dir(dir)))
+    }
+
+    /// Copy the results into a directory, directories with everything in them
+    /// ### Arguments
+    /// * `dir` - The destination, created if needed, `~` is expanded.
+    pub fn copy_to(dir: impl AsRef<Path>) -> Self {
+        Self::new(Action::Copy(replace_tilde_with_home_dir(dir)))
+    }
+
+    /// Set the permission bits of the results, on Unix only
+    /// ### Arguments
+    /// * `mode` - The permission bits, e.g. `0o644`.
+    pub fn chmod(mode: u32) -> Self {
+        Self::new(Action::Chmod(mode))
+    }
+
+    /// Where moved, trashed or copied files go inside the destination, defaults to
+    /// [`Layout::Keep`].
+    pub const fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// What to do when a target already exists, defaults to [`Conflict::Skip`].
+    pub const fn conflict(mut self, conflict: Conflict) -> Self {
+        self.conflict = conflict;
+        self
+    }
+
+    /// Don't change anything, only report what would be done.
+    pub const fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Apply the operation to every result.
+    /// ### Arguments
+    /// * `results` - The results, from [`build_results`](crate::SearchBuilder::build_results).
+    pub fn run(&self, results: impl IntoIterator<Item = SearchResult>) -> FileOpsReport {
+        let mut results: Vec<SearchResult> = results.into_iter().collect();
+        results.sort_unstable_by(|a, b| a.disk_path().cmp(b.disk_path()));
+        results.dedup_by(|a, b| a.disk_path() == b.disk_path());
+
+        let mut report = FileOpsReport::default();
+        let mut claimed = HashSet::new();
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for result in results {
+            let path = result.disk_path();
+            if !matches!(self.action, Action::Chmod(_)) {
+                if dirs.iter().any(|dir| path.starts_with(dir)) {
+                    continue;
+                }
+                if result.kind() == crate::FileKind::Dir {
+                    dirs.push(path.to_path_buf());
+                }
+            }
+            report.entries.push(self.apply(&result, &mut claimed));
+        }
+        report
+    }
+
+    fn apply(&self, result: &SearchResult, claimed: &mut HashSet<PathBuf>) -> FileOpEntry {
+        let source = result.disk_path().to_path_buf();
+        let dest = match &self.action {
+            Action::Delete => return self.finish(source, None, remove),
+            Action::Chmod(mode) => return self.finish(source, None, |path| chmod(path, *mode)),
+            Action::Trash(dest) | Action::Move(dest) | Action::Copy(dest) => dest,
+        };
+
+        let name = source
+            .file_name()
+            .map_or_else(|| OsString::from("root"), ToOwned::to_owned);
+        let relative = match self.layout {
+            Layout::Keep => match source.strip_prefix(result.root()) {
+                Ok(relative) if !relative.as_os_str().is_empty() => relative.to_path_buf(),
+                Ok(_) => PathBuf::from(&name),
+                Err(_) => {
+                    let error = io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "not inside the location it was found in",
+                    );
+                    return Self::failed(source, None, error);
+                }
+            },
+            Layout::Flatten => PathBuf::from(&name),
+        };
+        let mut target = dest.join(relative);
+        if is_same(&target, &source) {
+            let error = io::Error::new(io::ErrorKind::InvalidInput, "target is the source");
+            return Self::failed(source, Some(target), error);
+        }
+        if result.kind() == crate::FileKind::Dir && is_inside(&target, &source) {
+            let error = io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "destination is inside the directory",
+            );
+            return Self::failed(source, Some(target), error);
+        }
+        let taken = |target: &Path| claimed.contains(target) || target.symlink_metadata().is_ok();
+        let mut overwrite = false;
+        if taken(&target) {
+            match self.conflict {
+                Conflict::Skip => {
+                    return FileOpEntry {
+                        source,
+                        target: Some(target),
+                        status: FileOpStatus::Skipped,
+                    }
+                }
+                Conflict::Overwrite => overwrite = true,
+                Conflict::Rename => {
+                    let mut n = 1;
+                    while taken(&numbered(&target, n)) {
+                        n += 1;
+                    }
+                    target = numbered(&target, n);
+                }
+            }
+        }
+        if overwrite && target.is_dir() {
+            let error = io::Error::new(io::ErrorKind::AlreadyExists, "target is a directory");
+            return Self::failed(source, Some(target), error);
+        }
+        claimed.insert(target.clone());
+
+        let copy = matches!(self.action, Action::Copy(_));
+        let to = target.clone();
+        self.finish(source, Some(target), |source| {
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if overwrite && to.symlink_metadata().is_ok() {
+                fs::remove_file(&to)?;
+            }
+            if copy {
+                copy_all(source, &to)
+            } else {
+                move_path(source, &to)
+            }
+        })
+    }
+
+    const fn failed(source: PathBuf, target: Option<PathBuf>, error: io::Error) -> FileOpEntry {
+        FileOpEntry {
+            source,
+            target,
+            status: FileOpStatus::Failed(error),
+        }
+    }
+
+    /// Run `apply` on `source` unless this is a dry run, and report the outcome.
+    fn finish(
+        &self,
+        source: PathBuf,
+        target: Option<PathBuf>,
+        apply: impl FnOnce(&Path) -> io::Result<()>,
+    ) -> FileOpEntry {
+        let status = if self.dry_run {
+            FileOpStatus::Planned
+        } else {
+            match apply(&source) {
+                Ok(()) => FileOpStatus::Done,
+                Err(error) => FileOpStatus::Failed(error),
+            }
+        };
+        FileOpEntry {
+            source,
+            target,
+            status,
+        }
+    }
+}
+
+/// Whether `target` exists and is `source` once symlinks and `..` are resolved.
+fn is_same(target: &Path, source: &Path) -> bool {
+    match (target.canonicalize(), source.canonicalize()) {
+        (Ok(target), Ok(source)) => target == source,
+        _ => false,
+    }
+}
+
+/// Whether `path`, which may not exist yet, is `dir` or below it once symlinks and `..`
+/// are resolved.
+fn is_inside(path: &Path, dir: &Path) -> bool {
+    let Ok(dir) = dir.canonicalize() else {
+        return false;
+    };
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            return missing
+                .iter()
+                .rev()
+                .fold(resolved, |path, name| path.join(name))
+                .starts_with(dir);
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = if parent.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    parent
+                };
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// `path` with ` (n)` added to the file stem.
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(path.extension().map_or_else(
+        || format!("{stem} ({n})"),
+        |ext| format!("{stem} ({n}).{}", ext.to_string_lossy()),
+    ))
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    if path.symlink_metadata()?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Rename `from` to `to`, copying and deleting instead when they are on different file
+/// systems.
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(error) if error.kind() == io::ErrorKind::CrossesDevices => {
+            copy_all(from, to)?;
+            remove(from)
+        }
+        renamed => renamed,
+    }
+}
+
+/// Copy a file, symlink or directory with everything in it.
+fn copy_all(from: &Path, to: &Path) -> io::Result<()> {
+    let file_type = from.symlink_metadata()?.file_type();
+    if file_type.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else if file_type.is_symlink() {
+        copy_symlink(&fs::read_link(from)?, to)
+    } else {
+        fs::copy(from, to).map(drop)
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(link: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(link, to)
+}
+
+#[cfg(windows)]
+fn copy_symlink(link: &Path, to: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(link, to)
+}
+
+#[cfg(unix)]
+fn chmod(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn chmod(_path: &Path, _mode: u32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "permission bits are only supported on Unix",
+    ))
+}
+
+/// What happened to each result of [`FileOps::run`], in order of their paths
+#[derive(Debug, Default)]
+pub struct FileOpsReport {
+    entries: Vec<FileOpEntry>,
+}
+
+impl FileOpsReport {
+    /// Every result that was handled
+    pub fn entries(&self) -> &[FileOpEntry] {
+        &self.entries
+    }
+
+    /// The results that were changed, or would be in a dry run
+    pub fn succeeded(&self) -> impl Iterator<Item = &FileOpEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.status, FileOpStatus::Done | FileOpStatus::Planned))
+    }
+
+    /// The results left alone because their target already existed
+    pub fn skipped(&self) -> impl Iterator<Item = &FileOpEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.status, FileOpStatus::Skipped))
+    }
+
+    /// The results the operation failed for
+    pub fn failed(&self) -> impl Iterator<Item = &FileOpEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.status, FileOpStatus::Failed(_)))
+    }
+}
+
+/// What happened to one result, see [`FileOpsReport`]
+#[derive(Debug)]
+pub struct FileOpEntry {
+    source: PathBuf,
+    target: Option<PathBuf>,
+    status: FileOpStatus,
+}
+
+impl FileOpEntry {
+    /// The path of the result
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Where the result was moved or copied to, `None` for deleting and chmod
+    pub fn target(&self) -> Option<&Path> {
+        self.target.as_deref()
+    }
+
+    /// Whether the operation was done, planned, skipped or failed
+    pub const fn status(&self) -> &FileOpStatus {
+        &self.status
+    }
+}
+
+/// The outcome of an operation on one result
+#[derive(Debug)]
+pub enum FileOpStatus {
+    /// The operation would be done, in a [dry run](FileOps::dry_run)
+    Planned,
+    /// The operation was done
+    Done,
+    /// The target already existed and [`Conflict::Skip`] was set
+    Skipped,
+    /// The operation failed
+    Failed(io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renamed_targets_are_numbered() {
+        assert_eq!(
+            numbered(Path::new("out/report.pdf"), 1),
+            Path::new("out/report (1).pdf")
+        );
+        assert_eq!(
+            numbered(Path::new("out/Makefile"), 2),
+            Path::new("out/Makefile (2)")
+        );
+    }
+}
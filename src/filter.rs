@@ -1,11 +1,11 @@
 use super::SearchBuilder;
 use ignore::DirEntry;
-use std::{cmp::Ordering, time::SystemTime};
+use std::{cmp::Ordering, fs::Metadata, time::SystemTime};
 
 /// custom filter fn to expose the dir entry directly
 pub type FilterFn = fn(&DirEntry) -> bool;
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub enum FilterType {
     Created(Ordering, SystemTime),
     Modified(Ordering, SystemTime),
@@ -13,27 +13,58 @@ pub enum FilterType {
     Custom(FilterFn),
 }
 
+/// The metadata filters and query terms are checked against, read from the disk or an index.
+pub trait Stat {
+    fn len(&self) -> u64;
+    fn modified(&self) -> Option<SystemTime>;
+    fn created(&self) -> Option<SystemTime>;
+}
+
+impl Stat for Metadata {
+    fn len(&self) -> u64 {
+        self.len()
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified().ok()
+    }
+
+    fn created(&self) -> Option<SystemTime> {
+        self.created().ok()
+    }
+}
+
+impl<T: Stat> Stat for &T {
+    fn len(&self) -> u64 {
+        T::len(self)
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        T::modified(self)
+    }
+
+    fn created(&self) -> Option<SystemTime> {
+        T::created(self)
+    }
+}
+
 impl FilterType {
     pub fn apply(&self, dir: &DirEntry) -> bool {
-        if let Ok(m) = dir.metadata() {
-            match self {
-                Self::Created(cmp, time) => {
-                    if let Ok(created) = m.created() {
-                        return created.cmp(time) == *cmp;
-                    }
-                }
-                Self::Modified(cmp, time) => {
-                    if let Ok(modified) = m.modified() {
-                        return modified.cmp(time) == *cmp;
-                    }
-                }
-                Self::FileSize(cmp, size_in_bytes) => {
-                    return m.len().cmp(size_in_bytes) == *cmp;
-                }
-                Self::Custom(f) => return f(dir),
-            }
+        match (self, dir.metadata()) {
+            (Self::Custom(f), Ok(_)) => f(dir),
+            (_, Ok(m)) => self.apply_stat(&m).unwrap_or(false),
+            (_, Err(_)) => false,
+        }
+    }
+
+    /// Check the filter against metadata alone, `None` for custom filters which need the entry.
+    pub fn apply_stat(&self, stat: &impl Stat) -> Option<bool> {
+        match self {
+            Self::Created(cmp, time) => Some(stat.created().is_some_and(|t| t.cmp(time) == *cmp)),
+            Self::Modified(cmp, time) => Some(stat.modified().is_some_and(|t| t.cmp(time) == *cmp)),
+            Self::FileSize(cmp, size_in_bytes) => Some(stat.len().cmp(size_in_bytes) == *cmp),
+            Self::Custom(_) => None,
         }
-        false
     }
 }
 
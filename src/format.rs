@@ -0,0 +1,457 @@
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Write as _},
+    path::Path,
+};
+
+use crate::{
+    utils::{format_rfc3339, format_time, TIME_CONVERSIONS},
+    SearchResult,
+};
+
+/// A template rendering each [`SearchResult`] on a line of its own
+///
+/// Placeholders are written in braces, some take a spec after a colon:
+///
+/// | Placeholder | Renders |
+/// |---|---|
+/// | `{path}` | the path as found, in the [`PathStyle`](crate::PathStyle) of the search |
+/// | `{relpath}` | the path relative to the location it was found in |
+/// | `{root}` | the location the path was found in |
+/// | `{name}`, `{stem}`, `{ext}` | the file name, the name without extension and the extension |
+/// | `{parent}` | the directory containing the path |
+/// | `{size}`, `{size:h}` | the size in bytes, or human-readable like `1.5K` or `12M` |
+/// | `{mtime}`, `{mtime:%Y-%m-%d}` | the modification time in RFC 3339, or `strftime` style, in UTC |
+/// | `{perms}`, `{perms:o}` | the permissions like `-rw-r--r--`, or in octal like `644` |
+/// | `{owner}`, `{group}` | the names of the owning user and group, their ids if unknown |
+/// | `{depth}`, `{kind}` | the depth below the location and `file`, `dir`, `symlink` or `other` |
+/// | `{score}`, `{score:.2}` | the similarity score, optionally with a number of decimals |
+///
+/// Braces are written as `{{` and `}}`, and `\t`, `\n`, `\0` and `\\` are unescaped so
+/// templates can be passed straight from the command line. Values that are unknown, like
+/// the size of a path whose metadata couldn't be read, render empty.
+/// ### Examples
+/// ```rust
+/// use rust_search::{ResultFormat, SearchBuilder};
+///
+/// let format = ResultFormat::parse("{size:h}\t{mtime:%Y-%m-%d}\t{relpath}").unwrap();
+/// for result in SearchBuilder::default().location("src").ext("rs").build_results() {
+///     println!("{}", format.render(&result));
+/// }
+///
+/// let error = ResultFormat::parse("{name} {colour}").unwrap_err();
+/// assert_eq!(error.position(), 8);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultFormat {
+    pieces: Vec<Piece>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    Path,
+    RelPath,
+    Root,
+    Name,
+    Stem,
+    Ext,
+    Parent,
+    Size { human: bool },
+    Mtime(Option<String>),
+    Perms { octal: bool },
+    Owner,
+    Group,
+    Depth,
+    Kind,
+    Score(Option<usize>),
+}
+
+/// An error in the syntax of a [`ResultFormat`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatError {
+    position: usize,
+    message: String,
+}
+
+impl FormatError {
+    pub(crate) fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+
+    /// The byte offset in the template where the error was found
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    /// What is wrong with the template
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Error for FormatError {}
+
+impl ResultFormat {
+    /// Parse a template like `"{size:h}\t{relpath}"`
+    ///
+    /// ### Errors
+    /// Returns a [`FormatError`] pointing at an unclosed brace, an unknown placeholder or
+    /// an invalid spec.
+    pub fn parse(template: &str) -> Result<Self, FormatError> {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|&(_, c)| c == '{').is_some() => literal.push('{'),
+                '}' if chars.next_if(|&(_, c)| c == '}').is_some() => literal.push('}'),
+                '{' => {
+                    let start = pos + 1;
+                    let end = loop {
+                        match chars.next() {
+                            Some((end, '}')) => break end,
+                            Some(_) => {}
+                            None => return Err(FormatError::new(pos, "unclosed `{`")),
+                        }
+                    };
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Piece::parse(&template[start..end], start)?);
+                }
+                '}' => {
+                    return Err(FormatError::new(
+                        pos,
+                        "unmatched `}`, write `}}` for a brace",
+                    ))
+                }
+                '\\' => match chars.next_if(|&(_, c)| matches!(c, 't' | 'n' | '0' | '\\')) {
+                    Some((_, 't')) => literal.push('\t'),
+                    Some((_, 'n')) => literal.push('\n'),
+                    Some((_, '0')) => literal.push('\0'),
+                    _ => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(Self { pieces })
+    }
+
+    /// Render `result` following the template
+    pub fn render(&self, result: &SearchResult) -> String {
+        let mut out = String::new();
+        for piece in &self.pieces {
+            piece.render(result, &mut out);
+        }
+        out
+    }
+}
+
+impl Piece {
+    /// Parse the placeholder `field`, found at byte offset `pos` of the template.
+    fn parse(field: &str, pos: usize) -> Result<Self, FormatError> {
+        let (name, spec) = match field.split_once(':') {
+            Some((name, spec)) => (name, Some(spec)),
+            None => (field, None),
+        };
+        let spec_pos = pos + name.len() + 1;
+        let piece = match (name, spec) {
+            ("path", None) => Self::Path,
+            ("relpath", None) => Self::RelPath,
+            ("root", None) => Self::Root,
+            ("name", None) => Self::Name,
+            ("stem", None) => Self::Stem,
+            ("ext", None) => Self::Ext,
+            ("parent", None) => Self::Parent,
+            ("size", None) => Self::Size { human: false },
+            ("size", Some("h")) => Self::Size { human: true },
+            ("mtime", None) => Self::Mtime(None),
+            ("mtime", Some(format)) => {
+                check_time_format(format, spec_pos)?;
+                Self::Mtime(Some(format.to_owned()))
+            }
+            ("perms", None) => Self::Perms { octal: false },
+            ("perms", Some("o")) => Self::Perms { octal: true },
+            ("owner", None) => Self::Owner,
+            ("group", None) => Self::Group,
+            ("depth", None) => Self::Depth,
+            ("kind", None) => Self::Kind,
+            ("score", None) => Self::Score(None),
+            ("score", Some(spec)) => {
+                let decimals = spec
+                    .strip_prefix('.')
+                    .and_then(|decimals| decimals.parse().ok())
+                    .ok_or_else(|| {
+                        FormatError::new(
+                            spec_pos,
+                            format!("expected `.N` decimals, found `{spec}`"),
+                        )
+                    })?;
+                Self::Score(Some(decimals))
+            }
+            (
+                "path" | "relpath" | "root" | "name" | "stem" | "ext" | "parent" | "size" | "perms"
+                | "owner" | "group" | "depth" | "kind",
+                Some(spec),
+            ) => {
+                return Err(FormatError::new(
+                    spec_pos,
+                    format!("unknown spec `{spec}` for `{name}`"),
+                ))
+            }
+            _ => {
+                return Err(FormatError::new(
+                    pos,
+                    format!("unknown placeholder `{name}`"),
+                ))
+            }
+        };
+        Ok(piece)
+    }
+
+    fn render(&self, result: &SearchResult, out: &mut String) {
+        let path = result.path();
+        // Writing to a String can't fail.
+        let _ = match self {
+            Self::Literal(text) => out.write_str(text),
+            Self::Path => out.write_str(&path.to_string_lossy()),
+            Self::RelPath => out.write_str(&relative_to_root(result).to_string_lossy()),
+            Self::Root => out.write_str(&result.root().to_string_lossy()),
+            Self::Name => write_os(out, path.file_name()),
+            Self::Stem => write_os(out, path.file_stem()),
+            Self::Ext => write_os(out, path.extension()),
+            Self::Parent => write_os(out, path.parent().map(Path::as_os_str)),
+            Self::Size { human: false } => write_some(out, result.size()),
+            Self::Size { human: true } => write_some(out, result.size().map(human_size)),
+            Self::Mtime(None) => write_some(out, result.modified().map(format_rfc3339)),
+            Self::Mtime(Some(format)) => {
+                write_some(out, result.modified().map(|time| format_time(time, format)))
+            }
+            Self::Perms { octal: false } => write_some(out, result.mode().map(symbolic_mode)),
+            Self::Perms { octal: true } => write_some(
+                out,
+                result.mode().map(|mode| format!("{:o}", mode & 0o7777)),
+            ),
+            Self::Owner => write_some(out, result.uid().map(owner::user_name)),
+            Self::Group => write_some(out, result.gid().map(owner::group_name)),
+            Self::Depth => write!(out, "{}", result.depth()),
+            Self::Kind => out.write_str(result.kind().as_str()),
+            Self::Score(None) => write_some(out, result.score()),
+            Self::Score(Some(decimals)) => write_some(
+                out,
+                result.score().map(|score| format!("{score:.decimals$}")),
+            ),
+        };
+    }
+}
+
+fn write_some(out: &mut String, value: Option<impl fmt::Display>) -> fmt::Result {
+    value.map_or(Ok(()), |value| write!(out, "{value}"))
+}
+
+fn write_os(out: &mut String, value: Option<&std::ffi::OsStr>) -> fmt::Result {
+    value.map_or(Ok(()), |value| out.write_str(&value.to_string_lossy()))
+}
+
+/// Check that every `%` of a time `format`, found at byte offset `pos`, is a supported
+/// conversion.
+pub fn check_time_format(format: &str, pos: usize) -> Result<(), FormatError> {
+    let mut chars = format.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.next() {
+            Some((_, conversion)) if TIME_CONVERSIONS.contains(conversion) => {}
+            Some((_, conversion)) => {
+                return Err(FormatError::new(
+                    pos + i,
+                    format!("unsupported time conversion `%{conversion}`"),
+                ))
+            }
+            None => return Err(FormatError::new(pos + i, "incomplete time conversion `%`")),
+        }
+    }
+    Ok(())
+}
+
+/// The path of `result` relative to the location it was found in.
+fn relative_to_root(result: &SearchResult) -> Cow<'_, Path> {
+    let (path, root) = (result.path(), result.root());
+    let relative = match path.strip_prefix(root) {
+        Ok(relative) => Cow::Borrowed(relative),
+        // The path may be absolute while the location was given relative, or already
+        // written relative to it.
+        Err(_) => match std::path::absolute(root)
+            .ok()
+            .and_then(|root| path.strip_prefix(root).ok().map(Path::to_path_buf))
+        {
+            Some(relative) => Cow::Owned(relative),
+            None => return Cow::Borrowed(path),
+        },
+    };
+    if relative.as_os_str().is_empty() {
+        Cow::Borrowed(Path::new("."))
+    } else {
+        relative
+    }
+}
+
+/// Write `size` with a binary unit like `ls -h`, e.g. `512`, `1.5K` or `12M`.
+pub fn human_size(size: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{value:.1}{}", UNITS[unit])
+    } else {
+        format!("{value:.0}{}", UNITS[unit])
+    }
+}
+
+/// Write the file type and permission bits of `mode` like `ls -l`, e.g. `-rwxr-x---`.
+fn symbolic_mode(mode: u32) -> String {
+    let kind = match mode & 0o170_000 {
+        0o040_000 => 'd',
+        0o120_000 => 'l',
+        0o020_000 => 'c',
+        0o060_000 => 'b',
+        0o010_000 => 'p',
+        0o140_000 => 's',
+        _ => '-',
+    };
+    let mut out = String::with_capacity(10);
+    out.push(kind);
+    // (read, write, execute bits, special bit, letter when special and executable)
+    let triplets = [
+        (0o400, 0o200, 0o100, 0o4000, 's'),
+        (0o040, 0o020, 0o010, 0o2000, 's'),
+        (0o004, 0o002, 0o001, 0o1000, 't'),
+    ];
+    for (read, write, execute, special, letter) in triplets {
+        out.push(if mode & read != 0 { 'r' } else { '-' });
+        out.push(if mode & write != 0 { 'w' } else { '-' });
+        out.push(match (mode & execute != 0, mode & special != 0) {
+            (true, true) => letter,
+            (false, true) => letter.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+/// Names of users and groups, looked up once per id.
+pub mod owner {
+    #[cfg(unix)]
+    pub fn user_name(uid: u32) -> String {
+        lookup(&USERS, uid, |uid| {
+            uzers::get_user_by_uid(uid).map(|user| user.name().to_string_lossy().into_owned())
+        })
+    }
+
+    #[cfg(unix)]
+    pub fn group_name(gid: u32) -> String {
+        lookup(&GROUPS, gid, |gid| {
+            uzers::get_group_by_gid(gid).map(|group| group.name().to_string_lossy().into_owned())
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn user_name(uid: u32) -> String {
+        uid.to_string()
+    }
+
+    #[cfg(not(unix))]
+    pub fn group_name(gid: u32) -> String {
+        gid.to_string()
+    }
+
+    #[cfg(unix)]
+    type Names = std::sync::LazyLock<std::sync::Mutex<std::collections::HashMap<u32, String>>>;
+
+    #[cfg(unix)]
+    static USERS: Names = Names::new(Default::default);
+    #[cfg(unix)]
+    static GROUPS: Names = Names::new(Default::default);
+
+    /// The cached name of `id`, falling back to the id itself when it has no name.
+    #[cfg(unix)]
+    fn lookup(names: &Names, id: u32, find: impl FnOnce(u32) -> Option<String>) -> String {
+        let mut names = names
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        names
+            .entry(id)
+            .or_insert_with(|| find(id).unwrap_or_else(|| id.to_string()))
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_literals_escapes_and_braces() {
+        let format = ResultFormat::parse("{{{name}}}\\t\\0\\q").unwrap();
+        assert_eq!(
+            format.pieces,
+            vec![
+                Piece::Literal("{".into()),
+                Piece::Name,
+                Piece::Literal("}\t\0\\q".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_problem() {
+        assert_eq!(ResultFormat::parse("a {name").unwrap_err().position(), 2);
+        assert_eq!(ResultFormat::parse("a }").unwrap_err().position(), 2);
+        assert_eq!(ResultFormat::parse("{size:k}").unwrap_err().position(), 6);
+        assert_eq!(
+            ResultFormat::parse("{mtime:%Y-%q}").unwrap_err().position(),
+            10
+        );
+        assert_eq!(ResultFormat::parse("{score:2}").unwrap_err().position(), 7);
+    }
+
+    #[test]
+    fn human_size_units() {
+        assert_eq!(human_size(512), "512");
+        assert_eq!(human_size(1536), "1.5K");
+        assert_eq!(human_size(12 * 1024 * 1024), "12M");
+        assert_eq!(human_size(3 * 1024 * 1024 * 1024), "3.0G");
+    }
+
+    #[test]
+    fn symbolic_mode_like_ls() {
+        assert_eq!(symbolic_mode(0o100_644), "-rw-r--r--");
+        assert_eq!(symbolic_mode(0o040_755), "drwxr-xr-x");
+        assert_eq!(symbolic_mode(0o104_755), "-rwsr-xr-x");
+        assert_eq!(symbolic_mode(0o041_777), "drwxrwxrwt");
+        assert_eq!(symbolic_mode(0o102_644), "-rw-r-Sr--");
+    }
+}
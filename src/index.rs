@@ -0,0 +1,868 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, Metadata},
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use ignore::{DirEntry, WalkBuilder, WalkState};
+
+use crate::{
+    content::ContentMatcher,
+    filter::{FilterType, Stat},
+    result::FileKind,
+    search::Matcher,
+    utils::{
+        epoch_parts, from_epoch_parts, path_from_bytes, path_to_bytes, replace_tilde_with_home_dir,
+    },
+    Search, SearchBuilder, SearchResult,
+};
+
+/// Marks the start of an index file.
+const MAGIC: &[u8; 8] = b"rsindex\0";
+/// Bumped whenever the layout of the index file changes.
+const VERSION: u8 = 1;
+
+/// A database of the paths below some locations, searched without walking the disk
+///
+/// Like `updatedb` and `locate`: [`build`](Self::build) walks the locations once and
+/// records every path with its kind, size and modification and creation times, hidden
+/// files included and gitignored files excluded. The same [`SearchBuilder`] queries are
+/// then answered from the index in milliseconds, with the input, extension, depth, limit,
+/// hidden flag, filters and query terms checked against the recorded metadata. Custom
+/// filters and content searches still read the matched files from the disk.
+///
+/// The index only knows the disk as it was when it was built, use [`age`](Self::age) to
+/// decide when to build it again. Locations of a search that are not below an indexed
+/// location find nothing.
+/// ### Examples
+/// ```rust
+/// use rust_search::{Index, SearchBuilder};
+///
+/// let index = Index::build(["src"]).unwrap();
+/// let path = std::env::temp_dir().join("rust_search_index_doc.db");
+/// index.save(&path).unwrap();
+///
+/// let index = Index::load(&path).unwrap();
+/// println!("index is {:?} old", index.age());
+/// let search: Vec<String> = index
+///     .search(&SearchBuilder::default().location("src").ext("rs"))
+///     .collect();
+/// assert!(search.contains(&"src/lib.rs".to_string()));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Index {
+    built: SystemTime,
+    roots: Vec<IndexRoot>,
+}
+
+/// An indexed location and the paths below it, sorted so every directory is
+/// directly followed by its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IndexRoot {
+    path: PathBuf,
+    records: Vec<Record>,
+}
+
+/// A path recorded in the index, relative to its location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Record {
+    path: PathBuf,
+    kind: FileKind,
+    size: u64,
+    modified: Option<SystemTime>,
+    created: Option<SystemTime>,
+}
+
+impl Record {
+    fn new(path: PathBuf, kind: FileKind, metadata: &Metadata) -> Self {
+        Self {
+            path,
+            kind,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            created: metadata.created().ok(),
+        }
+    }
+}
+
+impl Stat for Record {
+    fn len(&self) -> u64 {
+        self.size
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+}
+
+impl Index {
+    /// Walk the locations and record every path below them
+    /// ## Notes
+    /// - Will replace `~` with [home directory](https://en.wikipedia.org/wiki/Home_directory)
+    /// ### Arguments
+    /// * `locations` - The locations to index.
+    /// ### Errors
+    /// Returns an error if a location does not exist. Paths that can't be read while
+    /// walking are left out.
+    pub fn build<P: AsRef<Path>>(locations: impl IntoIterator<Item = P>) -> io::Result<Self> {
+        let mut roots = Vec::new();
+        for location in locations {
+            let path = std::path::absolute(replace_tilde_with_home_dir(location))?;
+            fs::symlink_metadata(&path)?;
+            roots.push(IndexRoot {
+                path,
+                records: Vec::new(),
+            });
+        }
+        let built = SystemTime::now();
+        let Some((first, rest)) = roots.split_first() else {
+            return Ok(Self { built, roots });
+        };
+
+        let mut walker = walk(&first.path, None);
+        for root in rest {
+            walker.add(&root.path);
+        }
+        let cpus = std::thread::available_parallelism().map_or(8, std::num::NonZero::get);
+        walker.threads(cpus * 2);
+
+        let (tx, rx) = crossbeam_channel::unbounded::<DirEntry>();
+        walker.build_parallel().run(|| {
+            let tx = tx.clone();
+            Box::new(move |entry| {
+                if let Ok(entry) = entry {
+                    let _ = tx.send(entry);
+                }
+                WalkState::Continue
+            })
+        });
+        drop(tx);
+
+        for entry in rx {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let Some(root_path) = entry.path().ancestors().nth(entry.depth()) else {
+                continue;
+            };
+            let Some(root) = roots.iter_mut().find(|root| root.path == root_path) else {
+                continue;
+            };
+            let path = entry
+                .path()
+                .strip_prefix(root_path)
+                .unwrap_or_else(|_| Path::new(""));
+            let kind = FileKind::from_file_type(entry.file_type());
+            root.records
+                .push(Record::new(path.to_path_buf(), kind, &metadata));
+        }
+        for root in &mut roots {
+            root.records.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        }
+        Ok(Self { built, roots })
+    }
+
+    /// Bring the index up to date with the disk without walking it again
+    ///
+    /// Every indexed directory is checked, but only the directories whose modification
+    /// time changed are read again, the recorded contents of the others are kept. New
+    /// directories are walked completely. Files changed in place don't change the
+    /// modification time of their directory, so their new size and time are only
+    /// recorded when their directory is read again for another reason. The same goes
+    /// for changes to `.gitignore` files.
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::Index;
+    ///
+    /// let mut index = Index::build(["src"]).unwrap();
+    /// let changes = index.refresh();
+    /// for path in changes.added() {
+    ///     println!("+ {}", path.display());
+    /// }
+    /// for path in changes.removed() {
+    ///     println!("- {}", path.display());
+    /// }
+    /// ```
+    pub fn refresh(&mut self) -> Changeset {
+        let started = SystemTime::now();
+        let mut changes = Changeset::default();
+        for root in &mut self.roots {
+            let old = std::mem::take(&mut root.records);
+            let mut refresh = Refresh {
+                root: &root.path,
+                old: &old,
+                records: Vec::with_capacity(old.len()),
+                changes: &mut changes,
+            };
+            match fs::symlink_metadata(&root.path) {
+                Ok(metadata) => {
+                    let kind = FileKind::from_file_type(Some(metadata.file_type()));
+                    let previous = old.first().filter(|r| r.path.as_os_str().is_empty());
+                    refresh.visit(PathBuf::new(), kind, &metadata, previous);
+                }
+                Err(_) => refresh.remove(Path::new("")),
+            }
+            let mut records = refresh.records;
+            records.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+            root.records = records;
+        }
+        changes.added.sort_unstable();
+        changes.removed.sort_unstable();
+        changes.changed.sort_unstable();
+        self.built = started;
+        changes
+    }
+
+    /// The default index file, `rust_search/index.db` in the cache directory of the
+    /// user, e.g. `~/.cache/rust_search/index.db` on Linux
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("rust_search").join("index.db"))
+    }
+
+    /// Read an index written by [`save`](Self::save)
+    /// ### Arguments
+    /// * `path` - The index file.
+    /// ### Errors
+    /// Returns an error if the file can't be read or is not an index of this version.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    /// Write the index to a file, creating its directory if needed
+    ///
+    /// The index is written to a temporary file first and renamed over `path`, so
+    /// searches never see a partly written index.
+    /// ### Arguments
+    /// * `path` - The index file.
+    /// ### Errors
+    /// Returns an error if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        if !dir.as_os_str().is_empty() {
+            fs::create_dir_all(dir)?;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let tmp_path = dir.join(format!(".{name}.{}.tmp", std::process::id()));
+        let written =
+            fs::write(&tmp_path, self.encode()).and_then(|()| fs::rename(&tmp_path, path));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        written
+    }
+
+    /// The absolute locations in the index
+    pub fn roots(&self) -> impl Iterator<Item = &Path> {
+        self.roots.iter().map(|root| root.path.as_path())
+    }
+
+    /// The number of paths in the index, locations included
+    pub fn len(&self) -> usize {
+        self.roots.iter().map(|root| root.records.len()).sum()
+    }
+
+    /// Whether the index holds no paths
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// When the disk was walked to build the index, or last checked by [`refresh`](Self::refresh)
+    pub const fn built(&self) -> SystemTime {
+        self.built
+    }
+
+    /// How long ago the index was built, zero if the clock went back since
+    pub fn age(&self) -> Duration {
+        self.built.elapsed().unwrap_or_default()
+    }
+
+    /// Whether `location` is one of the indexed locations or below one
+    pub fn covers(&self, location: impl AsRef<Path>) -> bool {
+        std::path::absolute(replace_tilde_with_home_dir(location))
+            .is_ok_and(|location| self.root_of(&location).is_some())
+    }
+
+    /// Answer the search from the index, like [`SearchBuilder::build`]
+    pub fn search(&self, options: &SearchBuilder) -> Search {
+        Search::from_paths(Search::from_results(self.find(options, false, false)))
+    }
+
+    /// Answer the search from the index, like [`SearchBuilder::build_paths`]
+    pub fn search_paths(&self, options: &SearchBuilder) -> Search<PathBuf> {
+        Search::from_results(self.find(options, true, false))
+    }
+
+    /// Answer the search from the index, like [`SearchBuilder::build_results`]
+    ///
+    /// The size and modification time of the results are the ones recorded in the index.
+    pub fn search_results(&self, options: &SearchBuilder) -> Search<SearchResult> {
+        self.find(options, true, true)
+    }
+
+    /// The most specific indexed location containing the absolute `location`.
+    fn root_of(&self, location: &Path) -> Option<&IndexRoot> {
+        self.roots
+            .iter()
+            .filter(|root| location.starts_with(&root.path))
+            .max_by_key(|root| root.path.components().count())
+    }
+
+    /// Match the recorded paths against the options, in the order of a depth-first walk.
+    fn find(
+        &self,
+        options: &SearchBuilder,
+        match_bytes: bool,
+        with_metadata: bool,
+    ) -> Search<SearchResult> {
+        let matcher = Matcher::new(options, false, match_bytes);
+        let content = options
+            .content
+            .as_ref()
+            .map(|pattern| ContentMatcher::new(pattern, options));
+        let path_style = options.path_style.resolve();
+        let limit = options.limit.unwrap_or(usize::MAX);
+
+        let mut results = Vec::new();
+        for location in options.locations() {
+            let Ok(absolute) = std::path::absolute(location) else {
+                continue;
+            };
+            let Some(root) = self.root_of(&absolute) else {
+                continue;
+            };
+            let below = absolute
+                .strip_prefix(&root.path)
+                .unwrap_or_else(|_| Path::new(""));
+            let location: Arc<Path> = Arc::from(location);
+            let start = root
+                .records
+                .partition_point(|record| record.path.as_path() < below);
+
+            // A hidden or filtered directory is skipped with everything inside it.
+            let mut skipped: Option<&Path> = None;
+            for record in root.records[start..]
+                .iter()
+                .take_while(|record| record.path.starts_with(below))
+            {
+                if results.len() >= limit {
+                    return Search::from_vec(results);
+                }
+                let relative = record.path.strip_prefix(below).unwrap_or(&record.path);
+                if skipped.is_some_and(|dir| relative.starts_with(dir)) {
+                    continue;
+                }
+                let depth = relative.components().count();
+                if options.depth.is_some_and(|max| depth > max) {
+                    continue;
+                }
+                let path = if depth == 0 {
+                    location.to_path_buf()
+                } else {
+                    location.join(relative)
+                };
+                if depth > 0
+                    && ((!options.hidden && is_hidden(relative))
+                        || !passes_filters(&options.filters, &path, record))
+                {
+                    skipped = Some(relative);
+                    continue;
+                }
+
+                if !matcher.is_match_path(&path, record.kind == FileKind::Dir)
+                    || options
+                        .query
+                        .as_ref()
+                        .is_some_and(|q| !q.matches_with(&path, &|| Some(record)))
+                {
+                    continue;
+                }
+                let lines = match &content {
+                    Some(_) if record.kind == FileKind::Dir => continue,
+                    Some(content) => match content.search_file(&path) {
+                        Some(lines) => lines,
+                        None => continue,
+                    },
+                    None => Vec::new(),
+                };
+                let (path, found) = path_style.apply_keeping(path, &location);
+                let mut result =
+                    SearchResult::new(path, Arc::clone(&location), lines, record.kind, depth)
+                        .found_at(found);
+                if with_metadata {
+                    result = result.with_size_and_modified(record.size, record.modified);
+                }
+                results.push(result);
+            }
+        }
+        results.truncate(limit);
+        Search::from_vec(results)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        write_time(&mut out, Some(self.built));
+        write_varint(&mut out, self.roots.len() as u64);
+        for root in &self.roots {
+            write_bytes(&mut out, &path_to_bytes(&root.path));
+            write_varint(&mut out, root.records.len() as u64);
+            // Sorted paths share long prefixes, so only the differing end of each is stored.
+            let mut previous: Vec<u8> = Vec::new();
+            for record in &root.records {
+                let path = path_to_bytes(&record.path);
+                let shared = previous
+                    .iter()
+                    .zip(path.iter())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                write_varint(&mut out, shared as u64);
+                write_bytes(&mut out, &path[shared..]);
+                out.push(kind_to_byte(record.kind));
+                write_varint(&mut out, record.size);
+                write_time(&mut out, record.modified);
+                write_time(&mut out, record.created);
+                previous = path;
+            }
+        }
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not an index file"));
+        }
+        if reader.byte()? != VERSION {
+            return Err(invalid("unsupported index version"));
+        }
+        let built = reader
+            .time()?
+            .ok_or_else(|| invalid("missing build time"))?;
+        let mut roots = Vec::new();
+        for _ in 0..reader.varint()? {
+            let path = path_from_bytes(reader.bytes()?.to_vec());
+            let count = reader.varint()?;
+            let mut records = Vec::new();
+            let mut previous: Vec<u8> = Vec::new();
+            for _ in 0..count {
+                let shared = usize::try_from(reader.varint()?)
+                    .ok()
+                    .filter(|&shared| shared <= previous.len())
+                    .ok_or_else(|| invalid("corrupt path"))?;
+                previous.truncate(shared);
+                previous.extend_from_slice(reader.bytes()?);
+                records.push(Record {
+                    path: path_from_bytes(previous.clone()),
+                    kind: kind_from_byte(reader.byte()?)?,
+                    size: reader.varint()?,
+                    modified: reader.time()?,
+                    created: reader.time()?,
+                });
+            }
+            roots.push(IndexRoot { path, records });
+        }
+        if reader.pos != bytes.len() {
+            return Err(invalid("trailing data"));
+        }
+        Ok(Self { built, roots })
+    }
+}
+
+/// The paths that changed on the disk, found by [`Index::refresh`]
+///
+/// All paths are absolute. Directories are not reported as changed, their added and
+/// removed contents are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changeset {
+    added: Vec<PathBuf>,
+    removed: Vec<PathBuf>,
+    changed: Vec<PathBuf>,
+    dirs_read: usize,
+}
+
+impl Changeset {
+    /// The paths that are new, sorted
+    pub fn added(&self) -> &[PathBuf] {
+        &self.added
+    }
+
+    /// The paths that are gone, sorted
+    pub fn removed(&self) -> &[PathBuf] {
+        &self.removed
+    }
+
+    /// The paths with a different kind, size or modification or creation time, sorted
+    pub fn changed(&self) -> &[PathBuf] {
+        &self.changed
+    }
+
+    /// Whether nothing changed
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// How many directories had to be read, the others were unchanged
+    pub const fn dirs_read(&self) -> usize {
+        self.dirs_read
+    }
+}
+
+/// Rebuilds the records of one indexed location from its old records and the disk.
+struct Refresh<'a> {
+    root: &'a Path,
+    old: &'a [Record],
+    records: Vec<Record>,
+    changes: &'a mut Changeset,
+}
+
+impl<'a> Refresh<'a> {
+    /// The old records of `path` and everything below it.
+    fn subtree(&self, path: &Path) -> &'a [Record] {
+        let old = self.old;
+        let start = old.partition_point(|record| record.path.as_path() < path);
+        let len = old[start..].partition_point(|record| record.path.starts_with(path));
+        &old[start..start + len]
+    }
+
+    /// The old records directly inside the directory `path`.
+    fn children(&self, path: &Path) -> Vec<&'a Record> {
+        let mut subtree = self.subtree(path);
+        if subtree.first().is_some_and(|record| record.path == path) {
+            subtree = &subtree[1..];
+        }
+        let mut children = Vec::new();
+        while let Some(child) = subtree.first() {
+            children.push(child);
+            let len = subtree.partition_point(|record| record.path.starts_with(&child.path));
+            subtree = &subtree[len..];
+        }
+        children
+    }
+
+    /// Record `path` as found on the disk and compare it to its old record.
+    fn visit(&mut self, path: PathBuf, kind: FileKind, metadata: &Metadata, old: Option<&Record>) {
+        let record = Record::new(path, kind, metadata);
+        match old {
+            None => {
+                self.changes.added.push(self.root.join(&record.path));
+                if kind == FileKind::Dir {
+                    self.add_below(&record.path);
+                }
+                self.records.push(record);
+            }
+            Some(old) if old.kind != kind => {
+                self.changes.changed.push(self.root.join(&record.path));
+                for removed in &self.subtree(&old.path)[1..] {
+                    self.changes.removed.push(self.root.join(&removed.path));
+                }
+                if kind == FileKind::Dir {
+                    self.add_below(&record.path);
+                }
+                self.records.push(record);
+            }
+            Some(old) if kind == FileKind::Dir => {
+                let unchanged = record.modified.is_some() && record.modified == old.modified;
+                let path = record.path.clone();
+                self.records.push(record);
+                if unchanged {
+                    self.keep(&path);
+                } else {
+                    self.read(&path);
+                }
+            }
+            Some(old) => {
+                if *old != record {
+                    self.changes.changed.push(self.root.join(&record.path));
+                }
+                self.records.push(record);
+            }
+        }
+    }
+
+    /// Keep the recorded contents of the unchanged directory `path`, checking the
+    /// directories inside it.
+    fn keep(&mut self, path: &Path) {
+        for child in self.children(path) {
+            if child.kind != FileKind::Dir {
+                self.records.push(child.clone());
+                continue;
+            }
+            match fs::symlink_metadata(self.root.join(&child.path)) {
+                Ok(metadata) => {
+                    let kind = FileKind::from_file_type(Some(metadata.file_type()));
+                    self.visit(child.path.clone(), kind, &metadata, Some(child));
+                }
+                Err(_) => self.remove(&child.path),
+            }
+        }
+    }
+
+    /// Read the changed directory `path` again and compare its contents to the old ones.
+    fn read(&mut self, path: &Path) {
+        self.changes.dirs_read += 1;
+        let mut old: BTreeMap<&Path, &Record> = self
+            .children(path)
+            .into_iter()
+            .map(|record| (record.path.as_path(), record))
+            .collect();
+        for entry in walk(&self.root.join(path), Some(1)).build().flatten() {
+            if entry.depth() == 0 {
+                continue;
+            }
+            let (Ok(metadata), Ok(child)) =
+                (entry.metadata(), entry.path().strip_prefix(self.root))
+            else {
+                continue;
+            };
+            let kind = FileKind::from_file_type(entry.file_type());
+            let previous = old.remove(child);
+            self.visit(child.to_path_buf(), kind, &metadata, previous);
+        }
+        for removed in old.into_keys() {
+            self.remove(removed);
+        }
+    }
+
+    /// Record everything below the new directory `path` as added.
+    fn add_below(&mut self, path: &Path) {
+        self.changes.dirs_read += 1;
+        for entry in walk(&self.root.join(path), None).build().flatten() {
+            if entry.depth() == 0 {
+                continue;
+            }
+            let (Ok(metadata), Ok(child)) =
+                (entry.metadata(), entry.path().strip_prefix(self.root))
+            else {
+                continue;
+            };
+            let kind = FileKind::from_file_type(entry.file_type());
+            if kind == FileKind::Dir {
+                self.changes.dirs_read += 1;
+            }
+            self.changes.added.push(entry.path().to_path_buf());
+            self.records
+                .push(Record::new(child.to_path_buf(), kind, &metadata));
+        }
+    }
+
+    /// Record `path` and everything below it as removed.
+    fn remove(&mut self, path: &Path) {
+        for removed in self.subtree(path) {
+            self.changes.removed.push(self.root.join(&removed.path));
+        }
+    }
+}
+
+/// A walker over `path` that sees what the index records: hidden files, but no
+/// gitignored ones.
+fn walk(path: &Path, max_depth: Option<usize>) -> WalkBuilder {
+    let mut walker = WalkBuilder::new(path);
+    walker.hidden(false).git_ignore(true).max_depth(max_depth);
+    walker
+}
+
+/// Whether the last component of `path` is a hidden file or directory.
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+}
+
+/// Check the filters against the recorded metadata, reading the entry from the disk
+/// only for custom filters.
+fn passes_filters(filters: &[FilterType], path: &Path, record: &Record) -> bool {
+    let mut entry: Option<Option<DirEntry>> = None;
+    filters.iter().all(|filter| {
+        filter.apply_stat(record).unwrap_or_else(|| {
+            entry
+                .get_or_insert_with(|| disk_entry(path))
+                .as_ref()
+                .is_some_and(|entry| filter.apply(entry))
+        })
+    })
+}
+
+/// The entry of `path` as the walker would have found it.
+fn disk_entry(path: &Path) -> Option<DirEntry> {
+    WalkBuilder::new(path)
+        .standard_filters(false)
+        .max_depth(Some(0))
+        .build()
+        .next()?
+        .ok()
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, format!("invalid index: {message}"))
+}
+
+const fn kind_to_byte(kind: FileKind) -> u8 {
+    match kind {
+        FileKind::File => 0,
+        FileKind::Dir => 1,
+        FileKind::Symlink => 2,
+        FileKind::Other => 3,
+    }
+}
+
+fn kind_from_byte(byte: u8) -> io::Result<FileKind> {
+    match byte {
+        0 => Ok(FileKind::File),
+        1 => Ok(FileKind::Dir),
+        2 => Ok(FileKind::Symlink),
+        3 => Ok(FileKind::Other),
+        _ => Err(invalid("unknown file kind")),
+    }
+}
+
+/// Write `value` in 7-bit groups, least significant first, the high bit marking that more follow.
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Write a time as whole seconds since the epoch, zigzag encoded to allow earlier times,
+/// and nanoseconds, preceded by whether it is known.
+fn write_time(out: &mut Vec<u8>, time: Option<SystemTime>) {
+    let Some(time) = time else {
+        out.push(0);
+        return;
+    };
+    out.push(1);
+    let (secs, nanos) = epoch_parts(time);
+    write_varint(out, ((secs << 1) ^ (secs >> 63)) as u64);
+    write_varint(out, u64::from(nanos));
+}
+
+/// Reads the parts of an index file, failing on truncated data.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("number too large"))
+    }
+
+    fn bytes(&mut self) -> io::Result<&'a [u8]> {
+        let len = usize::try_from(self.varint()?).map_err(|_| invalid("length too large"))?;
+        self.take(len)
+    }
+
+    fn time(&mut self) -> io::Result<Option<SystemTime>> {
+        if self.byte()? == 0 {
+            return Ok(None);
+        }
+        let zigzag = self.varint()?;
+        let magnitude = i64::try_from(zigzag >> 1).unwrap_or(i64::MAX);
+        let secs = if zigzag & 1 == 0 {
+            magnitude
+        } else {
+            !magnitude
+        };
+        let nanos = u32::try_from(self.varint()?).map_err(|_| invalid("time out of range"))?;
+        from_epoch_parts(secs, nanos)
+            .map(Some)
+            .ok_or_else(|| invalid("time out of range"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn varint_round_trips() {
+        for value in [0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let mut out = Vec::new();
+            write_varint(&mut out, value);
+            let mut reader = Reader {
+                bytes: &out,
+                pos: 0,
+            };
+            assert_eq!(reader.varint().unwrap(), value);
+            assert_eq!(reader.pos, out.len());
+        }
+    }
+
+    #[test]
+    fn time_round_trips_before_and_after_the_epoch() {
+        for time in [
+            None,
+            Some(UNIX_EPOCH),
+            Some(UNIX_EPOCH + Duration::new(1_706_691_907, 123_456_789)),
+            Some(UNIX_EPOCH - Duration::new(86_400, 250)),
+        ] {
+            let mut out = Vec::new();
+            write_time(&mut out, time);
+            let mut reader = Reader {
+                bytes: &out,
+                pos: 0,
+            };
+            assert_eq!(reader.time().unwrap(), time);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_truncated_files() {
+        let index = Index {
+            built: UNIX_EPOCH,
+            roots: vec![IndexRoot {
+                path: PathBuf::from("/data"),
+                records: vec![Record {
+                    path: PathBuf::from("notes.txt"),
+                    kind: FileKind::File,
+                    size: 42,
+                    modified: Some(UNIX_EPOCH),
+                    created: None,
+                }],
+            }],
+        };
+        let bytes = index.encode();
+        assert_eq!(Index::decode(&bytes).unwrap(), index);
+        assert!(Index::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Index::decode(b"not an index").is_err());
+    }
+}
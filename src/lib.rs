@@ -12,15 +12,66 @@
 // Use the readme as the crate documentation
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "archives")]
+mod archive;
 mod builder;
+mod checksum;
+mod content;
+#[cfg(feature = "compressed")]
+mod decompress;
+mod duplicates;
+mod exec;
+mod fileops;
 mod filter;
+mod format;
+mod index;
+mod output;
+mod query;
+mod rename;
+mod replace;
+mod result;
+mod scorer;
 mod search;
+mod snapshot;
+#[cfg(feature = "serde")]
+mod spec;
+mod stats;
+mod usage;
 mod utils;
+mod vfs;
+mod watch;
 
 pub use builder::SearchBuilder;
+pub use checksum::{hash_results, HashAlgorithm, Manifest, Verification};
+pub use content::LineMatch;
+pub use duplicates::{find_duplicates, DuplicateGroup, Duplicates, HardLinks};
+pub use exec::{CommandOutput, Exec, ExecError, ExecReport};
+pub use fileops::{Conflict, FileOpEntry, FileOpStatus, FileOps, FileOpsReport, Layout};
 pub use filter::{FileSize, FilterExt, FilterFn};
+pub use format::{FormatError, ResultFormat};
+pub use index::{Changeset, Index};
 
 // export this in order to use it with custom filter functions
 pub use ignore::DirEntry;
+pub use output::{write_csv, Column};
+#[cfg(feature = "serde")]
+pub use output::{write_json, write_ndjson};
+pub use query::{Query, QueryError};
+pub use rename::{RenameConflict, RenameConflictKind, RenameError, RenamePlan, Renamer};
+pub use replace::{FileReplacement, ReplaceReport, Replacer};
+pub use result::{FileKind, PathStyle, SearchResult};
+pub use scorer::{
+    Acronym, DamerauLevenshtein, FzfV2, JaroWinkler, Levenshtein, ScoreTarget, Scorer, SorensenDice,
+};
 pub use search::Search;
-pub use utils::similarity_sort;
+pub use snapshot::{Snapshot, SnapshotDiff, SnapshotEntry};
+#[cfg(feature = "serde")]
+pub use spec::{SavedSearches, SearchSpec, SpecError};
+pub use stats::SearchStats;
+pub use usage::{DirUsage, DiskUsage, Usage};
+pub use utils::{
+    similarity_sort, similarity_sort_by_key, similarity_sort_by_key_with, similarity_sort_results,
+    similarity_sort_with,
+};
+pub use vfs::{DiskFs, FileSystem, FsMetadata, MemoryFs};
+pub use watch::{Watch, WatchBackend, WatchEvent, DEFAULT_POLL_INTERVAL};
@@ -0,0 +1,218 @@
+use std::{
+    borrow::{Borrow, Cow},
+    io::{self, Write},
+};
+
+use crate::{utils::format_rfc3339, SearchResult};
+
+/// A column of the CSV output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// The path that was found
+    Path,
+    /// The location the path was found in
+    Root,
+    /// The size in bytes
+    Size,
+    /// The modification time as an RFC 3339 timestamp in UTC
+    Mtime,
+    /// `file`, `dir`, `symlink` or `other`
+    Kind,
+    /// How many directories below its location the path was found
+    Depth,
+    /// The similarity score, if the results were sorted by similarity
+    Score,
+}
+
+impl Column {
+    /// The columns written when none are selected, in this order
+    pub const DEFAULT: [Self; 6] = [
+        Self::Path,
+        Self::Size,
+        Self::Mtime,
+        Self::Kind,
+        Self::Depth,
+        Self::Score,
+    ];
+
+    /// The column with the given header name, e.g. `"size"`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "path" => Some(Self::Path),
+            "root" => Some(Self::Root),
+            "size" => Some(Self::Size),
+            "mtime" | "modified" => Some(Self::Mtime),
+            "kind" => Some(Self::Kind),
+            "depth" => Some(Self::Depth),
+            "score" => Some(Self::Score),
+            _ => None,
+        }
+    }
+
+    /// The header name of the column
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Path => "path",
+            Self::Root => "root",
+            Self::Size => "size",
+            Self::Mtime => "mtime",
+            Self::Kind => "kind",
+            Self::Depth => "depth",
+            Self::Score => "score",
+        }
+    }
+
+    /// The value of the column for `result`, empty when it is unknown.
+    fn value(self, result: &SearchResult) -> Cow<'_, str> {
+        match self {
+            Self::Path => result.path().to_string_lossy(),
+            Self::Root => result.root().to_string_lossy(),
+            Self::Size => result
+                .size()
+                .map_or(Cow::Borrowed(""), |s| s.to_string().into()),
+            Self::Mtime => result
+                .modified()
+                .map_or(Cow::Borrowed(""), |t| format_rfc3339(t).into()),
+            Self::Kind => result.kind().as_str().into(),
+            Self::Depth => result.depth().to_string().into(),
+            Self::Score => result
+                .score()
+                .map_or(Cow::Borrowed(""), |s| s.to_string().into()),
+        }
+    }
+}
+
+/// Quote `field` if it contains a separator, quote or line break, doubling the quotes inside.
+fn escape_csv(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Write the results as CSV with a header row, quoting fields as described in RFC 4180
+///
+/// ### Arguments
+/// * `writer` - where to write to, wrap it in a [`BufWriter`](std::io::BufWriter) if it's unbuffered
+/// * `results` - the results to write, owned or borrowed
+/// * `columns` - the columns to write in order, [`Column::DEFAULT`] for all but the root
+/// ### Examples
+/// ```rust
+/// use rust_search::{write_csv, Column, SearchBuilder};
+///
+/// let results = SearchBuilder::default().location("src").ext("rs").build_results();
+/// let mut csv = Vec::new();
+/// write_csv(&mut csv, results, &[Column::Path, Column::Size]).unwrap();
+/// assert!(csv.starts_with(b"path,size\n"));
+/// ```
+/// ### Errors
+/// Returns the first error of the writer.
+pub fn write_csv<W, I>(mut writer: W, results: I, columns: &[Column]) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: Borrow<SearchResult>,
+{
+    let header: Vec<&str> = columns.iter().map(|column| column.name()).collect();
+    writeln!(writer, "{}", header.join(","))?;
+    for result in results {
+        let result = result.borrow();
+        for (i, column) in columns.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
+            }
+            writer.write_all(escape_csv(&column.value(result)).as_bytes())?;
+        }
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Write every result as a JSON object on its own line, flushing after each one so
+/// consumers see the results as they are found
+///
+/// ### Arguments
+/// * `writer` - where to write to
+/// * `results` - the results to write, owned or borrowed
+/// ### Examples
+/// ```rust
+/// use rust_search::{write_ndjson, SearchBuilder};
+///
+/// let results = SearchBuilder::default().location("src").ext("rs").build_results();
+/// write_ndjson(std::io::stdout().lock(), results).unwrap();
+/// ```
+/// ### Errors
+/// Returns the first error of the writer.
+#[cfg(feature = "serde")]
+pub fn write_ndjson<W, I>(mut writer: W, results: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: Borrow<SearchResult>,
+{
+    for result in results {
+        serde_json::to_writer(&mut writer, result.borrow())?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Write the results as a single JSON array, one object per line
+///
+/// ### Arguments
+/// * `writer` - where to write to, wrap it in a [`BufWriter`](std::io::BufWriter) if it's unbuffered
+/// * `results` - the results to write, owned or borrowed
+/// ### Examples
+/// ```rust
+/// use rust_search::{write_json, SearchBuilder};
+///
+/// let results = SearchBuilder::default().location("src").ext("rs").build_results();
+/// let mut json = Vec::new();
+/// write_json(&mut json, results).unwrap();
+/// assert!(json.starts_with(b"["));
+/// ```
+/// ### Errors
+/// Returns the first error of the writer.
+#[cfg(feature = "serde")]
+pub fn write_json<W, I>(mut writer: W, results: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: Borrow<SearchResult>,
+{
+    writer.write_all(b"[")?;
+    for (i, result) in results.into_iter().enumerate() {
+        writer.write_all(if i == 0 { b"\n" } else { b",\n" })?;
+        serde_json::to_writer(&mut writer, result.borrow())?;
+    }
+    writer.write_all(b"\n]\n")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_csv_plain() {
+        assert_eq!(escape_csv("src/main.rs"), "src/main.rs");
+    }
+
+    #[test]
+    fn escape_csv_quotes_separators_and_quotes() {
+        assert_eq!(escape_csv("a,b.txt"), "\"a,b.txt\"");
+        assert_eq!(escape_csv("say \"hi\".txt"), "\"say \"\"hi\"\".txt\"");
+        assert_eq!(escape_csv("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn column_parse_round_trips() {
+        for column in Column::DEFAULT {
+            assert_eq!(Column::parse(column.name()), Some(column));
+        }
+        assert_eq!(Column::parse(" Root "), Some(Column::Root));
+        assert_eq!(Column::parse("owner"), None);
+    }
+}
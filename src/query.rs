@@ -0,0 +1,700 @@
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fmt,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use ignore::DirEntry;
+
+use crate::{filter::Stat, utils::days_from_civil, FileSize, SearchBuilder};
+
+/// A one-line search, parsed from text typed by a user
+///
+/// Terms are separated by whitespace and must all match. A bare word matches file names,
+/// other terms are written as `field:value`:
+///
+/// | Term | Matches |
+/// |---|---|
+/// | `report`, `name:report` | file names containing `report`, ignoring case |
+/// | `ext:pdf`, `ext:pdf,docx` | files with one of the extensions |
+/// | `path:archive` | paths containing `archive`, ignoring case |
+/// | `size:>2MB`, `size:<10k`, `size:=0` | files by size, units `B`, `KB`, `MB`, `GB`, `TB` |
+/// | `modified:<30d`, `modified:>2024-01-31` | modified less than 30 days ago, or after a date (UTC) |
+/// | `created:>1w` | created more than a week ago, units `s`, `m`, `h`, `d`, `w`, `y` |
+/// | `depth:2`, `limit:100`, `hidden:yes` | the search settings of the same name |
+///
+/// Values with spaces can be quoted: `name:"annual report"`. A term is negated with `-`,
+/// alternatives are joined with `OR` (or `|`) and grouped with parentheses:
+/// `(name:report OR name:summary) -path:archive`.
+///
+/// Simple terms are mapped onto the [`SearchBuilder`] settings (`search_input`, `ext` and
+/// `depth`), the rest is checked on every entry matching them. Size and time terms never
+/// leave out a directory's contents, only the entries that don't match.
+/// ### Examples
+/// ```rust
+/// use rust_search::Query;
+///
+/// let query = Query::parse("name:report ext:pdf,docx size:>2MB modified:<30d -path:archive").unwrap();
+/// let search: Vec<String> = query
+///     .into_builder()
+///     .location("~/Documents")
+///     .build()
+///     .collect();
+///
+/// let error = Query::parse("size:>2XB").unwrap_err();
+/// assert_eq!(error.position(), 7);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    /// The terms that must all match, settings excluded.
+    terms: Vec<Expr>,
+    depth: Option<usize>,
+    limit: Option<usize>,
+    hidden: bool,
+}
+
+/// A predicate on an entry of the walk, checked after the name and extension matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    And(Vec<Self>),
+    Or(Vec<Self>),
+    Not(Box<Self>),
+    /// Lowercased text the file name must contain.
+    Name(String),
+    /// Lowercased extensions, one of which the file must have.
+    Ext(Vec<String>),
+    /// Lowercased text the path must contain.
+    Path(String),
+    Size(Ordering, u64),
+    Modified(Ordering, SystemTime),
+    Created(Ordering, SystemTime),
+}
+
+impl Expr {
+    /// Extensions, one of which the file must have, with or without their dot.
+    #[cfg(feature = "serde")]
+    pub(crate) fn extensions(exts: &[String]) -> Self {
+        Self::Ext(
+            exts.iter()
+                .map(|e| e.trim_start_matches('.').to_lowercase())
+                .collect(),
+        )
+    }
+
+    /// A size term from a condition like `>2MB`, the value of a `size:` term.
+    #[cfg(feature = "serde")]
+    pub(crate) fn size(condition: &str) -> Result<Self, QueryError> {
+        let (cmp, size) = split_comparison(condition);
+        Ok(Self::Size(
+            cmp,
+            parse_size(size, condition.len() - size.len())?,
+        ))
+    }
+
+    /// A modification time term from a condition like `<30d`, the value of a `modified:` term.
+    #[cfg(feature = "serde")]
+    pub(crate) fn modified(condition: &str) -> Result<Self, QueryError> {
+        let (cmp, time) = parse_time(condition, 0, SystemTime::now())?;
+        Ok(Self::Modified(cmp, time))
+    }
+
+    /// A creation time term from a condition like `>1w`, the value of a `created:` term.
+    #[cfg(feature = "serde")]
+    pub(crate) fn created(condition: &str) -> Result<Self, QueryError> {
+        let (cmp, time) = parse_time(condition, 0, SystemTime::now())?;
+        Ok(Self::Created(cmp, time))
+    }
+
+    pub fn matches(&self, entry: &DirEntry) -> bool {
+        self.matches_with(entry.path(), &|| entry.metadata().ok())
+    }
+
+    /// Check the expression against `path`, reading its metadata with `stat` only when needed.
+    pub fn matches_with<S: Stat>(&self, path: &Path, stat: &impl Fn() -> Option<S>) -> bool {
+        match self {
+            Self::And(exprs) => exprs.iter().all(|e| e.matches_with(path, stat)),
+            Self::Or(exprs) => exprs.iter().any(|e| e.matches_with(path, stat)),
+            Self::Not(expr) => !expr.matches_with(path, stat),
+            Self::Name(text) => path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(text)),
+            Self::Ext(exts) => path.extension().is_some_and(|ext| {
+                let ext = ext.to_string_lossy();
+                exts.iter().any(|e| e.eq_ignore_ascii_case(&ext))
+            }),
+            Self::Path(text) => path.to_string_lossy().to_lowercase().contains(text),
+            Self::Size(cmp, size) => stat().is_some_and(|m| m.len().cmp(size) == *cmp),
+            Self::Modified(cmp, time) => stat()
+                .and_then(|m| m.modified())
+                .is_some_and(|t| t.cmp(time) == *cmp),
+            Self::Created(cmp, time) => stat()
+                .and_then(|m| m.created())
+                .is_some_and(|t| t.cmp(time) == *cmp),
+        }
+    }
+}
+
+impl Query {
+    /// Parse a query, see [`Query`] for the syntax
+    /// ### Errors
+    /// Returns a [`QueryError`] with the byte offset of the offending part of the input.
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        Parser::new(input, SystemTime::now())?.parse()
+    }
+
+    /// Apply the query to a new [`SearchBuilder`] with default settings
+    pub fn into_builder(self) -> SearchBuilder {
+        self.apply(SearchBuilder::default())
+    }
+
+    /// Apply the query to an existing [`SearchBuilder`], keeping its location and settings
+    /// ### Arguments
+    /// * `builder` - The builder to add the terms and settings of the query to.
+    pub fn apply(self, mut builder: SearchBuilder) -> SearchBuilder {
+        if let Some(depth) = self.depth {
+            builder = builder.depth(depth);
+        }
+        if let Some(limit) = self.limit {
+            builder = builder.limit(limit);
+        }
+        if self.hidden {
+            builder = builder.hidden();
+        }
+
+        let mut name_set = builder.search_input.is_some();
+        let mut rest = Vec::new();
+        for term in self.terms {
+            builder = match term {
+                Expr::Name(name) if !name_set => {
+                    name_set = true;
+                    builder.search_input(regex::escape(&name)).ignore_case()
+                }
+                term => {
+                    rest.push(term);
+                    builder
+                }
+            };
+        }
+        match rest.len() {
+            0 => builder,
+            1 => builder.match_expr(rest.remove(0)),
+            _ => builder.match_expr(Expr::And(rest)),
+        }
+    }
+}
+
+/// An error in the syntax of a [`Query`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    position: usize,
+    message: String,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+
+    /// The byte offset in the query where the error was found
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    /// What is wrong with the query
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Error for QueryError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Minus,
+    Or,
+    /// A bare word or a `field:value` term, with quotes removed.
+    Word {
+        text: String,
+        quoted: bool,
+    },
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(pos, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '|' => {
+                chars.next();
+                let token = match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Or,
+                };
+                tokens.push((pos, token));
+            }
+            // Dashes inside a word are part of it, only a leading one negates.
+            '-' => {
+                chars.next();
+                tokens.push((pos, Token::Minus));
+            }
+            _ => {
+                // A word runs until whitespace or a parenthesis, quoted parts may contain both.
+                let mut text = String::new();
+                let mut quoted = false;
+                while let Some(&(quote_pos, c)) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '|') {
+                        break;
+                    }
+                    chars.next();
+                    if c != '"' {
+                        text.push(c);
+                        continue;
+                    }
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some((_, '"')) => break,
+                            Some((_, '\\')) => match chars.next() {
+                                Some((_, c)) => text.push(c),
+                                None => return Err(QueryError::new(quote_pos, "unclosed quote")),
+                            },
+                            Some((_, c)) => text.push(c),
+                            None => return Err(QueryError::new(quote_pos, "unclosed quote")),
+                        }
+                    }
+                }
+                let token = if text == "OR" && !quoted {
+                    Token::Or
+                } else {
+                    Token::Word { text, quoted }
+                };
+                tokens.push((pos, token));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    now: SystemTime,
+    query: Query,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str, now: SystemTime) -> Result<Self, QueryError> {
+        Ok(Self {
+            input,
+            tokens: tokenize(input)?,
+            next: 0,
+            now,
+            query: Query {
+                terms: Vec::new(),
+                depth: None,
+                limit: None,
+                hidden: false,
+            },
+        })
+    }
+
+    fn parse(mut self) -> Result<Query, QueryError> {
+        // The top level is parsed by hand so settings like `depth:` can be picked out.
+        let mut alternatives = vec![self.parse_and(true)?];
+        while self.eat(&Token::Or) {
+            alternatives.push(self.parse_and(false)?);
+        }
+        if let Some((pos, _)) = self.peek() {
+            return Err(QueryError::new(pos, "unmatched `)`"));
+        }
+        if alternatives.len() == 1 {
+            self.query.terms = alternatives.remove(0);
+        } else {
+            let alternatives = alternatives.into_iter().map(and).collect();
+            self.query.terms = vec![Expr::Or(alternatives)];
+        }
+        Ok(self.query)
+    }
+
+    fn peek(&self) -> Option<(usize, &Token)> {
+        self.tokens.get(self.next).map(|(pos, t)| (*pos, t))
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek().is_some_and(|(_, t)| t == token);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    const fn end(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Terms until the next `OR`, `)` or the end. Settings are only allowed at the top level.
+    fn parse_and(&mut self, top_level: bool) -> Result<Vec<Expr>, QueryError> {
+        let mut terms = Vec::new();
+        let start = self.peek().map_or_else(|| self.end(), |(pos, _)| pos);
+        while let Some((_, token)) = self.peek() {
+            if matches!(token, Token::Or | Token::Close) {
+                break;
+            }
+            if let Some(term) = self.parse_unary(top_level)? {
+                terms.push(term);
+            }
+        }
+        if terms.is_empty() && !(top_level && self.peek().is_none()) {
+            return Err(QueryError::new(start, "expected a search term"));
+        }
+        Ok(terms)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut alternatives = vec![and(self.parse_and(false)?)];
+        while self.eat(&Token::Or) {
+            alternatives.push(and(self.parse_and(false)?));
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            Expr::Or(alternatives)
+        })
+    }
+
+    fn parse_unary(&mut self, top_level: bool) -> Result<Option<Expr>, QueryError> {
+        let Some((pos, token)) = self.peek() else {
+            return Err(QueryError::new(self.end(), "expected a search term"));
+        };
+        match token.clone() {
+            Token::Minus => {
+                self.next += 1;
+                let expr = self
+                    .parse_unary(false)?
+                    .ok_or_else(|| QueryError::new(pos, "settings can't be negated"))?;
+                Ok(Some(Expr::Not(Box::new(expr))))
+            }
+            Token::Open => {
+                self.next += 1;
+                let expr = self.parse_or()?;
+                if !self.eat(&Token::Close) {
+                    return Err(QueryError::new(pos, "unclosed `(`"));
+                }
+                Ok(Some(expr))
+            }
+            Token::Word { text, quoted } => {
+                self.next += 1;
+                self.parse_term(pos, &text, quoted, top_level)
+            }
+            Token::Close | Token::Or => Err(QueryError::new(pos, "expected a search term")),
+        }
+    }
+
+    /// Parse a `field:value` term or a bare word, returns `None` for settings.
+    fn parse_term(
+        &mut self,
+        pos: usize,
+        text: &str,
+        quoted: bool,
+        top_level: bool,
+    ) -> Result<Option<Expr>, QueryError> {
+        let raw = &self.input[pos..];
+        // Only an unquoted field name counts, so `"a:b"` is a plain name.
+        let field = raw
+            .find(|c: char| c == ':' || c == '"' || c.is_whitespace())
+            .filter(|&i| raw[i..].starts_with(':'))
+            .map(|i| &raw[..i]);
+        let Some(field) = field else {
+            return Ok(Some(Expr::Name(text.to_lowercase())));
+        };
+        let value = &text[field.len() + 1..];
+        let value_pos = pos + field.len() + 1;
+        if value.is_empty() && !quoted {
+            return Err(QueryError::new(
+                value_pos,
+                format!("missing value for `{field}:`"),
+            ));
+        }
+
+        if matches!(field, "depth" | "limit" | "hidden") && !top_level {
+            return Err(QueryError::new(
+                pos,
+                format!("`{field}:` can't be negated, grouped or used with OR"),
+            ));
+        }
+        let expr = match field {
+            "name" => Expr::Name(value.to_lowercase()),
+            "path" => Expr::Path(value.to_lowercase()),
+            "ext" => Expr::Ext(
+                value
+                    .split(',')
+                    .map(|e| e.trim_start_matches('.').to_lowercase())
+                    .collect(),
+            ),
+            "size" => {
+                let (cmp, size) = split_comparison(value);
+                Expr::Size(cmp, parse_size(size, value_pos + value.len() - size.len())?)
+            }
+            "modified" | "mtime" => {
+                let (cmp, time) = parse_time(value, value_pos, self.now)?;
+                Expr::Modified(cmp, time)
+            }
+            "created" => {
+                let (cmp, time) = parse_time(value, value_pos, self.now)?;
+                Expr::Created(cmp, time)
+            }
+            "depth" => {
+                self.query.depth = Some(parse_number(value, value_pos)?);
+                return Ok(None);
+            }
+            "limit" => {
+                self.query.limit = Some(parse_number(value, value_pos)?);
+                return Ok(None);
+            }
+            "hidden" => {
+                self.query.hidden = match value {
+                    "yes" | "true" => true,
+                    "no" | "false" => false,
+                    _ => return Err(QueryError::new(value_pos, "expected `yes` or `no`")),
+                };
+                return Ok(None);
+            }
+            _ => return Err(QueryError::new(pos, format!("unknown field `{field}`"))),
+        };
+        Ok(Some(expr))
+    }
+}
+
+fn and(mut terms: Vec<Expr>) -> Expr {
+    if terms.len() == 1 {
+        terms.remove(0)
+    } else {
+        Expr::And(terms)
+    }
+}
+
+/// Parse an age like `<30d` or a date like `>2024-01-31`, ages counting back from `now`.
+///
+/// Ages compare how long ago, so `<30d` means after the time 30 days ago.
+fn parse_time(
+    value: &str,
+    pos: usize,
+    now: SystemTime,
+) -> Result<(Ordering, SystemTime), QueryError> {
+    let (cmp, rest) = split_comparison(value);
+    let pos = pos + value.len() - rest.len();
+    if let Some(date) = parse_date(rest, pos)? {
+        return Ok((cmp, date));
+    }
+    let age = parse_age(rest, pos)?;
+    let time = now
+        .checked_sub(age)
+        .ok_or_else(|| QueryError::new(pos, format!("age out of range: `{rest}`")))?;
+    Ok((cmp.reverse(), time))
+}
+
+fn split_comparison(value: &str) -> (Ordering, &str) {
+    match value.chars().next() {
+        Some('>') => (Ordering::Greater, &value[1..]),
+        Some('<') => (Ordering::Less, &value[1..]),
+        Some('=') => (Ordering::Equal, &value[1..]),
+        _ => (Ordering::Equal, value),
+    }
+}
+
+fn parse_number(value: &str, pos: usize) -> Result<usize, QueryError> {
+    value
+        .parse()
+        .map_err(|_| QueryError::new(pos, format!("expected a number, found `{value}`")))
+}
+
+/// Split `10.5MB` into `10.5` and `MB`.
+fn split_unit(value: &str) -> (&str, &str) {
+    let end = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    value.split_at(end)
+}
+
+pub fn parse_size(value: &str, pos: usize) -> Result<u64, QueryError> {
+    let (number, unit) = split_unit(value);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| QueryError::new(pos, format!("expected a size, found `{value}`")))?;
+    let size = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => FileSize::Byte(number as u64),
+        "k" | "kb" => FileSize::Kilobyte(number),
+        "m" | "mb" => FileSize::Megabyte(number),
+        "g" | "gb" => FileSize::Gigabyte(number),
+        "t" | "tb" => FileSize::Terabyte(number),
+        _ => {
+            let unit_pos = pos + value.len() - unit.len();
+            return Err(QueryError::new(
+                unit_pos,
+                format!("unknown size unit `{unit}`"),
+            ));
+        }
+    };
+    Ok(size.into())
+}
+
+fn parse_age(value: &str, pos: usize) -> Result<Duration, QueryError> {
+    let (number, unit) = split_unit(value);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| QueryError::new(pos, format!("expected an age or a date, found `{value}`")))?;
+    let seconds = match unit {
+        "s" => 1.0,
+        "m" | "min" => 60.0,
+        "h" => 3600.0,
+        "d" => 86400.0,
+        "w" => 7.0 * 86400.0,
+        "y" => 365.0 * 86400.0,
+        _ => {
+            let unit_pos = pos + value.len() - unit.len();
+            return Err(QueryError::new(
+                unit_pos,
+                format!("unknown time unit `{unit}`"),
+            ));
+        }
+    };
+    Duration::try_from_secs_f64(number * seconds)
+        .map_err(|_| QueryError::new(pos, format!("age out of range: `{value}`")))
+}
+
+/// Parse a `YYYY-MM-DD` date as midnight UTC, `None` if the value isn't a date.
+fn parse_date(value: &str, pos: usize) -> Result<Option<SystemTime>, QueryError> {
+    let mut parts = value.splitn(3, '-');
+    let mut next = || parts.next().and_then(|part| part.parse::<i64>().ok());
+    let (Some(year), Some(month), Some(day)) = (next(), next(), next()) else {
+        return Ok(None);
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Ok(None);
+    }
+    let out_of_range = || QueryError::new(pos, format!("date out of range: `{value}`"));
+    // A year that fits in the days of a year won't overflow the conversion to days.
+    let days = year
+        .checked_mul(366)
+        .map(|_| days_from_civil(year, month, day))
+        .ok_or_else(out_of_range)?;
+    let offset = Duration::from_secs(
+        days.unsigned_abs()
+            .checked_mul(86400)
+            .ok_or_else(out_of_range)?,
+    );
+    let date = if days < 0 {
+        SystemTime::UNIX_EPOCH.checked_sub(offset)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_add(offset)
+    };
+    date.map(Some).ok_or_else(out_of_range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<Query, QueryError> {
+        Parser::new(input, SystemTime::UNIX_EPOCH + Duration::from_hours(2400))?.parse()
+    }
+
+    #[test]
+    fn parses_terms_and_settings() {
+        let query = parse("report ext:pdf,docx size:>2MB depth:2 hidden:yes").unwrap();
+        assert_eq!(
+            query.terms,
+            vec![
+                Expr::Name("report".into()),
+                Expr::Ext(vec!["pdf".into(), "docx".into()]),
+                Expr::Size(Ordering::Greater, 2 * 1024 * 1024),
+            ]
+        );
+        assert_eq!(query.depth, Some(2));
+        assert!(query.hidden);
+    }
+
+    #[test]
+    fn parses_negation_groups_and_quotes() {
+        let query = parse(r#"-path:archive (name:"annual report" OR name:summary) | x"#).unwrap();
+        assert_eq!(
+            query.terms,
+            vec![Expr::Or(vec![
+                Expr::And(vec![
+                    Expr::Not(Box::new(Expr::Path("archive".into()))),
+                    Expr::Or(vec![
+                        Expr::Name("annual report".into()),
+                        Expr::Name("summary".into()),
+                    ]),
+                ]),
+                Expr::Name("x".into()),
+            ])]
+        );
+    }
+
+    #[test]
+    fn dash_inside_a_word_is_not_negation() {
+        let query = parse("my-file").unwrap();
+        assert_eq!(query.terms, vec![Expr::Name("my-file".into())]);
+    }
+
+    #[test]
+    fn ages_and_dates() {
+        let query = parse("modified:<30d created:>1970-01-02").unwrap();
+        let day = Duration::from_hours(24);
+        assert_eq!(
+            query.terms,
+            vec![
+                Expr::Modified(Ordering::Greater, SystemTime::UNIX_EPOCH + day * 70),
+                Expr::Created(Ordering::Greater, SystemTime::UNIX_EPOCH + day),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let error = |input: &str| parse(input).unwrap_err().position();
+        assert_eq!(error("a (b c"), 2);
+        assert_eq!(error("a b)"), 3);
+        assert_eq!(error("size:>2XB"), 7);
+        assert_eq!(error("colour:red"), 0);
+        assert_eq!(error("a \"b c"), 2);
+        assert_eq!(error("a OR"), 4);
+        assert_eq!(error("-depth:1"), 1);
+    }
+
+    #[test]
+    fn out_of_range_ages_and_dates_are_errors() {
+        let error = |input: &str| parse(input).unwrap_err();
+        assert_eq!(error("modified:<99999999999999999999y").position(), 10);
+        assert_eq!(error("modified:>99999999999999-01-01").position(), 10);
+        assert_eq!(
+            error("modified:<99999999999999999999y").message(),
+            "age out of range: `99999999999999999999y`"
+        );
+    }
+
+    #[test]
+    fn civil_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+    }
+}
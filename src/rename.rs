@@ -0,0 +1,774 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    ffi::OsStr,
+    fmt::{self, Write as _},
+    fs, io,
+    iter::Peekable,
+    path::{Path, PathBuf},
+    str::CharIndices,
+    time::SystemTime,
+};
+
+use regex::{Captures, Regex};
+
+use crate::{
+    format::check_time_format,
+    utils::{escape_path, format_time, path_from_bytes, path_to_bytes, unescape_path},
+    FormatError,
+};
+
+/// The first line of an undo journal.
+const JOURNAL_HEADER: &str = "rust_search rename journal 1";
+
+/// Renames files by matching their names against a regular expression
+///
+/// The first match in each file name is replaced following a template, the rest of the
+/// name is kept and files whose names don't match are left alone. Besides `$1` or
+/// `${name}` for capture groups and `$$` for a dollar sign, the template can hold:
+///
+/// | Placeholder | Replaced with |
+/// |---|---|
+/// | `{n}`, `{n:3}` | a counter of the matching files in order of their paths, starting at 1, optionally padded with zeros to a width |
+/// | `{upper:…}`, `{lower:…}`, `{title:…}` | the rest of the placeholder, which can hold captures and other placeholders, in another case |
+/// | `{mtime}`, `{mtime:%Y%m%d}` | the modification time of the file in UTC, `%Y-%m-%d` by default |
+/// | `{now}`, `{now:%Y%m%d}` | the current time in UTC, `%Y-%m-%d` by default |
+///
+/// `{{` and `}}` write literal braces.
+///
+/// The whole batch is planned first. A [`RenamePlan`] with conflicts, such as two files
+/// renamed to the same name, or to the name of a file that stays, renames nothing.
+/// Files are renamed in two phases, first to temporary names and then to their new
+/// names, so that names can be swapped or shifted along, and renames already done are
+/// rolled back when one fails.
+/// ### Examples
+/// ```rust,no_run
+/// use rust_search::{RenamePlan, Renamer, SearchBuilder};
+///
+/// let photos = SearchBuilder::default().location("~/photos").ext("JPG").build();
+/// let renamer = Renamer::new(r"IMG_(\d+)\.JPG", "{mtime:%Y%m%d}-photo-$1.jpg")
+///     .unwrap()
+///     .journal("renames.journal");
+///
+/// let plan = renamer.plan(photos);
+/// for (from, to) in plan.renames() {
+///     println!("{} -> {}", from.display(), to.display());
+/// }
+/// plan.apply().unwrap();
+///
+/// // Changed our mind.
+/// RenamePlan::undo("renames.journal").unwrap().apply().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Renamer {
+    regex: Regex,
+    template: Vec<Piece>,
+    journal: Option<PathBuf>,
+}
+
+/// A part of the replacement template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    /// Text with `$` references to capture groups.
+    Text(String),
+    Counter {
+        width: usize,
+    },
+    Case(Case, Vec<Self>),
+    Mtime(String),
+    Now(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    Upper,
+    Lower,
+    Title,
+}
+
+/// What the template is rendered with, for one file.
+struct Context<'a> {
+    captures: &'a Captures<'a>,
+    counter: usize,
+    path: &'a Path,
+    now: SystemTime,
+}
+
+impl Piece {
+    fn render(&self, context: &Context<'_>, out: &mut String) {
+        match self {
+            Self::Text(text) => context.captures.expand(text, out),
+            Self::Counter { width } => {
+                let _ = write!(out, "{:0width$}", context.counter);
+            }
+            Self::Case(case, pieces) => {
+                let mut inner = String::new();
+                for piece in pieces {
+                    piece.render(context, &mut inner);
+                }
+                out.push_str(&match case {
+                    Case::Upper => inner.to_uppercase(),
+                    Case::Lower => inner.to_lowercase(),
+                    Case::Title => title_case(&inner),
+                });
+            }
+            Self::Mtime(format) => {
+                if let Ok(modified) = fs::symlink_metadata(context.path).and_then(|m| m.modified())
+                {
+                    out.push_str(&format_time(modified, format));
+                }
+            }
+            Self::Now(format) => out.push_str(&format_time(context.now, format)),
+        }
+    }
+}
+
+/// Upper case the first letter of every word and lower case the rest.
+fn title_case(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word_start = true;
+    for c in text.chars() {
+        if word_start {
+            out.extend(c.to_uppercase());
+        } else {
+            out.extend(c.to_lowercase());
+        }
+        word_start = !c.is_alphanumeric();
+    }
+    out
+}
+
+/// Parse template pieces up to the end, or up to the `}` closing a case placeholder when
+/// `open` holds the position of its `{`.
+fn parse_pieces(
+    template: &str,
+    chars: &mut Peekable<CharIndices<'_>>,
+    open: Option<usize>,
+) -> Result<Vec<Piece>, FormatError> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '{' if chars.next_if(|&(_, c)| c == '{').is_some() => text.push('{'),
+            '}' if open.is_some() => {
+                push_text(&mut pieces, &mut text);
+                return Ok(pieces);
+            }
+            '}' if chars.next_if(|&(_, c)| c == '}').is_some() => text.push('}'),
+            '}' => {
+                return Err(FormatError::new(
+                    pos,
+                    "unmatched `}`, write `}}` for a brace",
+                ))
+            }
+            '{' => {
+                push_text(&mut pieces, &mut text);
+                pieces.push(parse_placeholder(template, chars, pos)?);
+            }
+            '$' if chars.next_if(|&(_, c)| c == '$').is_some() => text.push_str("$$"),
+            '$' if chars.next_if(|&(_, c)| c == '{').is_some() => {
+                // `${name}` is a capture group, kept for `Captures::expand`.
+                text.push_str("${");
+                for (_, c) in chars.by_ref() {
+                    text.push(c);
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    if let Some(open) = open {
+        return Err(FormatError::new(open, "unclosed `{`"));
+    }
+    push_text(&mut pieces, &mut text);
+    Ok(pieces)
+}
+
+fn push_text(pieces: &mut Vec<Piece>, text: &mut String) {
+    if !text.is_empty() {
+        pieces.push(Piece::Text(std::mem::take(text)));
+    }
+}
+
+/// Parse the placeholder whose `{` is at `open`.
+fn parse_placeholder(
+    template: &str,
+    chars: &mut Peekable<CharIndices<'_>>,
+    open: usize,
+) -> Result<Piece, FormatError> {
+    let start = open + 1;
+    let mut end = template.len();
+    let mut has_spec = false;
+    while let Some(&(pos, c)) = chars.peek() {
+        chars.next();
+        if c == ':' || c == '}' {
+            end = pos;
+            has_spec = c == ':';
+            break;
+        }
+    }
+    if end == template.len() {
+        return Err(FormatError::new(open, "unclosed `{`"));
+    }
+    let name = &template[start..end];
+    let case = match name {
+        "upper" => Some(Case::Upper),
+        "lower" => Some(Case::Lower),
+        "title" => Some(Case::Title),
+        _ => None,
+    };
+    if let Some(case) = case {
+        if !has_spec {
+            return Err(FormatError::new(
+                start,
+                format!("`{name}` needs text, e.g. `{{{name}:$1}}`"),
+            ));
+        }
+        return Ok(Piece::Case(
+            case,
+            parse_pieces(template, chars, Some(open))?,
+        ));
+    }
+
+    let spec_start = end + 1;
+    let spec = if has_spec {
+        let spec_end = loop {
+            match chars.next() {
+                Some((pos, '}')) => break pos,
+                Some(_) => {}
+                None => return Err(FormatError::new(open, "unclosed `{`")),
+            }
+        };
+        Some(&template[spec_start..spec_end])
+    } else {
+        None
+    };
+    match (name, spec) {
+        ("n", None) => Ok(Piece::Counter { width: 0 }),
+        ("n", Some(width)) => width
+            .parse()
+            .map(|width| Piece::Counter { width })
+            .map_err(|_| FormatError::new(spec_start, "the counter width must be a number")),
+        ("mtime" | "now", spec) => {
+            let format = spec.unwrap_or("%F");
+            check_time_format(format, spec_start)?;
+            Ok(if name == "mtime" {
+                Piece::Mtime(format.to_owned())
+            } else {
+                Piece::Now(format.to_owned())
+            })
+        }
+        _ => Err(FormatError::new(
+            start,
+            format!("unknown placeholder `{name}`"),
+        )),
+    }
+}
+
+impl Renamer {
+    /// Create a new renamer
+    /// ### Arguments
+    /// * `pattern` - The regular expression matched against file names.
+    /// * `replacement` - The template for the matched part of the names.
+    /// ### Errors
+    /// Returns an error if the pattern is not a valid regular expression or the template
+    /// has an unknown or unclosed placeholder.
+    pub fn new(pattern: &str, replacement: &str) -> Result<Self, RenameError> {
+        let regex = Regex::new(pattern).map_err(RenameError::Regex)?;
+        let template = parse_pieces(
+            replacement,
+            &mut replacement.char_indices().peekable(),
+            None,
+        )
+        .map_err(RenameError::Template)?;
+        Ok(Self {
+            regex,
+            template,
+            journal: None,
+        })
+    }
+
+    /// Write the renames to a journal file before doing them, for
+    /// [`RenamePlan::undo`].
+    pub fn journal(mut self, path: impl Into<PathBuf>) -> Self {
+        self.journal = Some(path.into());
+        self
+    }
+
+    /// Work out the new name of every given path without renaming anything.
+    ///
+    /// Paths whose file names are not valid UTF-8 are left alone.
+    /// ### Arguments
+    /// * `paths` - The paths to rename, usually a [`Search`](crate::Search).
+    pub fn plan<P: AsRef<Path>>(&self, paths: impl IntoIterator<Item = P>) -> RenamePlan {
+        let mut paths: Vec<PathBuf> = paths
+            .into_iter()
+            .map(|path| path.as_ref().to_path_buf())
+            .collect();
+        paths.sort_unstable();
+        paths.dedup();
+
+        let now = SystemTime::now();
+        let mut counter = 0;
+        let mut renames = Vec::new();
+        for path in paths {
+            let Some(name) = path.file_name().and_then(OsStr::to_str) else {
+                continue;
+            };
+            let Some(captures) = self.regex.captures(name) else {
+                continue;
+            };
+            counter += 1;
+            let matched = captures.get(0).map_or(0..0, |m| m.range());
+            let context = Context {
+                captures: &captures,
+                counter,
+                path: &path,
+                now,
+            };
+            let mut new_name = name[..matched.start].to_owned();
+            for piece in &self.template {
+                piece.render(&context, &mut new_name);
+            }
+            new_name.push_str(&name[matched.end..]);
+            if new_name != name {
+                let target = path.with_file_name(&new_name);
+                renames.push((path, target, new_name));
+            }
+        }
+        RenamePlan::new(renames, self.journal.clone())
+    }
+
+    /// Plan the renames, then do them.
+    /// ### Arguments
+    /// * `paths` - The paths to rename, usually a [`Search`](crate::Search).
+    /// ### Errors
+    /// See [`RenamePlan::apply`].
+    pub fn run<P: AsRef<Path>>(
+        &self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<RenamePlan, RenameError> {
+        let plan = self.plan(paths);
+        plan.apply()?;
+        Ok(plan)
+    }
+}
+
+/// An unused name next to `path` to move it to while renaming, for the `i`th rename.
+fn temp_name(path: &Path, i: usize) -> PathBuf {
+    let pid = std::process::id();
+    let mut temp = path.with_file_name(format!(".rsearch-rename-{pid}-{i}"));
+    let mut attempt = 0;
+    while temp.symlink_metadata().is_ok() {
+        attempt += 1;
+        temp = path.with_file_name(format!(".rsearch-rename-{pid}-{i}-{attempt}"));
+    }
+    temp
+}
+
+/// Why a rename can't be done, see [`RenamePlan::conflicts`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameConflictKind {
+    /// Another path is renamed to the same name
+    SameTarget,
+    /// A path that is not renamed already has the name
+    Exists,
+    /// The path is inside a directory that is renamed too
+    InsideRenamedDir,
+    /// The new name is empty, `.`, `..` or holds a path separator
+    InvalidName,
+}
+
+/// A rename that can't be done, see [`RenamePlan::conflicts`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameConflict {
+    source: PathBuf,
+    target: PathBuf,
+    kind: RenameConflictKind,
+}
+
+impl RenameConflict {
+    /// The path to rename
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// The path it would be renamed to
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    /// Why it can't be renamed
+    pub const fn kind(&self) -> RenameConflictKind {
+        self.kind
+    }
+}
+
+/// The renames worked out by [`Renamer::plan`], checked as a whole
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenamePlan {
+    renames: Vec<(PathBuf, PathBuf)>,
+    conflicts: Vec<RenameConflict>,
+    cycles: Vec<Vec<PathBuf>>,
+    journal: Option<PathBuf>,
+}
+
+impl RenamePlan {
+    /// Check the renames, given with the new file names.
+    fn new(renames: Vec<(PathBuf, PathBuf, String)>, journal: Option<PathBuf>) -> Self {
+        let sources: HashSet<&Path> = renames.iter().map(|(from, _, _)| from.as_path()).collect();
+        let mut targets: HashMap<&Path, usize> = HashMap::new();
+        for (_, to, _) in &renames {
+            *targets.entry(to.as_path()).or_default() += 1;
+        }
+
+        let mut conflicts = Vec::new();
+        for (from, to, name) in &renames {
+            let invalid = name.is_empty()
+                || name == "."
+                || name == ".."
+                || name.chars().any(std::path::is_separator);
+            let kind = if invalid {
+                RenameConflictKind::InvalidName
+            } else if targets[to.as_path()] > 1 {
+                RenameConflictKind::SameTarget
+            } else if from.ancestors().skip(1).any(|dir| sources.contains(dir)) {
+                RenameConflictKind::InsideRenamedDir
+            } else if to.symlink_metadata().is_ok() && !sources.contains(to.as_path()) {
+                RenameConflictKind::Exists
+            } else {
+                continue;
+            };
+            conflicts.push(RenameConflict {
+                source: from.clone(),
+                target: to.clone(),
+                kind,
+            });
+        }
+
+        let renames: Vec<(PathBuf, PathBuf)> = renames
+            .into_iter()
+            .map(|(from, to, _)| (from, to))
+            .collect();
+        let cycles = find_cycles(&renames);
+        Self {
+            renames,
+            conflicts,
+            cycles,
+            journal,
+        }
+    }
+
+    /// Every rename as the path and its new path, in order of the paths
+    pub fn renames(&self) -> &[(PathBuf, PathBuf)] {
+        &self.renames
+    }
+
+    /// The renames that can't be done, if any the plan can't be applied
+    pub fn conflicts(&self) -> &[RenameConflict] {
+        &self.conflicts
+    }
+
+    /// The groups of paths that take each other's names in turn, e.g. swapping two
+    /// names, which the two-phase rename takes care of
+    pub fn cycles(&self) -> &[Vec<PathBuf>] {
+        &self.cycles
+    }
+
+    /// Do the renames
+    ///
+    /// Writes the journal first if one was set with [`Renamer::journal`]. Each path is
+    /// renamed to an unused temporary name in its directory, then all of them to their new
+    /// names.
+    ///
+    /// The new names are checked again for paths created since the plan was made, but a
+    /// path created by another process while renaming is still replaced, as renames
+    /// overwrite on Unix.
+    /// ### Errors
+    /// Returns [`RenameError::Conflicts`] without renaming anything if the plan has
+    /// conflicts or a new name has been taken since, or [`RenameError::Io`] if the journal
+    /// can't be written or a rename fails, after moving the renamed paths back to their
+    /// old names.
+    pub fn apply(&self) -> Result<(), RenameError> {
+        if !self.conflicts.is_empty() {
+            return Err(RenameError::Conflicts(self.conflicts.clone()));
+        }
+        let sources: HashSet<&Path> = self
+            .renames
+            .iter()
+            .map(|(from, _)| from.as_path())
+            .collect();
+        let taken: Vec<RenameConflict> = self
+            .renames
+            .iter()
+            .filter(|(_, to)| to.symlink_metadata().is_ok() && !sources.contains(to.as_path()))
+            .map(|(from, to)| RenameConflict {
+                source: from.clone(),
+                target: to.clone(),
+                kind: RenameConflictKind::Exists,
+            })
+            .collect();
+        if !taken.is_empty() {
+            return Err(RenameError::Conflicts(taken));
+        }
+        if let Some(journal) = &self.journal {
+            write_journal(journal, &self.renames).map_err(|error| RenameError::Io {
+                path: journal.clone(),
+                error,
+            })?;
+        }
+
+        let mut temps: Vec<PathBuf> = Vec::with_capacity(self.renames.len());
+        for (i, (from, _)) in self.renames.iter().enumerate() {
+            let temp = temp_name(from, i);
+            let renamed = fs::rename(from, &temp);
+            temps.push(temp);
+            if let Err(error) = renamed {
+                self.roll_back(&temps, i, 0);
+                return Err(RenameError::Io {
+                    path: from.clone(),
+                    error,
+                });
+            }
+        }
+        for (i, (from, to)) in self.renames.iter().enumerate() {
+            if let Err(error) = fs::rename(&temps[i], to) {
+                self.roll_back(&temps, self.renames.len(), i);
+                return Err(RenameError::Io {
+                    path: from.clone(),
+                    error,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the paths back to their old names after a failure, when the first `moved`
+    /// were moved to their temporary names and the first `renamed` of those on to their
+    /// new names.
+    fn roll_back(&self, temps: &[PathBuf], moved: usize, renamed: usize) {
+        for (i, (_, to)) in self.renames.iter().enumerate().take(renamed) {
+            let _ = fs::rename(to, &temps[i]);
+        }
+        for (i, (from, _)) in self.renames.iter().enumerate().take(moved) {
+            let _ = fs::rename(&temps[i], from);
+        }
+    }
+
+    /// Plan undoing the renames recorded in a journal, see [`Renamer::journal`]
+    /// ### Arguments
+    /// * `journal` - The journal file.
+    /// ### Errors
+    /// Returns [`RenameError::Io`] if the journal can't be read or is not a journal.
+    pub fn undo(journal: impl AsRef<Path>) -> Result<Self, RenameError> {
+        let journal = journal.as_ref();
+        let renames = read_journal(journal).map_err(|error| RenameError::Io {
+            path: journal.to_path_buf(),
+            error,
+        })?;
+        let renames = renames
+            .into_iter()
+            .map(|(from, to)| {
+                let name = from
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                (to, from, name)
+            })
+            .collect();
+        Ok(Self::new(renames, None))
+    }
+}
+
+/// The chains of renames that end where they started.
+fn find_cycles(renames: &[(PathBuf, PathBuf)]) -> Vec<Vec<PathBuf>> {
+    let next: HashMap<&Path, &Path> = renames
+        .iter()
+        .map(|(from, to)| (from.as_path(), to.as_path()))
+        .collect();
+    let mut seen = HashSet::new();
+    let mut cycles = Vec::new();
+    for (start, _) in renames {
+        if seen.contains(start.as_path()) {
+            continue;
+        }
+        let mut chain = vec![start.as_path()];
+        let mut current = next[start.as_path()];
+        while let Some(&following) = next.get(current) {
+            if current == start.as_path() || seen.contains(current) || chain.contains(&current) {
+                break;
+            }
+            chain.push(current);
+            current = following;
+        }
+        seen.extend(chain.iter().copied());
+        if current == start.as_path() {
+            cycles.push(chain.into_iter().map(Path::to_path_buf).collect());
+        }
+    }
+    cycles
+}
+
+fn write_journal(path: &Path, renames: &[(PathBuf, PathBuf)]) -> io::Result<()> {
+    let mut out = format!("{JOURNAL_HEADER}\n").into_bytes();
+    for (from, to) in renames {
+        escape_path(&path_to_bytes(from), &mut out);
+        out.push(b'\t');
+        escape_path(&path_to_bytes(to), &mut out);
+        out.push(b'\n');
+    }
+    fs::write(path, out)
+}
+
+fn read_journal(path: &Path) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    let invalid = |line: usize| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid rename journal on line {line}"),
+        )
+    };
+    let bytes = fs::read(path)?;
+    let mut lines = bytes.split(|&b| b == b'\n');
+    if lines.next() != Some(JOURNAL_HEADER.as_bytes()) {
+        return Err(invalid(1));
+    }
+    let mut renames = Vec::new();
+    for (i, line) in lines.enumerate().filter(|(_, line)| !line.is_empty()) {
+        let mut fields = line.split(|&b| b == b'\t').map(unescape_path);
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(Some(from)), Some(Some(to)), None) => {
+                renames.push((path_from_bytes(from), path_from_bytes(to)));
+            }
+            _ => return Err(invalid(i + 2)),
+        }
+    }
+    Ok(renames)
+}
+
+/// An error creating a [`Renamer`] or applying a [`RenamePlan`]
+#[derive(Debug)]
+pub enum RenameError {
+    /// The pattern is not a valid regular expression
+    Regex(regex::Error),
+    /// The replacement template is invalid
+    Template(FormatError),
+    /// Some renames can't be done, so none were
+    Conflicts(Vec<RenameConflict>),
+    /// A file couldn't be renamed or the journal couldn't be read or written
+    Io {
+        /// The path that couldn't be renamed, or the journal
+        path: PathBuf,
+        /// What went wrong
+        error: io::Error,
+    },
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Regex(e) => write!(f, "invalid pattern: {e}"),
+            Self::Template(e) => write!(f, "invalid replacement: {e}"),
+            Self::Conflicts(conflicts) => {
+                write!(f, "{} renames conflict", conflicts.len())?;
+                if let Some(first) = conflicts.first() {
+                    write!(
+                        f,
+                        ", e.g. {} -> {} ({:?})",
+                        first.source.display(),
+                        first.target.display(),
+                        first.kind
+                    )?;
+                }
+                Ok(())
+            }
+            Self::Io { path, error } => write!(f, "{}: {error}", path.display()),
+        }
+    }
+}
+
+impl Error for RenameError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Regex(e) => Some(e),
+            Self::Template(e) => Some(e),
+            Self::Io { error, .. } => Some(error),
+            Self::Conflicts(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::literal_string_with_formatting_args)]
+mod tests {
+    use super::*;
+
+    fn rename(pattern: &str, replacement: &str, name: &str, counter: usize) -> String {
+        let renamer = Renamer::new(pattern, replacement).unwrap();
+        let captures = renamer.regex.captures(name).unwrap();
+        let context = Context {
+            captures: &captures,
+            counter,
+            path: Path::new(name),
+            now: SystemTime::UNIX_EPOCH,
+        };
+        let mut out = String::new();
+        for piece in &renamer.template {
+            piece.render(&context, &mut out);
+        }
+        out
+    }
+
+    #[test]
+    fn templates_render_captures_counters_and_case() {
+        assert_eq!(
+            rename(r"IMG_(\d+)\.JPG", "photo-$1.jpg", "IMG_0042.JPG", 1),
+            "photo-0042.jpg"
+        );
+        assert_eq!(rename(r"(?<word>\w+)", "{n:3}-${word}", "x", 7), "007-x");
+        assert_eq!(
+            rename(
+                r"(\w+) (\w+)",
+                "{upper:$1}_{lower:$2}_{title:$1 $2}",
+                "hello WORLD",
+                1
+            ),
+            "HELLO_world_Hello World"
+        );
+        assert_eq!(rename(r".+", "{now:%Y}{{$0}}$$", "a", 1), "1970{a}$");
+    }
+
+    #[test]
+    fn template_errors_point_at_the_problem() {
+        let error = |template: &str| match Renamer::new(".", template) {
+            Err(RenameError::Template(e)) => (e.position(), e.message().to_owned()),
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(error("a{b}").0, 2);
+        assert_eq!(error("{upper:$1").0, 0);
+        assert_eq!(error("{n:x}").0, 3);
+        assert_eq!(error("{mtime:%q}").0, 7);
+        assert_eq!(error("a}").0, 1);
+        assert!(matches!(Renamer::new("(", "x"), Err(RenameError::Regex(_))));
+    }
+
+    #[test]
+    fn cycles_are_found() {
+        let pairs = |names: &[(&str, &str)]| -> Vec<(PathBuf, PathBuf)> {
+            names
+                .iter()
+                .map(|(a, b)| (PathBuf::from(a), PathBuf::from(b)))
+                .collect()
+        };
+        assert_eq!(
+            find_cycles(&pairs(&[("a", "b"), ("b", "c"), ("c", "a"), ("x", "y")])),
+            [vec![
+                PathBuf::from("a"),
+                PathBuf::from("b"),
+                PathBuf::from("c")
+            ]]
+        );
+        assert!(find_cycles(&pairs(&[("a", "b"), ("b", "c")])).is_empty());
+    }
+}
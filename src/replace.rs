@@ -0,0 +1,531 @@
+use std::{
+    borrow::Cow,
+    fmt::Write as _,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use regex::bytes::Regex;
+
+use crate::content::is_binary;
+
+/// Lines of unchanged context around each hunk of the diff preview, like `diff -u`.
+const DIFF_CONTEXT: usize = 3;
+
+/// Applies a regex replacement to every file of a search
+///
+/// Files are written atomically: the new contents go to a temporary file next to the
+/// original, which keeps the permissions and, where allowed, the owner of the original
+/// and is then renamed over it. Symlinks are followed, and files with several hard links
+/// are written in place to keep the links.
+/// Binary files are left untouched. Errors are collected per file instead of stopping
+/// at the first one.
+/// ### Examples
+/// ```rust
+/// use rust_search::{Replacer, SearchBuilder};
+///
+/// let search = SearchBuilder::default()
+///     .location("src")
+///     .ext("rs")
+///     .contains_regex(r"SearchBuilder")
+///     .unwrap()
+///     .build();
+///
+/// // Preview the changes as a unified diff without writing anything.
+/// let report = Replacer::new(r"Search(\w*)Builder", "Finder${1}Builder")
+///     .unwrap()
+///     .dry_run()
+///     .run(search);
+///
+/// for file in report.files() {
+///     println!("{} replacements in {}", file.replacements(), file.path().display());
+///     print!("{}", file.diff().unwrap_or_default());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Replacer {
+    regex: Regex,
+    replacement: String,
+    dry_run: bool,
+}
+
+impl Replacer {
+    /// Create a new replacer
+    /// ### Arguments
+    /// * `pattern` - The regular expression to replace.
+    /// * `replacement` - The replacement, `$1` or `${name}` insert capture groups.
+    /// ### Errors
+    /// Returns an error if the pattern is not a valid regular expression.
+    pub fn new(pattern: &str, replacement: impl Into<String>) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: Regex::new(pattern)?,
+            replacement: replacement.into(),
+            dry_run: false,
+        })
+    }
+
+    /// Don't write any file, only report the replacements along with a unified diff.
+    pub const fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Apply the replacement to every given file.
+    /// ### Arguments
+    /// * `paths` - The files to replace in, usually a [`Search`](crate::Search).
+    pub fn run<P: AsRef<Path>>(&self, paths: impl IntoIterator<Item = P>) -> ReplaceReport {
+        let mut report = ReplaceReport::default();
+        for path in paths {
+            let path = path.as_ref();
+            match self.replace_file(path) {
+                Ok(Some(file)) => report.files.push(file),
+                Ok(None) => {}
+                Err(error) => report.errors.push((path.to_path_buf(), error)),
+            }
+        }
+        report
+    }
+
+    fn replace_file(&self, path: &Path) -> io::Result<Option<FileReplacement>> {
+        if path.is_dir() {
+            return Ok(None);
+        }
+        let contents = fs::read(path)?;
+        if is_binary(&contents) {
+            return Ok(None);
+        }
+        let replacements = self.regex.find_iter(&contents).count();
+        if replacements == 0 {
+            return Ok(None);
+        }
+        let replaced = self
+            .regex
+            .replace_all(&contents, self.replacement.as_bytes());
+
+        let diff = if self.dry_run {
+            Some(unified_diff(path, &contents, &replaced))
+        } else {
+            write_atomically(path, &replaced)?;
+            None
+        };
+        Ok(Some(FileReplacement {
+            path: path.to_path_buf(),
+            replacements,
+            diff,
+        }))
+    }
+}
+
+/// Write `contents` to a temporary file next to `path`, then rename it over `path`.
+///
+/// A symlink is followed so the file it points to is replaced rather than the link. A
+/// file with other hard links is written in place, as renaming would split it from them.
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let path = fs::canonicalize(path)?;
+    let metadata = fs::metadata(&path)?;
+    if hard_links(&metadata) > 1 {
+        let mut file = OpenOptions::new().write(true).truncate(true).open(&path)?;
+        return write_and_sync(&mut file, contents);
+    }
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    let mut attempt = 0;
+    let (tmp_path, mut tmp) = loop {
+        let tmp_path = dir.join(format!(".{name}.{}.{attempt}.tmp", std::process::id()));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)
+        {
+            Ok(file) => break (tmp_path, file),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    };
+
+    let written = write_and_sync(&mut tmp, contents)
+        .and_then(|()| fs::set_permissions(&tmp_path, metadata.permissions()))
+        .map(|()| keep_owner(&tmp_path, &metadata))
+        .and_then(|()| fs::rename(&tmp_path, &path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    written
+}
+
+#[cfg(unix)]
+fn hard_links(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink()
+}
+
+#[cfg(not(unix))]
+const fn hard_links(_metadata: &fs::Metadata) -> u64 {
+    1
+}
+
+/// Give the temporary file the owner of the original, when allowed to.
+#[cfg(unix)]
+fn keep_owner(tmp_path: &Path, metadata: &fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+    let _ = std::os::unix::fs::chown(tmp_path, Some(metadata.uid()), Some(metadata.gid()));
+}
+
+#[cfg(not(unix))]
+const fn keep_owner(_tmp_path: &Path, _metadata: &fs::Metadata) {}
+
+fn write_and_sync(file: &mut File, contents: &[u8]) -> io::Result<()> {
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// The outcome of [`Replacer::run`]
+#[derive(Debug, Default)]
+pub struct ReplaceReport {
+    files: Vec<FileReplacement>,
+    errors: Vec<(PathBuf, io::Error)>,
+}
+
+impl ReplaceReport {
+    /// The files with at least one replacement
+    pub fn files(&self) -> &[FileReplacement] {
+        &self.files
+    }
+
+    /// The files that couldn't be read or written, along with the error
+    pub fn errors(&self) -> &[(PathBuf, io::Error)] {
+        &self.errors
+    }
+
+    /// The number of replacements across all files
+    pub fn total_replacements(&self) -> usize {
+        self.files.iter().map(|f| f.replacements).sum()
+    }
+}
+
+/// The replacements made, or previewed, in a single file
+#[derive(Debug, Clone)]
+pub struct FileReplacement {
+    path: PathBuf,
+    replacements: usize,
+    diff: Option<String>,
+}
+
+impl FileReplacement {
+    /// The path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of matches that were replaced
+    pub const fn replacements(&self) -> usize {
+        self.replacements
+    }
+
+    /// The unified diff of the change, only available in [dry run](Replacer::dry_run) mode
+    pub fn diff(&self) -> Option<&str> {
+        self.diff.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Split into lines, keeping the line endings so a missing final newline shows up.
+fn lines(contents: &[u8]) -> Vec<&[u8]> {
+    contents.split_inclusive(|&b| b == b'\n').collect()
+}
+
+/// Shortest edit script between `old` and `new` using Myers' algorithm, in linear space.
+///
+/// Within each changed block the deletions come before the insertions, like `diff -u`.
+fn diff_lines(old: &[&[u8]], new: &[&[u8]]) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    diff_range(old, new, (0, old.len()), (0, new.len()), &mut edits);
+    for block in
+        edits.chunk_by_mut(|a, b| !matches!(a, Edit::Equal(..)) && !matches!(b, Edit::Equal(..)))
+    {
+        block.sort_by_key(|e| matches!(e, Edit::Insert(_)));
+    }
+    edits
+}
+
+/// Push the edits from `old[a.0..a.1]` to `new[b.0..b.1]`, splitting the ranges on the
+/// middle snake so only one pair of diagonals is kept at a time.
+#[allow(clippy::suspicious_operation_groupings)]
+fn diff_range(
+    old: &[&[u8]],
+    new: &[&[u8]],
+    (mut a_lo, mut a_hi): (usize, usize),
+    (mut b_lo, mut b_hi): (usize, usize),
+    edits: &mut Vec<Edit>,
+) {
+    while a_lo < a_hi && b_lo < b_hi && old[a_lo] == new[b_lo] {
+        edits.push(Edit::Equal(a_lo, b_lo));
+        a_lo += 1;
+        b_lo += 1;
+    }
+    let mut suffix = 0;
+    while a_lo < a_hi && b_lo < b_hi && old[a_hi - 1] == new[b_hi - 1] {
+        a_hi -= 1;
+        b_hi -= 1;
+        suffix += 1;
+    }
+
+    let split = if a_lo == a_hi || b_lo == b_hi {
+        None
+    } else {
+        middle_snake(&old[a_lo..a_hi], &new[b_lo..b_hi])
+            .filter(|&(x, y)| (x, y) != (0, 0) && (x, y) != (a_hi - a_lo, b_hi - b_lo))
+    };
+    if let Some((x, y)) = split {
+        diff_range(old, new, (a_lo, a_lo + x), (b_lo, b_lo + y), edits);
+        diff_range(old, new, (a_lo + x, a_hi), (b_lo + y, b_hi), edits);
+    } else {
+        edits.extend((a_lo..a_hi).map(Edit::Delete));
+        edits.extend((b_lo..b_hi).map(Edit::Insert));
+    }
+    edits.extend((0..suffix).map(|i| Edit::Equal(a_hi + i, b_hi + i)));
+}
+
+/// A point on a shortest edit path from `old` to `new`, found by running the search from
+/// both ends until the paths overlap.
+// Keeps the names of the paper: `x`/`y` index `old`/`new`, `k` is the diagonal.
+#[allow(
+    clippy::cast_possible_wrap,
+    clippy::cast_sign_loss,
+    clippy::many_single_char_names,
+    clippy::suspicious_operation_groupings
+)]
+fn middle_snake(old: &[&[u8]], new: &[&[u8]]) -> Option<(usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let d_max = (n + m + 1) / 2 + 1;
+    let offset = d_max + 1;
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = forward.clone();
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..d_max {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let start = (x, y);
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            if odd && (k - delta).abs() < d && x + backward[at(delta - k)] >= n {
+                return Some((start.0 as usize, start.1 as usize));
+            }
+        }
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            if !odd && (k - delta).abs() <= d && x + forward[at(delta - k)] >= n {
+                return Some(((n - x) as usize, (m - y) as usize));
+            }
+        }
+    }
+    None
+}
+
+/// Render the change from `old` to `new` as a unified diff with a few lines of context.
+fn unified_diff(path: &Path, old: &[u8], new: &[u8]) -> String {
+    let (old_lines, new_lines) = (lines(old), lines(new));
+    let edits = diff_lines(&old_lines, &new_lines);
+
+    let mut out = format!("--- {0}\n+++ {0}\n", path.display());
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !matches!(e, Edit::Equal(..)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut i = 0;
+    while i < changes.len() {
+        // Grow the hunk while the next change is close enough to share context.
+        let start = changes[i].saturating_sub(DIFF_CONTEXT);
+        let mut last = changes[i];
+        while i + 1 < changes.len() && changes[i + 1] - last <= 2 * DIFF_CONTEXT + 1 {
+            i += 1;
+            last = changes[i];
+        }
+        let end = (last + DIFF_CONTEXT + 1).min(edits.len());
+        i += 1;
+
+        let hunk = &edits[start..end];
+        let (old_start, new_start) = hunk_start(&edits[..start]);
+        let old_len = hunk
+            .iter()
+            .filter(|e| !matches!(e, Edit::Insert(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|e| !matches!(e, Edit::Delete(_)))
+            .count();
+        let _ = writeln!(
+            out,
+            "@@ -{} +{} @@",
+            hunk_range(old_start, old_len),
+            hunk_range(new_start, new_len)
+        );
+        for edit in hunk {
+            let (sign, line) = match *edit {
+                Edit::Equal(o, _) => (' ', old_lines[o]),
+                Edit::Delete(o) => ('-', old_lines[o]),
+                Edit::Insert(n) => ('+', new_lines[n]),
+            };
+            push_line(&mut out, sign, line);
+        }
+    }
+    out
+}
+
+/// The 1-based first old and new line numbers of a hunk, given the edits before it.
+fn hunk_start(before: &[Edit]) -> (usize, usize) {
+    let old = before
+        .iter()
+        .filter(|e| !matches!(e, Edit::Insert(_)))
+        .count();
+    let new = before
+        .iter()
+        .filter(|e| !matches!(e, Edit::Delete(_)))
+        .count();
+    (old + 1, new + 1)
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start - 1),
+        1 => start.to_string(),
+        _ => format!("{start},{len}"),
+    }
+}
+
+fn push_line(out: &mut String, sign: char, line: &[u8]) {
+    out.push(sign);
+    let text: Cow<'_, str> = String::from_utf8_lossy(line);
+    if let Some(text) = text.strip_suffix('\n') {
+        out.push_str(text);
+        out.push('\n');
+    } else {
+        out.push_str(&text);
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lines_finds_single_change() {
+        let old = lines(b"a\nb\nc\n");
+        let new = lines(b"a\nx\nc\n");
+        assert_eq!(
+            diff_lines(&old, &new),
+            vec![
+                Edit::Equal(0, 0),
+                Edit::Delete(1),
+                Edit::Insert(1),
+                Edit::Equal(2, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_lines_matches_a_longest_common_subsequence() {
+        let old = lines(b"a\nb\nc\na\nb\nb\na\n");
+        let new = lines(b"c\nb\na\nb\na\nc\n");
+        let edits = diff_lines(&old, &new);
+        let equal = edits
+            .iter()
+            .filter(|e| matches!(e, Edit::Equal(..)))
+            .count();
+        assert_eq!(equal, 4);
+        assert_eq!(edits.len(), old.len() + new.len() - equal);
+
+        // Replaying the edits rebuilds both sides in order.
+        let (mut x, mut y) = (0, 0);
+        for edit in edits {
+            match edit {
+                Edit::Equal(i, j) => {
+                    assert_eq!((i, j), (x, y));
+                    assert_eq!(old[i], new[j]);
+                    (x, y) = (x + 1, y + 1);
+                }
+                Edit::Delete(i) => {
+                    assert_eq!(i, x);
+                    x += 1;
+                }
+                Edit::Insert(j) => {
+                    assert_eq!(j, y);
+                    y += 1;
+                }
+            }
+        }
+        assert_eq!((x, y), (old.len(), new.len()));
+    }
+
+    #[test]
+    fn unified_diff_has_context_and_ranges() {
+        let old = b"1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = b"1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+        let diff = unified_diff(Path::new("f.txt"), old, new);
+        assert_eq!(
+            diff,
+            "--- f.txt\n+++ f.txt\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff_merges_hunks_like_diff_u() {
+        // Checked against `diff -u`: six unchanged lines between two changes are shared
+        // as context by one hunk, seven split it in two.
+        let old = b"1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let new = b"1\none\n3\n4\n5\n6\n7\n8\nnine\n10\n11\n12\n";
+        assert_eq!(
+            unified_diff(Path::new("f.txt"), old, new),
+            "--- f.txt\n+++ f.txt\n@@ -1,12 +1,12 @@\n 1\n-2\n+one\n 3\n 4\n 5\n 6\n 7\n 8\n\
+             -9\n+nine\n 10\n 11\n 12\n"
+        );
+        let new = b"1\none\n3\n4\n5\n6\n7\n8\n9\n10\neleven\n12\n";
+        assert_eq!(
+            unified_diff(Path::new("f.txt"), old, new),
+            "--- f.txt\n+++ f.txt\n@@ -1,5 +1,5 @@\n 1\n-2\n+one\n 3\n 4\n 5\n\
+             @@ -8,5 +8,5 @@\n 8\n 9\n 10\n-11\n+eleven\n 12\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff_marks_missing_newline() {
+        let diff = unified_diff(Path::new("f.txt"), b"old", b"new");
+        assert!(
+            diff.ends_with("+new\n\\ No newline at end of file\n"),
+            "{diff}"
+        );
+    }
+}
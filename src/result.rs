@@ -0,0 +1,340 @@
+use std::{
+    fs::{FileType, Metadata},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::{utils::relative_path, LineMatch};
+
+/// How the paths of the results are written
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum PathStyle {
+    /// Keep the paths as they were found, relative or absolute depending on how the
+    /// location was given. This is the default.
+    #[default]
+    AsGiven,
+    /// Relative to the location the path was found in
+    Relative,
+    /// Absolute, without resolving symlinks or `..` components
+    Absolute,
+    /// Absolute with all symlinks and `..` components resolved, falls back to
+    /// [`Absolute`](Self::Absolute) for paths that can't be resolved
+    Canonical,
+    /// Relative to the given base directory, using `..` where needed
+    RelativeTo(PathBuf),
+}
+
+impl PathStyle {
+    /// Resolve the parts of the style that don't depend on the found path, once per search.
+    pub(crate) fn resolve(&self) -> Self {
+        match self {
+            Self::RelativeTo(base) => {
+                Self::RelativeTo(std::path::absolute(base).unwrap_or_else(|_| base.clone()))
+            }
+            style => style.clone(),
+        }
+    }
+
+    /// Write `path`, found in `root`, in this style, along with `path` itself unless the
+    /// style keeps it as given.
+    pub(crate) fn apply_keeping(&self, path: PathBuf, root: &Path) -> (PathBuf, Option<PathBuf>) {
+        match self {
+            Self::AsGiven => (path, None),
+            style => (style.apply(path.clone(), root), Some(path)),
+        }
+    }
+
+    /// Write `path`, found in `root`, in this style.
+    pub(crate) fn apply(&self, path: PathBuf, root: &Path) -> PathBuf {
+        match self {
+            Self::AsGiven => path,
+            Self::Relative => match path.strip_prefix(root) {
+                Ok(relative) if relative.as_os_str().is_empty() => PathBuf::from("."),
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => path,
+            },
+            Self::Absolute => std::path::absolute(&path).unwrap_or(path),
+            Self::Canonical => path
+                .canonicalize()
+                .or_else(|_| std::path::absolute(&path))
+                .unwrap_or(path),
+            Self::RelativeTo(base) => {
+                std::path::absolute(&path).map_or(path, |absolute| relative_path(&absolute, base))
+            }
+        }
+    }
+}
+
+/// What kind of file system entry a [`SearchResult`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum FileKind {
+    /// A regular file
+    File,
+    /// A directory
+    Dir,
+    /// A symbolic link, which is not followed
+    Symlink,
+    /// Anything else, e.g. a socket, FIFO or device
+    Other,
+}
+
+impl FileKind {
+    pub(crate) fn from_file_type(file_type: Option<FileType>) -> Self {
+        match file_type {
+            Some(ft) if ft.is_file() => Self::File,
+            Some(ft) if ft.is_dir() => Self::Dir,
+            Some(ft) if ft.is_symlink() => Self::Symlink,
+            _ => Self::Other,
+        }
+    }
+
+    /// The lowercase name of the kind, as used in the output formats
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Dir => "dir",
+            Self::Symlink => "symlink",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// A path found by a search, along with the location it was found in
+///
+/// Returned by [`SearchBuilder::build_results`](crate::SearchBuilder::build_results).
+/// ### Examples
+/// ```rust
+/// use rust_search::{PathStyle, SearchBuilder};
+///
+/// for result in SearchBuilder::default()
+///     .location("src")
+///     .ext("rs")
+///     .output_paths(PathStyle::Relative)
+///     .build_results()
+/// {
+///     println!("{} in {}", result.path().display(), result.root().display());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    path: PathBuf,
+    root: Arc<Path>,
+    lines: Vec<LineMatch>,
+    kind: FileKind,
+    depth: usize,
+    size: Option<u64>,
+    modified: Option<SystemTime>,
+    /// The `st_mode`, user, group, device and inode of the path, on Unix only.
+    mode: Option<u32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    dev: Option<u64>,
+    inode: Option<u64>,
+    score: Option<f64>,
+    hash: Option<String>,
+    /// The path as the walk found it, when the [`PathStyle`] wrote it differently.
+    found: Option<PathBuf>,
+}
+
+impl SearchResult {
+    pub(crate) const fn new(
+        path: PathBuf,
+        root: Arc<Path>,
+        lines: Vec<LineMatch>,
+        kind: FileKind,
+        depth: usize,
+    ) -> Self {
+        Self {
+            path,
+            root,
+            lines,
+            kind,
+            depth,
+            size: None,
+            modified: None,
+            mode: None,
+            uid: None,
+            gid: None,
+            dev: None,
+            inode: None,
+            score: None,
+            hash: None,
+            found: None,
+        }
+    }
+
+    /// Record the path as the walk found it, if the [`PathStyle`] wrote it differently.
+    pub(crate) fn found_at(mut self, found: Option<PathBuf>) -> Self {
+        self.found = found.filter(|found| *found != self.path);
+        self
+    }
+
+    /// Record the size, modification time and on Unix the owner, mode and inode of the path.
+    pub(crate) fn with_metadata(mut self, metadata: &Metadata) -> Self {
+        self.size = Some(metadata.len());
+        self.modified = metadata.modified().ok();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            self.mode = Some(metadata.mode());
+            self.uid = Some(metadata.uid());
+            self.gid = Some(metadata.gid());
+            self.dev = Some(metadata.dev());
+            self.inode = Some(metadata.ino());
+        }
+        self
+    }
+
+    /// Record the size and modification time of the path, known without reading its metadata.
+    pub(crate) const fn with_size_and_modified(
+        mut self,
+        size: u64,
+        modified: Option<SystemTime>,
+    ) -> Self {
+        self.size = Some(size);
+        self.modified = modified;
+        self
+    }
+
+    pub(crate) const fn set_score(&mut self, score: f64) {
+        self.score = Some(score);
+    }
+
+    pub(crate) fn set_hash(&mut self, hash: Option<String>) {
+        self.hash = hash;
+    }
+
+    /// The path that was found, written in the [`PathStyle`] of the search
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path as the walk found it, inside [`root`](Self::root), whatever the
+    /// [`PathStyle`] of the search. This is the path to open or change the result with when
+    /// its [`path`](Self::path) is relative to the location or another directory.
+    pub fn disk_path(&self) -> &Path {
+        self.found.as_deref().unwrap_or(&self.path)
+    }
+
+    /// The location the path was found in, either the main location or one of the
+    /// extra locations of the search
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The lines matching the content pattern, only filled in when
+    /// [`SearchBuilder::line_matches`](crate::SearchBuilder::line_matches) is set
+    pub fn lines(&self) -> &[LineMatch] {
+        &self.lines
+    }
+
+    /// Whether the path is a file, directory, symlink or something else
+    pub const fn kind(&self) -> FileKind {
+        self.kind
+    }
+
+    /// How many directories below its location the path was found, 0 for the location itself
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The size in bytes, `None` if the metadata couldn't be read
+    pub const fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// The last modification time, `None` if it couldn't be read
+    pub const fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// The file type and permission bits (`st_mode`), `None` if the metadata couldn't be
+    /// read or on platforms other than Unix
+    pub const fn mode(&self) -> Option<u32> {
+        self.mode
+    }
+
+    /// The id of the user owning the path, `None` if the metadata couldn't be read or on
+    /// platforms other than Unix
+    pub const fn uid(&self) -> Option<u32> {
+        self.uid
+    }
+
+    /// The id of the group owning the path, `None` if the metadata couldn't be read or on
+    /// platforms other than Unix
+    pub const fn gid(&self) -> Option<u32> {
+        self.gid
+    }
+
+    /// The id of the device holding the path, `None` if the metadata couldn't be read or
+    /// on platforms other than Unix
+    pub const fn dev(&self) -> Option<u64> {
+        self.dev
+    }
+
+    /// The inode number of the path on its device, `None` if the metadata couldn't be
+    /// read or on platforms other than Unix
+    pub const fn inode(&self) -> Option<u64> {
+        self.inode
+    }
+
+    /// The similarity score, only set once the results are sorted with
+    /// [`similarity_sort_results`](crate::similarity_sort_results)
+    pub const fn score(&self) -> Option<f64> {
+        self.score
+    }
+
+    /// The hash of the contents in lowercase hex, only set for regular files once the
+    /// results are hashed with [`hash_results`](crate::hash_results)
+    pub fn hash(&self) -> Option<&str> {
+        self.hash.as_deref()
+    }
+
+    /// Consume the result and return the path that was found
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+}
+
+/// Borrows the [`disk_path`](SearchResult::disk_path), so that results can be handed to
+/// anything that opens or changes files whatever the [`PathStyle`] of the search.
+impl AsRef<Path> for SearchResult {
+    fn as_ref(&self) -> &Path {
+        self.disk_path()
+    }
+}
+
+/// Paths are written lossily and times as RFC 3339 timestamps in UTC, missing values
+/// as `null`. The matching lines are only written when there are any.
+#[cfg(feature = "serde")]
+impl serde::Serialize for SearchResult {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("SearchResult", 9)?;
+        state.serialize_field("path", &self.path.to_string_lossy())?;
+        state.serialize_field("root", &self.root.to_string_lossy())?;
+        state.serialize_field("size", &self.size)?;
+        state.serialize_field("mtime", &self.modified.map(crate::utils::format_rfc3339))?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("depth", &self.depth)?;
+        state.serialize_field("score", &self.score)?;
+        if self.hash.is_none() {
+            state.skip_field("hash")?;
+        } else {
+            state.serialize_field("hash", &self.hash)?;
+        }
+        if self.lines.is_empty() {
+            state.skip_field("lines")?;
+        } else {
+            state.serialize_field("lines", &self.lines)?;
+        }
+        state.end()
+    }
+}
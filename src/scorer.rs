@@ -0,0 +1,451 @@
+use std::borrow::Cow;
+use strsim::{jaro_winkler, levenshtein, normalized_damerau_levenshtein, sorensen_dice};
+
+/// A scoring algorithm used by [`similarity_sort_with`](crate::similarity_sort_with)
+///
+/// Higher scores mean the candidate is more similar to the input. Scores only need to be
+/// comparable with each other, they don't have to be in a fixed range.
+///
+/// Any `Fn(&str, &str) -> f64` closure can be used as a scorer, the first argument is the
+/// candidate and the second one is the search input.
+/// ### Examples
+/// ```rust
+/// use rust_search::{similarity_sort_with, ScoreTarget};
+///
+/// let mut files = vec!["b.txt".to_string(), "aaa.txt".to_string()];
+/// // prefer the longest file names
+/// similarity_sort_with(&mut files, "", &|candidate: &str, _: &str| candidate.len() as f64, ScoreTarget::FileName);
+/// assert_eq!(files[0], "aaa.txt");
+/// ```
+pub trait Scorer: Sync {
+    /// Score the `candidate` against the search `input`.
+    fn score(&self, candidate: &str, input: &str) -> f64;
+
+    /// Whether scores ignore case, so the sorts can lowercase the input once rather than
+    /// for every candidate. Defaults to `false`.
+    fn ignores_case(&self) -> bool {
+        false
+    }
+}
+
+impl<F> Scorer for F
+where
+    F: Fn(&str, &str) -> f64 + Sync,
+{
+    fn score(&self, candidate: &str, input: &str) -> f64 {
+        self(candidate, input)
+    }
+}
+
+/// What part of the path is handed to the [`Scorer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScoreTarget {
+    /// Only the file name, e.g. `main.rs` for `src/main.rs`
+    #[default]
+    FileName,
+    /// The whole path as it was returned by the search
+    FullPath,
+}
+
+/// Lowercase `s` char by char, only allocating when it has upper case characters.
+fn lowercase(s: &str) -> Cow<'_, str> {
+    if s.chars().any(char::is_uppercase) {
+        Cow::Owned(s.chars().map(lowercase_char).collect())
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// The first char of the lowercase form of `c`, so a lowercased string keeps its length
+/// in chars.
+fn lowercase_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// The input as the scorer should get it from a sort, lowercased once if case is ignored.
+pub fn prepare_input<'a, S: Scorer + ?Sized>(scorer: &S, input: &'a str) -> Cow<'a, str> {
+    if scorer.ignores_case() {
+        lowercase(input)
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// Case insensitive Jaro-Winkler similarity, used by [`similarity_sort`](crate::similarity_sort)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JaroWinkler;
+
+impl Scorer for JaroWinkler {
+    fn score(&self, candidate: &str, input: &str) -> f64 {
+        jaro_winkler(&lowercase(candidate), &lowercase(input))
+    }
+
+    fn ignores_case(&self) -> bool {
+        true
+    }
+}
+
+/// Case insensitive Levenshtein distance, scored as the negated number of edits
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Levenshtein;
+
+impl Scorer for Levenshtein {
+    fn score(&self, candidate: &str, input: &str) -> f64 {
+        -(levenshtein(&lowercase(candidate), &lowercase(input)) as f64)
+    }
+
+    fn ignores_case(&self) -> bool {
+        true
+    }
+}
+
+/// Case insensitive Damerau-Levenshtein similarity, normalized between `0.0` and `1.0`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DamerauLevenshtein;
+
+impl Scorer for DamerauLevenshtein {
+    fn score(&self, candidate: &str, input: &str) -> f64 {
+        normalized_damerau_levenshtein(&lowercase(candidate), &lowercase(input))
+    }
+
+    fn ignores_case(&self) -> bool {
+        true
+    }
+}
+
+/// Case insensitive Sørensen-Dice coefficient over character bigrams
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SorensenDice;
+
+impl Scorer for SorensenDice {
+    fn score(&self, candidate: &str, input: &str) -> f64 {
+        sorensen_dice(&lowercase(candidate), &lowercase(input))
+    }
+
+    fn ignores_case(&self) -> bool {
+        true
+    }
+}
+
+/// Editor style matching that understands prefixes, `camelCase`, `snake_case` and acronyms
+///
+/// From best to worst: exact match of the name without extension, prefix match,
+/// every input character landing on a word start (`"sb"` → `SearchBuilder.rs`),
+/// characters continuing words (`"seabu"` → `SearchBuilder.rs`) and finally a plain
+/// subsequence match. Candidates that don't contain the input as a subsequence score `0.0`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Acronym;
+
+impl Scorer for Acronym {
+    fn score(&self, candidate: &str, input: &str) -> f64 {
+        let input: Vec<char> = input.chars().map(lowercase_char).collect();
+        if input.is_empty() {
+            return 0.0;
+        }
+        let chars: Vec<char> = candidate.chars().collect();
+        let lower: Vec<char> = chars.iter().copied().map(lowercase_char).collect();
+        let coverage = input.len() as f64 / lower.len().max(1) as f64;
+
+        let stem_len = candidate
+            .rfind('.')
+            .filter(|&i| i > 0)
+            .map_or(lower.len(), |i| candidate[..i].chars().count());
+        if lower[..stem_len] == input[..] {
+            return 1.0;
+        }
+        if lower.starts_with(&input) {
+            return 0.2f64.mul_add(coverage, 0.8);
+        }
+
+        let starts = word_starts(&chars);
+        if let Some(hits) = best_word_match(&lower, &starts, &input) {
+            let hit_ratio = hits as f64 / input.len() as f64;
+            return 0.3f64.mul_add(hit_ratio, 0.1f64.mul_add(coverage, 0.4));
+        }
+        if is_subsequence(&lower, &input) {
+            return 0.3 * coverage;
+        }
+        0.0
+    }
+
+    fn ignores_case(&self) -> bool {
+        true
+    }
+}
+
+/// Marks the characters that start a word: the first character, an upper case letter
+/// following a lower case one, the first digit of a number and anything after a separator.
+fn word_starts(chars: &[char]) -> Vec<bool> {
+    chars
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            if !c.is_alphanumeric() {
+                return false;
+            }
+            let Some(&prev) = i.checked_sub(1).and_then(|p| chars.get(p)) else {
+                return true;
+            };
+            !prev.is_alphanumeric()
+                || (prev.is_lowercase() && c.is_uppercase())
+                || (!prev.is_numeric() && c.is_numeric())
+        })
+        .collect()
+}
+
+/// Matches `input` against `lower` where each character either continues the previous
+/// match or jumps to a word start. Returns the most word starts hit by any such match.
+fn best_word_match(lower: &[char], starts: &[bool], input: &[char]) -> Option<usize> {
+    // best[j] holds the most word starts hit when the current input char lands on `j`.
+    let mut best: Vec<Option<usize>> = lower
+        .iter()
+        .zip(starts)
+        .map(|(&c, &start)| (start && c == input[0]).then_some(1))
+        .collect();
+    for &ic in &input[1..] {
+        let mut next = vec![None; lower.len()];
+        let mut best_before: Option<usize> = None;
+        for j in 0..lower.len() {
+            if lower[j] == ic {
+                let contiguous = j.checked_sub(1).and_then(|p| best[p]);
+                let jump = if starts[j] {
+                    best_before.map(|hits| hits + 1)
+                } else {
+                    None
+                };
+                next[j] = contiguous.max(jump);
+            }
+            best_before = best_before.max(best[j]);
+        }
+        best = next;
+    }
+    best.into_iter().flatten().max()
+}
+
+fn is_subsequence(haystack: &[char], needle: &[char]) -> bool {
+    let mut needle = needle.iter().peekable();
+    for c in haystack {
+        if needle.peek() == Some(&c) {
+            needle.next();
+        }
+    }
+    needle.peek().is_none()
+}
+
+/// The fuzzy matching algorithm used by [fzf](https://github.com/junegunn/fzf) (`--algo=v2`)
+///
+/// A Smith-Waterman style alignment which rewards consecutive characters and matches at
+/// word boundaries, and penalizes gaps. Matching is case insensitive and candidates that
+/// don't contain the input as a subsequence score `0.0`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FzfV2;
+
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+const BONUS_BOUNDARY: i32 = SCORE_MATCH / 2;
+const BONUS_NON_WORD: i32 = SCORE_MATCH / 2;
+const BONUS_CAMEL_123: i32 = BONUS_BOUNDARY + SCORE_GAP_EXTENSION;
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+const BONUS_BOUNDARY_WHITE: i32 = BONUS_BOUNDARY + 2;
+const BONUS_BOUNDARY_DELIMITER: i32 = BONUS_BOUNDARY + 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CharClass {
+    White,
+    NonWord,
+    Delimiter,
+    Lower,
+    Upper,
+    Letter,
+    Number,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_whitespace() {
+            Self::White
+        } else if matches!(c, '/' | '\\' | ',' | ':' | ';' | '|') {
+            Self::Delimiter
+        } else if c.is_lowercase() {
+            Self::Lower
+        } else if c.is_uppercase() {
+            Self::Upper
+        } else if c.is_numeric() {
+            Self::Number
+        } else if c.is_alphabetic() {
+            Self::Letter
+        } else {
+            Self::NonWord
+        }
+    }
+}
+
+fn fzf_bonus(prev: CharClass, class: CharClass) -> i32 {
+    if class > CharClass::Delimiter {
+        match prev {
+            CharClass::White => return BONUS_BOUNDARY_WHITE,
+            CharClass::Delimiter => return BONUS_BOUNDARY_DELIMITER,
+            CharClass::NonWord => return BONUS_BOUNDARY,
+            _ => {}
+        }
+    }
+    if (prev == CharClass::Lower && class == CharClass::Upper)
+        || (prev != CharClass::Number && class == CharClass::Number)
+    {
+        return BONUS_CAMEL_123;
+    }
+    match class {
+        CharClass::NonWord | CharClass::Delimiter => BONUS_NON_WORD,
+        CharClass::White => BONUS_BOUNDARY_WHITE,
+        _ => 0,
+    }
+}
+
+impl Scorer for FzfV2 {
+    fn score(&self, candidate: &str, input: &str) -> f64 {
+        let pattern: Vec<char> = input.chars().map(lowercase_char).collect();
+        let chars: Vec<char> = candidate.chars().collect();
+        let text: Vec<char> = chars.iter().copied().map(lowercase_char).collect();
+        if pattern.is_empty() || !is_subsequence(&text, &pattern) {
+            return 0.0;
+        }
+
+        let mut prev_class = CharClass::White;
+        let bonus: Vec<i32> = chars
+            .iter()
+            .map(|&c| {
+                let class = CharClass::of(c);
+                let bonus = fzf_bonus(prev_class, class);
+                prev_class = class;
+                bonus
+            })
+            .collect();
+
+        // h[j]: best score for the pattern so far ending at or before `j`,
+        // c[j]: length of the consecutive run ending at `j`,
+        // reach[j]: the pattern so far can be matched within `text[..=j]`.
+        let n = text.len();
+        let mut h = vec![0i32; n];
+        let mut c = vec![0i32; n];
+        let mut reach = vec![false; n];
+        let mut in_gap = false;
+        for j in 0..n {
+            let left = if j > 0 { h[j - 1] } else { 0 };
+            reach[j] = text[j] == pattern[0] || (j > 0 && reach[j - 1]);
+            if text[j] == pattern[0] {
+                h[j] = SCORE_MATCH + bonus[j] * BONUS_FIRST_CHAR_MULTIPLIER;
+                c[j] = 1;
+                in_gap = false;
+            } else {
+                let gap = if in_gap {
+                    SCORE_GAP_EXTENSION
+                } else {
+                    SCORE_GAP_START
+                };
+                h[j] = (left + gap).max(0);
+                c[j] = 0;
+                in_gap = true;
+            }
+        }
+        let mut max_score = if pattern.len() == 1 {
+            h.iter().copied().max().unwrap_or(0)
+        } else {
+            0
+        };
+
+        for (i, &pc) in pattern.iter().enumerate().skip(1) {
+            let mut row_h = vec![0i32; n];
+            let mut row_c = vec![0i32; n];
+            let mut row_reach = vec![false; n];
+            let mut in_gap = false;
+            for j in i..n {
+                let left = if j > i { row_h[j - 1] } else { 0 };
+                let s2 = left
+                    + if in_gap {
+                        SCORE_GAP_EXTENSION
+                    } else {
+                        SCORE_GAP_START
+                    };
+                let mut s1 = 0;
+                let mut consecutive = 0;
+                let matched = pc == text[j] && reach[j - 1];
+                row_reach[j] = matched || row_reach[j - 1];
+                if matched {
+                    s1 = h[j - 1] + SCORE_MATCH;
+                    let mut b = bonus[j];
+                    consecutive = c[j - 1] + 1;
+                    if consecutive > 1 {
+                        let first = bonus[j + 1 - consecutive as usize];
+                        if b >= BONUS_BOUNDARY && b > first {
+                            consecutive = 1;
+                        } else {
+                            b = b.max(BONUS_CONSECUTIVE).max(first);
+                        }
+                    }
+                    if s1 + b < s2 {
+                        s1 += bonus[j];
+                        consecutive = 0;
+                    } else {
+                        s1 += b;
+                    }
+                }
+                row_c[j] = consecutive;
+                in_gap = s1 < s2;
+                row_h[j] = s1.max(s2).max(0);
+                if i == pattern.len() - 1 {
+                    max_score = max_score.max(row_h[j]);
+                }
+            }
+            h = row_h;
+            c = row_c;
+            reach = row_reach;
+        }
+        f64::from(max_score)
+    }
+
+    fn ignores_case(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_starts_camel_and_separators() {
+        let chars: Vec<char> = "SearchBuilder_v2.rs".chars().collect();
+        let starts: String = word_starts(&chars)
+            .iter()
+            .zip(&chars)
+            .filter_map(|(&s, &c)| s.then_some(c))
+            .collect();
+        assert_eq!(starts, "SBv2r");
+    }
+
+    #[test]
+    fn acronym_prefers_word_starts() {
+        let acronym = Acronym.score("SearchBuilder.rs", "sb");
+        let scattered = Acronym.score("subscribe.rs", "sb");
+        assert!(acronym > scattered, "{acronym} <= {scattered}");
+    }
+
+    #[test]
+    fn fzf_rewards_consecutive_matches() {
+        let consecutive = FzfV2.score("builder.rs", "build");
+        let scattered = FzfV2.score("bxuxixlxd.rs", "build");
+        assert!(consecutive > scattered, "{consecutive} <= {scattered}");
+        assert!(FzfV2.score("main.rs", "xyz") == 0.0);
+    }
+
+    #[test]
+    fn input_and_candidates_lowercase_alike() {
+        // `İ` lowercases to two chars as a string, which must not shift the pattern.
+        assert!(FzfV2.score("İstanbul.txt", "İst") > 0.0);
+        assert!(Acronym.score("İstanbul.txt", "İstanbul") > 0.99);
+        assert_eq!(prepare_input(&JaroWinkler, "ReadMe"), "readme");
+        assert_eq!(prepare_input(&|_: &str, _: &str| 0.0, "ReadMe"), "ReadMe");
+    }
+}
@@ -1,25 +1,86 @@
 use std::{
     ffi::OsStr,
-    path::Path,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
-use crate::{filter::FilterType, utils, SearchBuilder};
-use crossbeam_channel::Sender;
+#[cfg(feature = "archives")]
+use crate::archive;
+use crate::{
+    content::ContentMatcher, filter::FilterType, query::Expr, result::FileKind, stats::Counters,
+    utils, vfs::Walk, Exec, ExecReport, FileSystem, PathStyle, SearchBuilder, SearchResult,
+    SearchStats,
+};
+use crossbeam_channel::{Receiver, Sender};
 use ignore::types::TypesBuilder;
-use ignore::{WalkBuilder, WalkState};
+use ignore::{DirEntry, WalkBuilder, WalkState};
 
 /// Matcher strategy for the walk callback.
-enum Matcher {
+pub enum Matcher {
     /// The types pre-filter already handles extension matching; accept all entries.
     AcceptAll,
     /// Simple extension-only check (fallback when types filter setup failed).
     ExtOnly(String),
     /// Full regex matching on file names.
     Regex(regex::Regex),
+    /// Full regex matching on the raw bytes of file names, keeps non-UTF-8 names matchable.
+    Bytes(regex::bytes::Regex),
+}
+
+impl Matcher {
+    /// Determine the matcher strategy based on search parameters.
+    pub fn new(options: &SearchBuilder, types_filter_active: bool, match_bytes: bool) -> Self {
+        let search_input = options.search_input.as_deref();
+        let file_ext = options.file_ext.as_deref();
+        let (strict, ignore_case) = (options.strict, options.ignore_case);
+
+        if search_input.is_none() && !strict && !ignore_case {
+            if file_ext.is_some() && types_filter_active {
+                // Types pre-filter handles extension matching; no additional check needed.
+                return Self::AcceptAll;
+            } else if let Some(ext) = file_ext {
+                // Fallback: simple extension comparison.
+                return Self::ExtOnly(ext.to_owned());
+            }
+        }
+        if match_bytes {
+            Self::Bytes(utils::build_bytes_regex_search_input(
+                search_input,
+                file_ext,
+                strict,
+                ignore_case,
+            ))
+        } else {
+            Self::Regex(utils::build_regex_search_input(
+                search_input,
+                file_ext,
+                strict,
+                ignore_case,
+            ))
+        }
+    }
+
+    fn is_match(&self, entry: &DirEntry) -> bool {
+        self.is_match_path(
+            entry.path(),
+            entry.file_type().is_some_and(|ft| ft.is_dir()),
+        )
+    }
+
+    /// Match the file name of `path`, which is a directory if `is_dir` is set.
+    pub fn is_match_path(&self, path: &Path, is_dir: bool) -> bool {
+        match self {
+            Self::AcceptAll => !is_dir,
+            Self::ExtOnly(ext) => path.extension() == Some(OsStr::new(ext.as_str())),
+            Self::Regex(reg_exp) => path.file_name().is_some_and(|file_name| {
+                let file_name = file_name.to_string_lossy();
+                reg_exp.is_match(&file_name)
+            }),
+            Self::Bytes(reg_exp) => path
+                .file_name()
+                .is_some_and(|file_name| reg_exp.is_match(file_name.as_encoded_bytes())),
+        }
+    }
 }
 
 /// A struct that holds the receiver for the search results
@@ -56,162 +117,511 @@ enum Matcher {
 ///     .build()
 ///     .collect();
 /// ```
-pub struct Search {
-    rx: Box<dyn Iterator<Item = String>>,
+///
+/// ## Keep paths that are not valid UTF-8
+///
+/// ```
+/// use rust_search::SearchBuilder;
+/// use std::path::PathBuf;
+///
+/// let paths_vec: Vec<PathBuf> = SearchBuilder::default()
+///     .location("src")
+///     .ext("rs")
+///     .build_paths()
+///     .collect();
+/// ```
+pub struct Search<T = String> {
+    rx: Box<dyn Iterator<Item = T>>,
+    stats: Arc<Counters>,
 }
 
-impl Iterator for Search {
-    type Item = String;
+impl<T> Iterator for Search<T> {
+    type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.rx.next()
     }
 }
 
-impl Search {
-    /// Search for files in a given arguments
+impl Search<SearchResult> {
+    /// Search for files matching the options of a [`SearchBuilder`]
     /// ### Arguments
-    /// * `search_location` - The location to search in
-    /// * `search_input` - The search input, defaults to any word
-    /// * `file_ext` - The file extension to search for, defaults to any file extension
-    /// * `depth` - The depth to search to, defaults to no limit
-    /// * `limit` - The limit of results to return, defaults to no limit
-    /// * `strict` - Whether to search for the exact word or not
-    /// * `ignore_case` - Whether to ignore case or not
-    /// * `hidden` - Whether to search hidden files or not
-    /// * `filters` - Vector of filters to search by `DirEntry` data
-    #[allow(clippy::too_many_arguments)]
-    pub(crate) fn new(
-        search_location: impl AsRef<Path>,
-        more_locations: Option<Vec<impl AsRef<Path>>>,
-        search_input: Option<&str>,
-        file_ext: Option<&str>,
-        depth: Option<usize>,
-        limit: Option<usize>,
-        strict: bool,
-        ignore_case: bool,
-        with_hidden: bool,
-        filters: Vec<FilterType>,
-    ) -> Self {
-        let mut walker = WalkBuilder::new(search_location);
-
-        // Use more threads than CPUs for I/O-bound work: while one thread
-        // waits for I/O, others can make progress.
-        let cpus = std::thread::available_parallelism().map_or(8, std::num::NonZero::get);
-        let thread_count = cpus * 2;
-
-        walker
-            .hidden(!with_hidden)
-            .git_ignore(true)
-            .max_depth(depth)
-            .threads(thread_count);
-
-        // Pre-filter by extension using ignore's type system when possible.
-        // This avoids calling our callback for non-matching files.
-        let mut types_filter_active = false;
-        if let Some(ext) = file_ext {
-            let mut types = TypesBuilder::new();
-            if types.add("custom", &format!("*.{ext}")).is_ok() {
-                types.select("custom");
-                if let Ok(built) = types.build() {
-                    walker.types(built);
-                    types_filter_active = true;
-                }
-            }
+    /// * `options` - The builder holding the location, input, extension, depth, limit,
+    ///   flags and filters to search with
+    /// * `match_bytes` - Whether to match the raw bytes of file names instead of a lossy string
+    /// * `with_metadata` - Whether to read the size and modification time of every result
+    pub(crate) fn new(options: &SearchBuilder, match_bytes: bool, with_metadata: bool) -> Self {
+        let stats = Arc::new(Counters::new());
+        if let Some(fs) = &options.file_system {
+            let fs = Arc::clone(fs);
+            return Self::on_file_system(fs, options, match_bytes, with_metadata, stats);
         }
+        let (walker, types_filter_active) = build_walker(options, Some(&stats));
+        let (limit, max_depth) = (options.limit, options.depth);
 
-        // Determine the matcher strategy based on search parameters.
-        let matcher = if search_input.is_none() && !strict && !ignore_case {
-            if file_ext.is_some() && types_filter_active {
-                // Types pre-filter handles extension matching; no additional check needed.
-                Matcher::AcceptAll
-            } else if let Some(ext) = file_ext {
-                // Fallback: simple extension comparison.
-                Matcher::ExtOnly(ext.to_owned())
-            } else {
-                Matcher::Regex(utils::build_regex_search_input(
-                    search_input,
-                    file_ext,
-                    strict,
-                    ignore_case,
-                ))
-            }
-        } else {
-            Matcher::Regex(utils::build_regex_search_input(
-                search_input,
-                file_ext,
-                strict,
-                ignore_case,
-            ))
-        };
-
-        // Only apply filter_entry if there are filters to check
-        if !filters.is_empty() {
-            walker.filter_entry(move |dir| filters.iter().all(|f| f.apply(dir)));
-        }
+        let (tx, rx) = crossbeam_channel::unbounded::<SearchResult>();
+        let roots = options.locations().map(Arc::from).collect();
+        let rules = Arc::new(Rules {
+            matcher: Matcher::new(options, types_filter_active, match_bytes),
+            query: options.query.clone(),
+            content: options
+                .content
+                .as_ref()
+                .map(|pattern| ContentMatcher::new(pattern, options)),
+            roots,
+            path_style: options.path_style.resolve(),
+            with_metadata,
+            detailed_stats: options.detailed_stats,
+        });
+        let listing = options
+            .detailed_stats
+            .then(|| Arc::new(Listing::new(options, types_filter_active)));
+        #[cfg(feature = "archives")]
+        let archives = Arc::new(Archives {
+            max_depth: options.archive_depth,
+            filters: options.filters.clone(),
+            hidden: options.hidden,
+        });
+        let progress = options.progress.clone();
+        let walk_stats = Arc::clone(&stats);
 
-        if let Some(locations) = more_locations {
-            for location in locations {
-                walker.add(location);
-            }
-        }
+        // Walk in the background so results can be consumed as they are found. The walker
+        // threads hold the only senders, so the receiver ends once they are done.
+        std::thread::spawn(move || {
+            let stats = walk_stats;
+            let reporter = progress.map(|progress| progress.spawn(Arc::clone(&stats)));
+            walker.build_parallel().run(|| {
+                let tx: Sender<SearchResult> = tx.clone();
+                let rules = Arc::clone(&rules);
+                let stats = Arc::clone(&stats);
+                let listing = listing.clone();
+                #[cfg(feature = "archives")]
+                let archives = Arc::clone(&archives);
 
-        let (tx, rx) = crossbeam_channel::unbounded::<String>();
-        let matcher = Arc::new(matcher);
-        let counter = Arc::new(AtomicUsize::new(0));
-
-        walker.build_parallel().run(|| {
-            let tx: Sender<String> = tx.clone();
-            let matcher = Arc::clone(&matcher);
-            let counter = Arc::clone(&counter);
-
-            Box::new(move |path_entry| {
-                if let Ok(entry) = path_entry {
-                    // Check match using borrowed path first, then convert to owned
-                    // only if matched (avoids allocation for non-matching entries).
-                    let is_match = match matcher.as_ref() {
-                        Matcher::AcceptAll => entry.file_type().is_some_and(|ft| !ft.is_dir()),
-                        Matcher::ExtOnly(ext) => {
-                            entry.path().extension() == Some(OsStr::new(ext.as_str()))
-                        }
-                        Matcher::Regex(reg_exp) => {
-                            entry.path().file_name().is_some_and(|file_name| {
-                                let file_name = file_name.to_string_lossy();
-                                reg_exp.is_match(&file_name)
-                            })
+                Box::new(move |path_entry| {
+                    let Ok(entry) = path_entry else {
+                        stats.error();
+                        return WalkState::Continue;
+                    };
+                    let entered = stats.walked(&entry, max_depth);
+                    if let Some(listing) = listing.as_ref().filter(|_| entered) {
+                        stats.listed(listing.count(entry.path()));
+                    }
+                    let send = |result: SearchResult, bytes: u64| {
+                        let sent = stats.try_match(limit) && tx.send(result).is_ok();
+                        if sent {
+                            stats.bytes_matched(bytes);
                         }
+                        sent
                     };
-                    if is_match {
-                        if limit.is_none_or(|l| counter.fetch_add(1, Ordering::Relaxed) < l) {
-                            // Use into_path() for zero-copy PathBuf, then try zero-copy
-                            // String conversion (succeeds for valid UTF-8 paths).
-                            let path_string = entry
-                                .into_path()
-                                .into_os_string()
-                                .into_string()
-                                .unwrap_or_else(|os| os.to_string_lossy().into_owned());
-                            if tx.send(path_string).is_ok() {
-                                return WalkState::Continue;
+                    #[cfg(feature = "archives")]
+                    if let Some(state) = archives.search(&entry, &rules, &stats, &send) {
+                        return state;
+                    }
+                    match rules.check(entry) {
+                        Some((result, bytes)) => {
+                            if send(result, bytes) {
+                                WalkState::Continue
+                            } else {
+                                WalkState::Quit
                             }
                         }
-                        return WalkState::Quit;
+                        None => WalkState::Continue,
                     }
+                })
+            });
+            stats.finish();
+            if let Some(reporter) = reporter {
+                reporter.finish();
+            }
+        });
+
+        Self::from_receiver(rx, limit, stats)
+    }
+
+    /// Like [`new`](Self::new), but walking `fs` instead of the disk.
+    fn on_file_system(
+        fs: Arc<dyn FileSystem>,
+        options: &SearchBuilder,
+        match_bytes: bool,
+        with_metadata: bool,
+        stats: Arc<Counters>,
+    ) -> Self {
+        let walk = Walk {
+            fs,
+            matcher: Matcher::new(options, false, match_bytes),
+            query: options.query.clone(),
+            content: options
+                .content
+                .as_ref()
+                .map(|pattern| ContentMatcher::new(pattern, options)),
+            filters: options.filters.clone(),
+            hidden: options.hidden,
+            max_depth: options.depth,
+            path_style: options.path_style.resolve(),
+            with_metadata,
+            stats: Arc::clone(&stats),
+            detailed_stats: options.detailed_stats,
+        };
+        let roots: Vec<Arc<Path>> = options.locations().map(Arc::from).collect();
+        let limit = options.limit;
+        let (tx, rx) = crossbeam_channel::unbounded::<SearchResult>();
+        let progress = options.progress.clone();
+
+        std::thread::spawn(move || {
+            let stats = Arc::clone(&walk.stats);
+            let reporter = progress.map(|progress| progress.spawn(Arc::clone(&stats)));
+            let mut send = |result, bytes| {
+                let sent = stats.try_match(limit) && tx.send(result).is_ok();
+                if sent {
+                    stats.bytes_matched(bytes);
                 }
-                WalkState::Continue
-            })
+                sent
+            };
+            for root in &roots {
+                if !walk.run(root, &mut send) {
+                    break;
+                }
+            }
+            stats.finish();
+            if let Some(reporter) = reporter {
+                reporter.finish();
+            }
         });
 
-        // Drop the sender so the receiver knows when all results have been sent
-        drop(tx);
+        Self::from_receiver(rx, limit, stats)
+    }
 
+    fn from_receiver(
+        rx: Receiver<SearchResult>,
+        limit: Option<usize>,
+        stats: Arc<Counters>,
+    ) -> Self {
         if let Some(limit) = limit {
             Self {
                 rx: Box::new(rx.into_iter().take(limit)),
+                stats,
             }
         } else {
             Self {
                 rx: Box::new(rx.into_iter()),
+                stats,
+            }
+        }
+    }
+}
+
+/// What the entries of the walk of the disk are matched with and how results are made.
+struct Rules {
+    matcher: Matcher,
+    query: Option<Expr>,
+    content: Option<ContentMatcher>,
+    roots: Vec<Arc<Path>>,
+    path_style: PathStyle,
+    with_metadata: bool,
+    detailed_stats: bool,
+}
+
+impl Rules {
+    /// The result for `entry` and the bytes it counts for in the stats, if it matches.
+    fn check(&self, entry: DirEntry) -> Option<(SearchResult, u64)> {
+        // Check match using borrowed path first, then convert to owned
+        // only if matched (avoids allocation for non-matching entries).
+        if !self.matcher.is_match(&entry) || self.query.as_ref().is_some_and(|q| !q.matches(&entry))
+        {
+            return None;
+        }
+        // Content is only read once the name and extension matched.
+        let lines = match &self.content {
+            Some(content) => content.search(&entry)?,
+            None => Vec::new(),
+        };
+        let root = root_of(&entry, &self.roots);
+        let kind = FileKind::from_file_type(entry.file_type());
+        let depth = entry.depth();
+        let count_bytes = self.detailed_stats && kind == FileKind::File;
+        let metadata = (self.with_metadata || count_bytes)
+            .then(|| entry.metadata().ok())
+            .flatten();
+        let bytes = metadata
+            .as_ref()
+            .filter(|_| count_bytes)
+            .map_or(0, std::fs::Metadata::len);
+        // Use into_path() for a zero-copy PathBuf.
+        let (path, found) = self.path_style.apply_keeping(entry.into_path(), &root);
+        let mut result = SearchResult::new(path, root, lines, kind, depth).found_at(found);
+        if let Some(metadata) = metadata.filter(|_| self.with_metadata) {
+            result = result.with_metadata(&metadata);
+        }
+        Some((result, bytes))
+    }
+}
+
+/// Lists the directories of the walk of the disk to count the entries it should reach, to
+/// estimate the ignored entries from those it didn't, see
+/// [`SearchStats::ignored`](crate::SearchStats::ignored).
+struct Listing {
+    hidden: bool,
+    /// The suffix of the files kept by the extension pre-filter of the walker, if active.
+    suffix: Option<String>,
+}
+
+impl Listing {
+    fn new(options: &SearchBuilder, types_filter_active: bool) -> Self {
+        Self {
+            hidden: options.hidden,
+            suffix: options
+                .file_ext
+                .as_ref()
+                .filter(|_| types_filter_active)
+                .map(|ext| format!(".{ext}")),
+        }
+    }
+
+    /// How many entries of `dir` the walker will reach unless the ignore files or
+    /// filters leave them out.
+    fn count(&self, dir: &Path) -> usize {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return 0;
+        };
+        entries
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.as_encoded_bytes();
+                let is_dir = entry.file_type().is_ok_and(|ft| ft.is_dir());
+                (self.hidden || !name.starts_with(b"."))
+                    && (is_dir
+                        || self
+                            .suffix
+                            .as_ref()
+                            .is_none_or(|suffix| name.ends_with(suffix.as_bytes())))
+            })
+            .count()
+    }
+}
+
+/// The settings for searching inside archives, see
+/// [`SearchBuilder::archives`](crate::SearchBuilder::archives).
+#[cfg(feature = "archives")]
+struct Archives {
+    max_depth: usize,
+    filters: Vec<FilterType>,
+    hidden: bool,
+}
+
+#[cfg(feature = "archives")]
+impl Archives {
+    /// Send the members of the archive of `entry` that match the `rules`, if it is one.
+    ///
+    /// Returns what the walk should do next when it is done with `entry`: quit once
+    /// `send` asks to stop, or skip an archive that doesn't pass the filters, which are
+    /// not applied to archives while walking so their members are still searched.
+    /// Archives that can't be read are skipped.
+    fn search(
+        &self,
+        entry: &DirEntry,
+        rules: &Rules,
+        stats: &Counters,
+        send: &dyn Fn(SearchResult, u64) -> bool,
+    ) -> Option<WalkState> {
+        if self.max_depth == 0
+            || !entry.file_type().is_some_and(|ft| ft.is_file())
+            || !archive::is_archive(entry.path())
+        {
+            return None;
+        }
+        let member_rules = archive::MemberRules {
+            matcher: &rules.matcher,
+            query: rules.query.as_ref(),
+            content: rules.content.as_ref(),
+            filters: &self.filters,
+            hidden: self.hidden,
+        };
+        let root = root_of(entry, &rules.roots);
+        let shown = rules.path_style.apply(entry.path().to_path_buf(), &root);
+        let mut keep_going = true;
+        let _ = archive::scan_file(entry.path(), &shown, self.max_depth, &mut |member| {
+            let Some(lines) = member_rules.matches(member) else {
+                return true;
+            };
+            let depth = entry.depth() + member.depth;
+            let path = member.path.clone();
+            let mut result = SearchResult::new(path, Arc::clone(&root), lines, member.kind, depth);
+            if rules.with_metadata {
+                result = result.with_size_and_modified(member.size, member.modified);
             }
+            let count_bytes = rules.detailed_stats && member.kind == FileKind::File;
+            keep_going = send(result, if count_bytes { member.size } else { 0 });
+            keep_going
+        });
+        if !keep_going {
+            Some(WalkState::Quit)
+        } else if self.filters.iter().all(|f| f.apply(entry)) {
+            None
+        } else {
+            stats.filtered();
+            Some(WalkState::Continue)
+        }
+    }
+}
+
+/// The location `entry` was found in, its `depth` components up from the entry.
+fn root_of(entry: &DirEntry, roots: &[Arc<Path>]) -> Arc<Path> {
+    let root = entry
+        .path()
+        .ancestors()
+        .nth(entry.depth())
+        .unwrap_or_else(|| entry.path());
+    roots
+        .iter()
+        .find(|location| location.as_ref() == root)
+        .map_or_else(|| Arc::from(root), Arc::clone)
+}
+
+/// Whether `path` is an archive whose members can be searched.
+#[cfg(feature = "archives")]
+fn is_archive(path: &Path) -> bool {
+    archive::is_archive(path)
+}
+
+#[cfg(not(feature = "archives"))]
+const fn is_archive(_: &Path) -> bool {
+    false
+}
+
+/// Set up the walker for the locations, depth, hidden flag and filters of the search,
+/// counting the entries the filters leave out in `stats`.
+///
+/// Also returns whether the extension is already pre-filtered by the walker.
+pub fn build_walker(options: &SearchBuilder, stats: Option<&Arc<Counters>>) -> (WalkBuilder, bool) {
+    let mut walker = WalkBuilder::new(&options.search_location);
+
+    // Use more threads than CPUs for I/O-bound work: while one thread
+    // waits for I/O, others can make progress.
+    let cpus = std::thread::available_parallelism().map_or(8, std::num::NonZero::get);
+    let thread_count = cpus * 2;
+
+    walker
+        .hidden(!options.hidden)
+        .git_ignore(true)
+        .max_depth(options.depth)
+        .threads(thread_count);
+
+    // Pre-filter by extension using ignore's type system when possible.
+    // This avoids calling our callback for non-matching files.
+    let mut types_filter_active = false;
+    #[cfg(feature = "archives")]
+    let archives = options.archive_depth > 0;
+    #[cfg(not(feature = "archives"))]
+    let archives = false;
+    // Archives must reach the callback whatever their extension, to search their members.
+    if let Some(ext) = options.file_ext.as_ref().filter(|_| !archives) {
+        let mut types = TypesBuilder::new();
+        if types.add("custom", &format!("*.{ext}")).is_ok() {
+            types.select("custom");
+            if let Ok(built) = types.build() {
+                walker.types(built);
+                types_filter_active = true;
+            }
+        }
+    }
+
+    // Only apply filter_entry if there are filters to check
+    if !options.filters.is_empty() {
+        let filters: Vec<FilterType> = options.filters.clone();
+        let stats = stats.map(Arc::clone);
+        walker.filter_entry(move |dir| {
+            let keep = filters.iter().all(|f| f.apply(dir))
+                || archives
+                    && dir.file_type().is_some_and(|ft| ft.is_file())
+                    && is_archive(dir.path());
+            if let Some(stats) = stats.as_ref().filter(|_| !keep) {
+                stats.pruned();
+            }
+            keep
+        });
+    }
+
+    for location in options.locations().skip(1) {
+        walker.add(location);
+    }
+
+    (walker, types_filter_active)
+}
+
+impl<T: 'static> Search<T> {
+    /// Yield items that were already found.
+    pub(crate) fn from_vec(items: Vec<T>) -> Self {
+        Self {
+            stats: Arc::new(Counters::done(items.len())),
+            rx: Box::new(items.into_iter()),
+        }
+    }
+}
+
+impl<T> Search<T> {
+    /// What the search has done so far, or in total once all results were taken
+    ///
+    /// Can be read while iterating, e.g. through [`by_ref`](Iterator::by_ref).
+    /// ### Examples
+    /// ```rust
+    /// use rust_search::SearchBuilder;
+    ///
+    /// let mut search = SearchBuilder::default().location("src").ext("rs").build();
+    /// for path in search.by_ref().take(3) {
+    ///     println!("{path}");
+    /// }
+    /// println!("{} files scanned so far", search.stats().entries());
+    /// ```
+    pub fn stats(&self) -> SearchStats {
+        self.stats.snapshot()
+    }
+}
+
+impl<T: AsRef<Path>> Search<T> {
+    /// Run a command for every path found, see [`Exec`]. Results of
+    /// [`build_results`](crate::SearchBuilder::build_results) are run at their
+    /// [`disk_path`](crate::SearchResult::disk_path), whatever the [`PathStyle`](crate::PathStyle).
+    /// ### Arguments
+    /// * `exec` - The command template and how to run it.
+    /// ### Examples
+    /// ```rust,no_run
+    /// use rust_search::{Exec, SearchBuilder};
+    ///
+    /// let report = SearchBuilder::default()
+    ///     .location("~/photos")
+    ///     .ext("heic")
+    ///     .build()
+    ///     .exec(&Exec::new("convert {} {.}.jpg").unwrap());
+    /// assert!(report.success());
+    /// ```
+    pub fn exec(self, exec: &Exec) -> ExecReport {
+        exec.run(self)
+    }
+}
+
+impl Search<PathBuf> {
+    /// Drop the locations of the found paths.
+    pub(crate) fn from_results(results: Search<SearchResult>) -> Self {
+        Self {
+            stats: Arc::clone(&results.stats),
+            rx: Box::new(results.map(SearchResult::into_path)),
+        }
+    }
+}
+
+impl Search {
+    /// Convert the found paths into strings, lossily for paths that are not valid UTF-8.
+    pub(crate) fn from_paths(paths: Search<PathBuf>) -> Self {
+        Self {
+            stats: Arc::clone(&paths.stats),
+            // Zero-copy String conversion succeeds for valid UTF-8 paths.
+            rx: Box::new(paths.map(|path| {
+                path.into_os_string()
+                    .into_string()
+                    .unwrap_or_else(|os| os.to_string_lossy().into_owned())
+            })),
         }
     }
 }
@@ -0,0 +1,477 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    result::FileKind,
+    utils::{
+        epoch_parts, escape_path, from_epoch_parts, path_from_bytes, path_to_bytes, sha256_file,
+        unescape_path,
+    },
+    SearchResult,
+};
+
+/// The first line of a snapshot file, followed by the time it was taken.
+const HEADER: &str = "rust_search snapshot 1";
+
+/// The results of a search at one point in time, to find out what changed since
+///
+/// Keeps the kind, size, modification time, device and inode of every path and
+/// optionally the SHA-256 hash of every file. Take the snapshot from
+/// [`SearchBuilder::build_results`](crate::SearchBuilder::build_results), which reads the
+/// metadata, and search the same locations in the same way to compare snapshots.
+///
+/// The snapshot file is text with one tab-separated line per path, the path last with
+/// tabs, line breaks and backslashes escaped.
+/// ### Examples
+/// ```rust
+/// use rust_search::{SearchBuilder, Snapshot};
+///
+/// let search = SearchBuilder::default().location("src").ext("rs");
+/// let last_week = Snapshot::take_hashed(search.build_results());
+/// let path = std::env::temp_dir().join("rust_search_snapshot_doc.txt");
+/// last_week.save(&path).unwrap();
+///
+/// let last_week = Snapshot::load(&path).unwrap();
+/// let diff = last_week.diff(&Snapshot::take_hashed(search.build_results()));
+/// for (from, to) in diff.renamed() {
+///     println!("{} -> {}", from.display(), to.display());
+/// }
+/// assert!(diff.is_empty());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    taken: SystemTime,
+    entries: BTreeMap<PathBuf, SnapshotEntry>,
+}
+
+/// A path recorded in a [`Snapshot`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotEntry {
+    kind: FileKind,
+    size: Option<u64>,
+    modified: Option<SystemTime>,
+    inode: Option<(u64, u64)>,
+    hash: Option<String>,
+}
+
+impl SnapshotEntry {
+    fn new(result: &SearchResult) -> Self {
+        Self {
+            kind: result.kind(),
+            size: result.size(),
+            modified: result.modified(),
+            inode: result.dev().zip(result.inode()),
+            hash: None,
+        }
+    }
+
+    /// Whether the path is a file, directory, symlink or something else
+    pub const fn kind(&self) -> FileKind {
+        self.kind
+    }
+
+    /// The size in bytes, `None` if it wasn't known
+    pub const fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// The modification time, `None` if it wasn't known
+    pub const fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// The device and inode number, on Unix only
+    pub const fn inode(&self) -> Option<(u64, u64)> {
+        self.inode
+    }
+
+    /// The SHA-256 hash of the contents in lowercase hex, for files of a hashed snapshot
+    pub fn hash(&self) -> Option<&str> {
+        self.hash.as_deref()
+    }
+
+    /// Whether an entry with the same inode can be the same file, not a new one that
+    /// reused the inode.
+    fn same_file(&self, newer: &Self) -> bool {
+        self.kind == newer.kind
+            && match (&self.hash, &newer.hash) {
+                (Some(old), Some(new)) => old == new,
+                _ => true,
+            }
+    }
+
+    /// Whether the contents of the file seem to differ from `newer`, going by the hashes
+    /// when both have one and by the size and modification time otherwise.
+    fn differs(&self, newer: &Self) -> bool {
+        if self.kind != newer.kind {
+            return true;
+        }
+        if self.kind == FileKind::Dir {
+            return false;
+        }
+        match (&self.hash, &newer.hash) {
+            (Some(old), Some(new)) => old != new || self.size != newer.size,
+            _ => self.size != newer.size || self.modified != newer.modified,
+        }
+    }
+}
+
+impl Snapshot {
+    /// Record the results of a search
+    /// ### Arguments
+    /// * `results` - The results, from [`build_results`](crate::SearchBuilder::build_results).
+    pub fn take(results: impl IntoIterator<Item = SearchResult>) -> Self {
+        Self {
+            taken: SystemTime::now(),
+            entries: results
+                .into_iter()
+                .map(|result| {
+                    let entry = SnapshotEntry::new(&result);
+                    (result.into_path(), entry)
+                })
+                .collect(),
+        }
+    }
+
+    /// Record the results of a search with the SHA-256 hash of every file, hashed in parallel
+    ///
+    /// Files that can't be read are recorded without a hash.
+    /// ### Arguments
+    /// * `results` - The results, from [`build_results`](crate::SearchBuilder::build_results).
+    pub fn take_hashed(results: impl IntoIterator<Item = SearchResult>) -> Self {
+        let taken = SystemTime::now();
+        let results: Vec<SearchResult> = results.into_iter().collect();
+        let entries = results
+            .into_par_iter()
+            .map(|result| {
+                let mut entry = SnapshotEntry::new(&result);
+                if entry.kind == FileKind::File {
+                    entry.hash = sha256_file(result.disk_path()).ok();
+                }
+                (result.into_path(), entry)
+            })
+            .collect();
+        Self { taken, entries }
+    }
+
+    /// When the snapshot was taken
+    pub const fn taken(&self) -> SystemTime {
+        self.taken
+    }
+
+    /// The number of recorded paths
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no paths were recorded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The record of `path`, written as it was found by the search
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&SnapshotEntry> {
+        self.entries.get(path.as_ref())
+    }
+
+    /// The recorded paths in order, with their records
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &SnapshotEntry)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (path.as_path(), entry))
+    }
+
+    /// Compare the snapshot with a newer one of the same search
+    ///
+    /// Paths only in one snapshot are paired up as renames when they have the same
+    /// inode on the same device, or failing that the same hash. A renamed file whose
+    /// contents changed too is also reported as modified under its new path. Inodes of
+    /// deleted files can be reused, so when both snapshots are hashed the contents must
+    /// match for the inodes to count.
+    /// ### Arguments
+    /// * `newer` - The snapshot taken later.
+    pub fn diff(&self, newer: &Self) -> SnapshotDiff {
+        let mut diff = SnapshotDiff::default();
+        let mut deleted: Vec<(&PathBuf, &SnapshotEntry)> = self
+            .entries
+            .iter()
+            .filter(|(path, _)| !newer.entries.contains_key(*path))
+            .collect();
+        let mut added: Vec<(&PathBuf, &SnapshotEntry)> = Vec::new();
+        for (path, entry) in &newer.entries {
+            match self.entries.get(path) {
+                Some(old) if old.differs(entry) => diff.modified.push(path.clone()),
+                Some(_) => {}
+                None => added.push((path, entry)),
+            }
+        }
+
+        // Pair by inode first, then by hash among the rest.
+        let mut by_inode: HashMap<(u64, u64), usize> = HashMap::new();
+        for (i, (_, entry)) in deleted.iter().enumerate() {
+            if let Some(inode) = entry.inode {
+                by_inode.insert(inode, i);
+            }
+        }
+        let mut paired = vec![false; deleted.len()];
+        let mut unpaired = Vec::new();
+        for (path, entry) in added {
+            let same_inode = entry
+                .inode
+                .filter(|inode| {
+                    by_inode
+                        .get(inode)
+                        .is_some_and(|&i| deleted[i].1.same_file(entry))
+                })
+                .and_then(|inode| by_inode.remove(&inode));
+            match same_inode {
+                Some(i) => {
+                    paired[i] = true;
+                    diff.renamed.push((deleted[i].0.clone(), path.clone()));
+                    if deleted[i].1.differs(entry) {
+                        diff.modified.push(path.clone());
+                    }
+                }
+                None => unpaired.push((path, entry)),
+            }
+        }
+        let mut by_hash: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, (_, entry)) in deleted.iter().enumerate().rev() {
+            if let (false, Some(hash)) = (paired[i], entry.hash.as_deref()) {
+                by_hash.entry(hash).or_default().push(i);
+            }
+        }
+        for (path, entry) in unpaired {
+            let same_hash = entry
+                .hash
+                .as_deref()
+                .and_then(|hash| by_hash.get_mut(hash)?.pop());
+            match same_hash {
+                Some(i) => {
+                    paired[i] = true;
+                    diff.renamed.push((deleted[i].0.clone(), path.clone()));
+                }
+                None => diff.added.push(path.clone()),
+            }
+        }
+
+        let mut paired = paired.into_iter();
+        deleted.retain(|_| !paired.next().unwrap_or(false));
+        diff.deleted = deleted.into_iter().map(|(path, _)| path.clone()).collect();
+        diff.modified.sort_unstable();
+        diff.renamed.sort_unstable();
+        diff
+    }
+
+    /// Read a snapshot written by [`save`](Self::save)
+    /// ### Arguments
+    /// * `path` - The snapshot file.
+    /// ### Errors
+    /// Returns an error if the file can't be read or is not a snapshot.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    /// Write the snapshot to a file
+    /// ### Arguments
+    /// * `path` - The snapshot file.
+    /// ### Errors
+    /// Returns an error if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.encode())
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = format!("{HEADER}\t{}\n", format_time(Some(self.taken))).into_bytes();
+        for (path, entry) in &self.entries {
+            let mut line = String::new();
+            let _ = write!(
+                line,
+                "{}\t{}\t{}\t",
+                entry.kind.as_str(),
+                entry.size.map_or_else(|| "-".to_owned(), |s| s.to_string()),
+                format_time(entry.modified),
+            );
+            match entry.inode {
+                Some((dev, inode)) => {
+                    let _ = write!(line, "{dev}:{inode}\t");
+                }
+                None => line.push_str("-\t"),
+            }
+            line.push_str(entry.hash.as_deref().unwrap_or("-"));
+            line.push('\t');
+            out.extend_from_slice(line.as_bytes());
+            escape_path(&path_to_bytes(path), &mut out);
+            out.push(b'\n');
+        }
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut lines = bytes.split(|&b| b == b'\n').enumerate();
+        let taken = lines
+            .next()
+            .and_then(|(_, line)| std::str::from_utf8(line).ok()?.strip_prefix(HEADER))
+            .and_then(|rest| parse_time(rest.strip_prefix('\t')?).ok().flatten())
+            .ok_or_else(|| invalid(1, "not a snapshot file"))?;
+
+        let mut entries = BTreeMap::new();
+        for (i, line) in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let number = i + 1;
+            let mut fields = line.splitn(6, |&b| b == b'\t');
+            let mut field = |name: &str| {
+                fields
+                    .next()
+                    .ok_or_else(|| invalid(number, &format!("missing {name}")))
+            };
+            let mut text = |name: &str| {
+                field(name).and_then(|bytes| {
+                    std::str::from_utf8(bytes)
+                        .map_err(|_| invalid(number, &format!("invalid {name}")))
+                })
+            };
+            let kind = parse_kind(text("kind")?).ok_or_else(|| invalid(number, "invalid kind"))?;
+            let size = match text("size")? {
+                "-" => None,
+                size => Some(size.parse().map_err(|_| invalid(number, "invalid size"))?),
+            };
+            let modified =
+                parse_time(text("mtime")?).map_err(|()| invalid(number, "invalid mtime"))?;
+            let inode = match text("inode")? {
+                "-" => None,
+                inode => Some(
+                    inode
+                        .split_once(':')
+                        .and_then(|(dev, ino)| Some((dev.parse().ok()?, ino.parse().ok()?)))
+                        .ok_or_else(|| invalid(number, "invalid inode"))?,
+                ),
+            };
+            let hash = match text("hash")? {
+                "-" => None,
+                hash => Some(hash.to_owned()),
+            };
+            let path = unescape_path(
+                fields
+                    .next()
+                    .ok_or_else(|| invalid(number, "missing path"))?,
+            )
+            .ok_or_else(|| invalid(number, "invalid path"))?;
+            entries.insert(
+                path_from_bytes(path),
+                SnapshotEntry {
+                    kind,
+                    size,
+                    modified,
+                    inode,
+                    hash,
+                },
+            );
+        }
+        Ok(Self { taken, entries })
+    }
+}
+
+/// The differences between two [`Snapshot`]s, each list sorted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    added: Vec<PathBuf>,
+    deleted: Vec<PathBuf>,
+    modified: Vec<PathBuf>,
+    renamed: Vec<(PathBuf, PathBuf)>,
+}
+
+impl SnapshotDiff {
+    /// The paths only in the newer snapshot
+    pub fn added(&self) -> &[PathBuf] {
+        &self.added
+    }
+
+    /// The paths only in the older snapshot
+    pub fn deleted(&self) -> &[PathBuf] {
+        &self.deleted
+    }
+
+    /// The files in both snapshots whose contents changed, under their newer path
+    pub fn modified(&self) -> &[PathBuf] {
+        &self.modified
+    }
+
+    /// The paths that were moved, from the older path to the newer one
+    pub fn renamed(&self) -> &[(PathBuf, PathBuf)] {
+        &self.renamed
+    }
+
+    /// Whether nothing changed
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.deleted.is_empty()
+            && self.modified.is_empty()
+            && self.renamed.is_empty()
+    }
+}
+
+fn invalid(line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid snapshot: {message} on line {line}"),
+    )
+}
+
+fn parse_kind(kind: &str) -> Option<FileKind> {
+    [
+        FileKind::File,
+        FileKind::Dir,
+        FileKind::Symlink,
+        FileKind::Other,
+    ]
+    .into_iter()
+    .find(|k| k.as_str() == kind)
+}
+
+/// Write a time exactly as seconds and nanoseconds since the epoch, `-` if unknown.
+fn format_time(time: Option<SystemTime>) -> String {
+    time.map_or_else(
+        || "-".to_owned(),
+        |time| {
+            let (secs, nanos) = epoch_parts(time);
+            format!("{secs}.{nanos:09}")
+        },
+    )
+}
+
+fn parse_time(text: &str) -> Result<Option<SystemTime>, ()> {
+    if text == "-" {
+        return Ok(None);
+    }
+    let (secs, nanos) = text.split_once('.').ok_or(())?;
+    let nanos = nanos.parse().map_err(|_| ())?;
+    from_epoch_parts(secs.parse().map_err(|_| ())?, nanos)
+        .map(Some)
+        .ok_or(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_round_trip() {
+        let time = SystemTime::UNIX_EPOCH + std::time::Duration::new(1_706_691_907, 5);
+        assert_eq!(format_time(Some(time)), "1706691907.000000005");
+        assert_eq!(parse_time("1706691907.000000005"), Ok(Some(time)));
+        assert_eq!(parse_time("-"), Ok(None));
+        assert!(parse_time("yesterday").is_err());
+    }
+}
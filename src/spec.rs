@@ -0,0 +1,441 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    query::{parse_size, Expr},
+    FileSize, Query, SearchBuilder,
+};
+
+/// A search written down as data, to be loaded from and saved to TOML or JSON
+///
+/// Filters use the syntax of the [`Query`] fields of the same name, so relative times like
+/// `>90d` are evaluated when the spec is turned into a [`SearchBuilder`], not when it was
+/// written. All fields are optional.
+/// ### Examples
+/// ```rust
+/// use rust_search::SearchSpec;
+///
+/// let spec = SearchSpec::from_toml(r#"
+///     locations = ["src", "tests"]
+///     extensions = ["rs"]
+///     size = [">1KB"]
+///     modified = ["<30d"]
+///     hidden = true
+/// "#).unwrap();
+///
+/// let search: Vec<String> = spec.into_builder().unwrap().build().collect();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct SearchSpec {
+    /// The locations to search in, the first one is the main location. Defaults to the
+    /// current directory.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<PathBuf>,
+    /// The search input matched against file names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    /// The extensions to search for, any of them matches
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+    /// How many directories deep to search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<usize>,
+    /// The limit of results to return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Match the search input exactly
+    #[serde(skip_serializing_if = "is_false")]
+    pub strict: bool,
+    /// Match the search input regardless of case
+    #[serde(skip_serializing_if = "is_false")]
+    pub ignore_case: bool,
+    /// Search hidden files and directories too
+    #[serde(skip_serializing_if = "is_false")]
+    pub hidden: bool,
+    /// Size filters, e.g. `>2MB`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub size: Vec<String>,
+    /// Modification time filters, e.g. `>90d` or `<2024-01-31`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modified: Vec<String>,
+    /// Creation time filters, e.g. `<1w`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub created: Vec<String>,
+    /// A one-line [`Query`] every match must satisfy too
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Text the contents of the files must contain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contains: Option<String>,
+    /// Regular expression a line of the files must match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contains_regex: Option<String>,
+    /// Search the contents of binary files too
+    #[serde(skip_serializing_if = "is_false")]
+    pub include_binary: bool,
+    /// Skip the contents of files larger than this, e.g. `10MB`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<String>,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// An error loading, saving or converting a [`SearchSpec`] or [`SavedSearches`]
+#[derive(Debug)]
+pub enum SpecError {
+    /// The file couldn't be read or written
+    Io(io::Error),
+    /// The TOML couldn't be parsed or written
+    Toml(String),
+    /// The JSON couldn't be parsed or written
+    Json(serde_json::Error),
+    /// A field holds a value that can't be used
+    Invalid {
+        /// The name of the field
+        field: &'static str,
+        /// What is wrong with the value
+        message: String,
+    },
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Toml(message) => write!(f, "invalid TOML: {message}"),
+            Self::Json(e) => write!(f, "invalid JSON: {e}"),
+            Self::Invalid { field, message } => write!(f, "invalid `{field}`: {message}"),
+        }
+    }
+}
+
+impl Error for SpecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Json(e) => Some(e),
+            Self::Toml(_) | Self::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for SpecError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SpecError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// The formats a spec file can be written in, told apart by the file extension.
+#[derive(Clone, Copy)]
+enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    fn of(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+
+    fn parse<T: for<'de> Deserialize<'de>>(self, text: &str) -> Result<T, SpecError> {
+        match self {
+            Self::Toml => toml::from_str(text).map_err(|e| SpecError::Toml(e.to_string())),
+            Self::Json => Ok(serde_json::from_str(text)?),
+        }
+    }
+
+    fn write<T: Serialize>(self, value: &T) -> Result<String, SpecError> {
+        match self {
+            Self::Toml => toml::to_string_pretty(value).map_err(|e| SpecError::Toml(e.to_string())),
+            Self::Json => Ok(serde_json::to_string_pretty(value)? + "\n"),
+        }
+    }
+}
+
+impl SearchSpec {
+    /// Parse a spec written in TOML
+    /// ### Errors
+    /// Returns [`SpecError::Toml`] for invalid TOML or unknown fields.
+    pub fn from_toml(text: &str) -> Result<Self, SpecError> {
+        Format::Toml.parse(text)
+    }
+
+    /// Parse a spec written in JSON
+    /// ### Errors
+    /// Returns [`SpecError::Json`] for invalid JSON or unknown fields.
+    pub fn from_json(text: &str) -> Result<Self, SpecError> {
+        Format::Json.parse(text)
+    }
+
+    /// Write the spec as TOML
+    /// ### Errors
+    /// Returns [`SpecError::Toml`] if a location is not valid UTF-8.
+    pub fn to_toml(&self) -> Result<String, SpecError> {
+        Format::Toml.write(self)
+    }
+
+    /// Write the spec as JSON
+    /// ### Errors
+    /// Returns [`SpecError::Json`] if a location is not valid UTF-8.
+    pub fn to_json(&self) -> Result<String, SpecError> {
+        Format::Json.write(self)
+    }
+
+    /// Load a spec from a `.json` file, or a TOML file for any other extension
+    /// ### Errors
+    /// Returns an error if the file can't be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SpecError> {
+        let path = path.as_ref();
+        Format::of(path).parse(&fs::read_to_string(path)?)
+    }
+
+    /// Save the spec to a `.json` file, or a TOML file for any other extension
+    /// ### Errors
+    /// Returns an error if the spec can't be written or the file can't be saved.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SpecError> {
+        let path = path.as_ref();
+        fs::write(path, Format::of(path).write(self)?)?;
+        Ok(())
+    }
+
+    /// Turn the spec into a [`SearchBuilder`]
+    /// ### Errors
+    /// Returns [`SpecError::Invalid`] for filters, queries, patterns or sizes that can't be
+    /// parsed.
+    pub fn into_builder(self) -> Result<SearchBuilder, SpecError> {
+        self.apply(SearchBuilder::default())
+    }
+
+    /// Apply the spec to an existing [`SearchBuilder`], replacing the location if the spec
+    /// has one and adding its filters to those of the builder
+    /// ### Errors
+    /// Returns [`SpecError::Invalid`] for filters, queries, patterns or sizes that can't be
+    /// parsed.
+    pub fn apply(self, mut builder: SearchBuilder) -> Result<SearchBuilder, SpecError> {
+        let mut locations = self.locations.into_iter();
+        if let Some(location) = locations.next() {
+            builder = builder.location(location);
+        }
+        let more_locations: Vec<PathBuf> = locations.collect();
+        if !more_locations.is_empty() {
+            builder = builder.more_locations(more_locations);
+        }
+        if let Some(input) = self.input {
+            builder = builder.search_input(input);
+        }
+        if let Some(depth) = self.depth {
+            builder = builder.depth(depth);
+        }
+        if let Some(limit) = self.limit {
+            builder = builder.limit(limit);
+        }
+        if self.strict {
+            builder = builder.strict();
+        }
+        if self.ignore_case {
+            builder = builder.ignore_case();
+        }
+        if self.hidden {
+            builder = builder.hidden();
+        }
+
+        // Filters share their syntax with the query language, but each value is parsed
+        // on its own rather than as query text.
+        if !self.extensions.is_empty() {
+            builder = builder.match_expr(Expr::extensions(&self.extensions));
+        }
+        let filters = [
+            ("size", self.size, Expr::size as fn(&str) -> _),
+            ("modified", self.modified, Expr::modified),
+            ("created", self.created, Expr::created),
+        ];
+        for (field, conditions, term) in filters {
+            for condition in conditions {
+                let expr = term(condition.trim()).map_err(|e| SpecError::Invalid {
+                    field,
+                    message: format!("`{condition}`: {}", e.message()),
+                })?;
+                builder = builder.match_expr(expr);
+            }
+        }
+        if let Some(query) = self.query {
+            let query = Query::parse(&query).map_err(|e| SpecError::Invalid {
+                field: "query",
+                message: e.to_string(),
+            })?;
+            builder = query.apply(builder);
+        }
+
+        if let Some(text) = self.contains {
+            builder = builder.contains(text);
+        }
+        if let Some(pattern) = self.contains_regex {
+            builder = builder
+                .contains_regex(&pattern)
+                .map_err(|e| SpecError::Invalid {
+                    field: "contains_regex",
+                    message: e.to_string(),
+                })?;
+        }
+        if self.include_binary {
+            builder = builder.include_binary();
+        }
+        if let Some(size) = self.max_file_size {
+            let size = parse_size(&size, 0).map_err(|e| SpecError::Invalid {
+                field: "max_file_size",
+                message: e.message().to_owned(),
+            })?;
+            builder = builder.max_file_size(FileSize::Byte(size));
+        }
+        Ok(builder)
+    }
+}
+
+impl TryFrom<SearchSpec> for SearchBuilder {
+    type Error = SpecError;
+
+    fn try_from(spec: SearchSpec) -> Result<Self, Self::Error> {
+        spec.into_builder()
+    }
+}
+
+/// Named [`SearchSpec`]s kept together in a config file
+///
+/// In TOML every search is a table under `searches`:
+/// ```toml
+/// [searches.old-logs]
+/// locations = ["/var/log"]
+/// extensions = ["log", "gz"]
+/// modified = [">90d"]
+/// ```
+/// ### Examples
+/// ```rust
+/// use rust_search::{SavedSearches, SearchSpec};
+///
+/// let mut saved = SavedSearches::default();
+/// saved.insert("sources", SearchSpec {
+///     locations: vec!["src".into()],
+///     extensions: vec!["rs".into()],
+///     ..SearchSpec::default()
+/// });
+///
+/// let toml = saved.to_toml().unwrap();
+/// let saved = SavedSearches::from_toml(&toml).unwrap();
+/// let builder = saved.builder("sources").unwrap().unwrap();
+/// let search: Vec<String> = builder.build().collect();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SavedSearches {
+    searches: BTreeMap<String, SearchSpec>,
+}
+
+impl SavedSearches {
+    /// The default config file, `rust_search/searches.toml` in the config directory of
+    /// the user, e.g. `~/.config/rust_search/searches.toml` on Linux
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rust_search").join("searches.toml"))
+    }
+
+    /// Parse saved searches written in TOML
+    /// ### Errors
+    /// Returns [`SpecError::Toml`] for invalid TOML or unknown fields.
+    pub fn from_toml(text: &str) -> Result<Self, SpecError> {
+        Format::Toml.parse(text)
+    }
+
+    /// Parse saved searches written in JSON
+    /// ### Errors
+    /// Returns [`SpecError::Json`] for invalid JSON or unknown fields.
+    pub fn from_json(text: &str) -> Result<Self, SpecError> {
+        Format::Json.parse(text)
+    }
+
+    /// Write the saved searches as TOML
+    /// ### Errors
+    /// Returns [`SpecError::Toml`] if a location is not valid UTF-8.
+    pub fn to_toml(&self) -> Result<String, SpecError> {
+        Format::Toml.write(self)
+    }
+
+    /// Write the saved searches as JSON
+    /// ### Errors
+    /// Returns [`SpecError::Json`] if a location is not valid UTF-8.
+    pub fn to_json(&self) -> Result<String, SpecError> {
+        Format::Json.write(self)
+    }
+
+    /// Load saved searches from a `.json` file, or a TOML file for any other extension.
+    /// A file that doesn't exist yet holds no searches.
+    /// ### Errors
+    /// Returns an error if the file exists but can't be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SpecError> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(text) => Format::of(path).parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Save the searches to a `.json` file, or a TOML file for any other extension,
+    /// creating its directory if needed
+    /// ### Errors
+    /// Returns an error if the searches can't be written or the file can't be saved.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SpecError> {
+        let path = path.as_ref();
+        let text = Format::of(path).write(self)?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// The search saved under `name`
+    pub fn get(&self, name: &str) -> Option<&SearchSpec> {
+        self.searches.get(name)
+    }
+
+    /// A [`SearchBuilder`] for the search saved under `name`, `None` if there is none
+    /// ### Errors
+    /// Returns [`SpecError::Invalid`] if the saved search can't be converted.
+    pub fn builder(&self, name: &str) -> Option<Result<SearchBuilder, SpecError>> {
+        self.get(name).cloned().map(SearchSpec::into_builder)
+    }
+
+    /// Save `spec` under `name`, returning the search it replaced
+    pub fn insert(&mut self, name: impl Into<String>, spec: SearchSpec) -> Option<SearchSpec> {
+        self.searches.insert(name.into(), spec)
+    }
+
+    /// Remove the search saved under `name`
+    pub fn remove(&mut self, name: &str) -> Option<SearchSpec> {
+        self.searches.remove(name)
+    }
+
+    /// The names of the saved searches, sorted
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.searches.keys().map(String::as_str)
+    }
+}
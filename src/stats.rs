@@ -0,0 +1,323 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use ignore::DirEntry;
+
+use crate::format::human_size;
+
+/// What a search has done so far, or in total once it is finished
+///
+/// Read it while the search runs with [`Search::stats`](crate::Search::stats) or a
+/// [`progress`](crate::SearchBuilder::progress) callback. The bytes matched and the
+/// entries ignored, an estimate on the disk, are only counted with
+/// [`detailed_stats`](crate::SearchBuilder::detailed_stats).
+/// ### Examples
+/// ```rust
+/// use rust_search::SearchBuilder;
+///
+/// let mut search = SearchBuilder::default()
+///     .location("src")
+///     .ext("rs")
+///     .detailed_stats()
+///     .build();
+/// let found = search.by_ref().count();
+/// let stats = search.stats();
+/// assert!(stats.is_finished());
+/// assert_eq!(stats.matches(), found);
+/// eprintln!("{stats}");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchStats {
+    dirs: usize,
+    entries: usize,
+    matches: usize,
+    filtered: usize,
+    ignored: usize,
+    errors: usize,
+    bytes_matched: u64,
+    elapsed: Duration,
+    finished: bool,
+}
+
+impl SearchStats {
+    /// The directories whose entries were read
+    pub const fn dirs(&self) -> usize {
+        self.dirs
+    }
+
+    /// The files, directories and other entries checked against the search, including
+    /// the locations themselves
+    pub const fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// The results found
+    pub const fn matches(&self) -> usize {
+        self.matches
+    }
+
+    /// The entries left out by the size, time and custom filters. A directory left out is
+    /// counted once, the entries below it are neither walked nor counted.
+    pub const fn filtered(&self) -> usize {
+        self.filtered
+    }
+
+    /// The entries left out by ignore rules, only counted with
+    /// [`detailed_stats`](crate::SearchBuilder::detailed_stats)
+    ///
+    /// On the disk this is an estimate, known once the search is finished: the entries
+    /// listed in the directories walked that the walk didn't reach. It includes the
+    /// entries left out by `.git/info/exclude` and the global gitignore, and is off by
+    /// the entries created or removed while searching.
+    pub const fn ignored(&self) -> usize {
+        self.ignored
+    }
+
+    /// The entries and directories that couldn't be read
+    pub const fn errors(&self) -> usize {
+        self.errors
+    }
+
+    /// The total size of the files found, only counted with
+    /// [`detailed_stats`](crate::SearchBuilder::detailed_stats)
+    pub const fn bytes_matched(&self) -> u64 {
+        self.bytes_matched
+    }
+
+    /// How long the search has been running, or took once it is finished
+    pub const fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Whether the walk is done and the counts are final
+    pub const fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl fmt::Display for SearchStats {
+    /// A one-line report, e.g. `1204 entries in 87 dirs, 12 matches (1.5M), 3 filtered
+    /// out, 40 ignored, 0 errors in 0.21s`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} entries in {} dirs, {} matches ({}), {} filtered out, {} ignored, {} errors in {:.2}s",
+            self.entries,
+            self.dirs,
+            self.matches,
+            human_size(self.bytes_matched),
+            self.filtered,
+            self.ignored,
+            self.errors,
+            self.elapsed.as_secs_f64(),
+        )
+    }
+}
+
+/// The live counts of a search, shared by the threads walking it.
+#[derive(Debug)]
+pub struct Counters {
+    dirs: AtomicUsize,
+    entries: AtomicUsize,
+    matches: AtomicUsize,
+    filtered: AtomicUsize,
+    ignored: AtomicUsize,
+    errors: AtomicUsize,
+    bytes_matched: AtomicU64,
+    /// The entries the walk of the disk is expected to reach below the directories read,
+    /// the entries it did reach and the entries the filters pruned, to tell how many the
+    /// ignore files left out once it is done.
+    listed: AtomicUsize,
+    reached: AtomicUsize,
+    pruned: AtomicUsize,
+    start: Instant,
+    elapsed: OnceLock<Duration>,
+}
+
+impl Counters {
+    pub fn new() -> Self {
+        Self {
+            dirs: AtomicUsize::new(0),
+            entries: AtomicUsize::new(0),
+            matches: AtomicUsize::new(0),
+            filtered: AtomicUsize::new(0),
+            ignored: AtomicUsize::new(0),
+            errors: AtomicUsize::new(0),
+            bytes_matched: AtomicU64::new(0),
+            listed: AtomicUsize::new(0),
+            reached: AtomicUsize::new(0),
+            pruned: AtomicUsize::new(0),
+            start: Instant::now(),
+            elapsed: OnceLock::new(),
+        }
+    }
+
+    /// The counts of a search that found `matches` results without walking.
+    pub fn done(matches: usize) -> Self {
+        let counters = Self::new();
+        counters.matches.store(matches, Ordering::Relaxed);
+        counters.finish();
+        counters
+    }
+
+    /// Count an entry of the disk, returning whether its entries will be read.
+    pub fn walked(&self, entry: &DirEntry, max_depth: Option<usize>) -> bool {
+        let entered = entry.file_type().is_some_and(|ft| ft.is_dir())
+            && max_depth.is_none_or(|max| entry.depth() < max);
+        self.entry(entered);
+        if entry.depth() > 0 {
+            self.reached.fetch_add(1, Ordering::Relaxed);
+        }
+        entered
+    }
+
+    /// Count an entry, and a directory if its entries will be read.
+    pub fn entry(&self, entered: bool) {
+        self.entries.fetch_add(1, Ordering::Relaxed);
+        if entered {
+            self.dirs.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn filtered(&self) {
+        self.filtered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count an entry the filters left out while walking the disk.
+    pub fn pruned(&self) {
+        self.filtered();
+        self.pruned.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn ignored(&self) {
+        self.ignored.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count the entries of a directory of the disk the walk should reach.
+    pub fn listed(&self, entries: usize) {
+        self.listed.fetch_add(entries, Ordering::Relaxed);
+    }
+
+    /// Count a match unless `limit` matches were already counted, returning whether it was.
+    pub fn try_match(&self, limit: Option<usize>) -> bool {
+        self.matches
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                limit.is_none_or(|l| n < l).then_some(n + 1)
+            })
+            .is_ok()
+    }
+
+    pub fn bytes_matched(&self, bytes: u64) {
+        self.bytes_matched.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Stop the clock and estimate the entries of the disk the ignore rules left out.
+    pub fn finish(&self) {
+        let expected = self.listed.load(Ordering::Relaxed);
+        let reached = self.reached.load(Ordering::Relaxed) + self.pruned.load(Ordering::Relaxed);
+        self.ignored
+            .fetch_add(expected.saturating_sub(reached), Ordering::Relaxed);
+        let _ = self.elapsed.set(self.start.elapsed());
+    }
+
+    pub fn snapshot(&self) -> SearchStats {
+        let elapsed = self.elapsed.get().copied();
+        SearchStats {
+            dirs: self.dirs.load(Ordering::Relaxed),
+            entries: self.entries.load(Ordering::Relaxed),
+            matches: self.matches.load(Ordering::Relaxed),
+            filtered: self.filtered.load(Ordering::Relaxed),
+            ignored: self.ignored.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            bytes_matched: self.bytes_matched.load(Ordering::Relaxed),
+            elapsed: elapsed.unwrap_or_else(|| self.start.elapsed()),
+            finished: elapsed.is_some(),
+        }
+    }
+}
+
+/// A callback given the [`SearchStats`] of a search at regular intervals.
+pub type ProgressFn = dyn Fn(&SearchStats) + Send + Sync;
+
+/// The progress callback of a search and how often it is called.
+#[derive(Clone)]
+pub struct Progress {
+    pub interval: Duration,
+    pub callback: Arc<ProgressFn>,
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Progress {
+    /// Report `counters` every interval on a new thread until the reporter is finished.
+    pub fn spawn(&self, counters: Arc<Counters>) -> Reporter {
+        let (done, stop) = crossbeam_channel::bounded::<()>(0);
+        let progress = self.clone();
+        let thread = std::thread::spawn(move || loop {
+            match stop.recv_timeout(progress.interval) {
+                Err(RecvTimeoutError::Timeout) => (progress.callback)(&counters.snapshot()),
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => {
+                    (progress.callback)(&counters.snapshot());
+                    break;
+                }
+            }
+        });
+        Reporter { done, thread }
+    }
+}
+
+/// The thread calling a progress callback.
+pub struct Reporter {
+    done: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Reporter {
+    /// Give the final report, once the counters are finished, and wait for it.
+    pub fn finish(self) {
+        drop(self.done);
+        let _ = self.thread.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_stop_at_the_limit_and_ignored_is_worked_out_at_the_end() {
+        let counters = Counters::new();
+        assert!(counters.try_match(Some(2)));
+        assert!(counters.try_match(Some(2)));
+        assert!(!counters.try_match(Some(2)));
+        counters.listed(10);
+        counters.reached.store(6, Ordering::Relaxed);
+        counters.pruned();
+        assert!(!counters.snapshot().is_finished());
+
+        counters.finish();
+        let stats = counters.snapshot();
+        assert!(stats.is_finished());
+        assert_eq!(stats.matches(), 2);
+        assert_eq!(stats.filtered(), 1);
+        assert_eq!(stats.ignored(), 3);
+    }
+}
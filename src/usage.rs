@@ -0,0 +1,186 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{format::owner, FileKind, PathStyle, SearchBuilder, SearchResult};
+
+/// The size and number of a set of files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    size: u64,
+    files: u64,
+}
+
+impl Usage {
+    /// The total size of the files in bytes
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The number of files
+    pub const fn files(&self) -> u64 {
+        self.files
+    }
+
+    const fn add(&mut self, size: u64) {
+        self.size += size;
+        self.files += 1;
+    }
+}
+
+/// The files in a directory and all of its subdirectories, see [`DiskUsage`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirUsage {
+    path: PathBuf,
+    depth: usize,
+    usage: Usage,
+    largest: Option<(PathBuf, u64)>,
+}
+
+impl DirUsage {
+    fn new(path: PathBuf, depth: usize) -> Self {
+        Self {
+            path,
+            depth,
+            usage: Usage::default(),
+            largest: None,
+        }
+    }
+
+    /// The directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// How many directories below its location the directory is, 0 for the location itself
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The total size of the files in bytes
+    pub const fn size(&self) -> u64 {
+        self.usage.size
+    }
+
+    /// The number of files
+    pub const fn files(&self) -> u64 {
+        self.usage.files
+    }
+
+    /// The largest file and its size, `None` if there are no files
+    pub fn largest(&self) -> Option<(&Path, u64)> {
+        self.largest
+            .as_ref()
+            .map(|(path, size)| (path.as_path(), *size))
+    }
+
+    fn add(&mut self, path: &Path, size: u64) {
+        self.usage.add(size);
+        if self
+            .largest
+            .as_ref()
+            .is_none_or(|(_, largest)| size > *largest)
+        {
+            self.largest = Some((path.to_path_buf(), size));
+        }
+    }
+}
+
+/// How much space the files matching a search take up, like `du`
+///
+/// Made by [`SearchBuilder::disk_usage`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    total: Usage,
+    dirs: Vec<DirUsage>,
+    by_extension: BTreeMap<String, Usage>,
+    by_owner: BTreeMap<String, Usage>,
+}
+
+impl DiskUsage {
+    /// Add up the files found by a search.
+    pub(crate) fn new(builder: &SearchBuilder, max_depth: usize) -> Self {
+        let mut builder = builder.clone();
+        builder.path_style = PathStyle::AsGiven;
+        let mut usage = Self::default();
+        let mut dirs: BTreeMap<PathBuf, DirUsage> = builder
+            .locations()
+            .map(|location| {
+                (
+                    location.to_path_buf(),
+                    DirUsage::new(location.to_path_buf(), 0),
+                )
+            })
+            .collect();
+        for result in builder.build_results() {
+            if result.kind() == FileKind::File {
+                usage.add(&result, max_depth, &mut dirs);
+            }
+        }
+        usage.dirs = dirs.into_values().collect();
+        usage
+    }
+
+    fn add(
+        &mut self,
+        result: &SearchResult,
+        max_depth: usize,
+        dirs: &mut BTreeMap<PathBuf, DirUsage>,
+    ) {
+        let path = result.path();
+        let size = result.size().unwrap_or(0);
+        self.total.add(size);
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.by_extension.entry(extension).or_default().add(size);
+        if let Some(uid) = result.uid() {
+            self.by_owner
+                .entry(owner::user_name(uid))
+                .or_default()
+                .add(size);
+        }
+
+        let mut dir = result.root().to_path_buf();
+        let below = path.strip_prefix(result.root()).unwrap_or(path);
+        let parents = below
+            .parent()
+            .map_or(0, |parent| parent.components().count());
+        dirs.entry(dir.clone())
+            .or_insert_with(|| DirUsage::new(dir.clone(), 0))
+            .add(path, size);
+        for (depth, component) in below.components().take(parents.min(max_depth)).enumerate() {
+            dir.push(component);
+            dirs.entry(dir.clone())
+                .or_insert_with(|| DirUsage::new(dir.clone(), depth + 1))
+                .add(path, size);
+        }
+    }
+
+    /// All the files together
+    pub const fn total(&self) -> Usage {
+        self.total
+    }
+
+    /// The locations of the search and the directories below them down to the chosen
+    /// depth that hold any of the files, in order
+    ///
+    /// Each directory counts the files in all of its subdirectories too.
+    pub fn dirs(&self) -> &[DirUsage] {
+        &self.dirs
+    }
+
+    /// The files by extension, without the dot, files without one under `""`
+    pub const fn by_extension(&self) -> &BTreeMap<String, Usage> {
+        &self.by_extension
+    }
+
+    /// The files by the name of the user owning them, or the id for unknown users
+    ///
+    /// Always empty on platforms other than Unix.
+    pub const fn by_owner(&self) -> &BTreeMap<String, Usage> {
+        &self.by_owner
+    }
+}
@@ -1,10 +1,22 @@
 use rayon::prelude::*;
 use regex::Regex;
+use sha2::{Digest, Sha256};
 use std::cmp::Ordering;
-use std::path::{Path, PathBuf};
-use strsim::jaro_winkler;
+use std::ffi::OsStr;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, ErrorKind, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::scorer::{prepare_input, JaroWinkler, ScoreTarget, Scorer};
+use crate::SearchResult;
 
 const FUZZY_SEARCH: &str = r".*";
+/// Like [`FUZZY_SEARCH`] but also matches bytes that are not valid UTF-8.
+const FUZZY_SEARCH_BYTES: &str = r"(?-u:.)*";
+/// From how many items on scores are computed in parallel.
+const PARALLEL_SORT_THRESHOLD: usize = 5000;
 
 pub fn build_regex_search_input(
     search_input: Option<&str>,
@@ -12,19 +24,50 @@ pub fn build_regex_search_input(
     strict: bool,
     ignore_case: bool,
 ) -> Regex {
+    let formatted_search_input =
+        format_search_input(search_input, file_ext, strict, ignore_case, FUZZY_SEARCH);
+    Regex::new(&formatted_search_input).unwrap()
+}
+
+/// Same as [`build_regex_search_input`], but matches the raw bytes of a file name
+/// so names that are not valid UTF-8 can be matched too.
+pub fn build_bytes_regex_search_input(
+    search_input: Option<&str>,
+    file_ext: Option<&str>,
+    strict: bool,
+    ignore_case: bool,
+) -> regex::bytes::Regex {
+    let formatted_search_input = format_search_input(
+        search_input,
+        file_ext,
+        strict,
+        ignore_case,
+        FUZZY_SEARCH_BYTES,
+    );
+    regex::bytes::Regex::new(&formatted_search_input).unwrap()
+}
+
+fn format_search_input(
+    search_input: Option<&str>,
+    file_ext: Option<&str>,
+    strict: bool,
+    ignore_case: bool,
+    fuzzy_search: &str,
+) -> String {
     let file_type = file_ext.unwrap_or("*");
     let search_input = search_input.unwrap_or(r"\w+");
 
-    let mut formatted_search_input = if strict {
+    let formatted_search_input = if strict {
         format!(r"{search_input}\.{file_type}$")
     } else {
-        format!(r"{search_input}{FUZZY_SEARCH}\.{file_type}$")
+        format!(r"{search_input}{fuzzy_search}\.{file_type}$")
     };
 
     if ignore_case {
-        formatted_search_input = set_case_insensitive(&formatted_search_input);
+        set_case_insensitive(&formatted_search_input)
+    } else {
+        formatted_search_input
     }
-    Regex::new(&formatted_search_input).unwrap()
 }
 
 fn set_case_insensitive(formatted_search_input: &str) -> String {
@@ -45,15 +88,232 @@ pub fn replace_tilde_with_home_dir(path: impl AsRef<Path>) -> PathBuf {
     path.to_path_buf()
 }
 
-fn file_name_from_path(path: &str) -> &str {
-    Path::new(path)
-        .file_name()
-        .and_then(|f| f.to_str())
-        .unwrap_or(path)
+/// The path leading from the directory `base` to `path`, both absolute.
+/// ### Arguments
+/// * `path` - The path to write relative to `base`
+/// * `base` - The directory the returned path starts from
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+    while let (Some(a), Some(b)) = (path_components.peek(), base_components.peek()) {
+        if a != b {
+            break;
+        }
+        path_components.next();
+        base_components.next();
+    }
+    let relative: PathBuf = base_components
+        .map(|_| Component::ParentDir)
+        .chain(path_components)
+        .collect();
+    if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative
+    }
+}
+
+/// Write the raw bytes of a path on one line, escaping backslashes, tabs and line breaks.
+pub fn escape_path(path: &[u8], out: &mut Vec<u8>) {
+    for &byte in path {
+        match byte {
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            byte => out.push(byte),
+        }
+    }
+}
+
+/// The raw bytes of a path written by [`escape_path`], `None` if an escape is invalid.
+pub fn unescape_path(escaped: &[u8]) -> Option<Vec<u8>> {
+    let mut path = Vec::with_capacity(escaped.len());
+    let mut bytes = escaped.iter();
+    while let Some(&byte) = bytes.next() {
+        path.push(match byte {
+            b'\\' => match bytes.next()? {
+                b'\\' => b'\\',
+                b't' => b'\t',
+                b'n' => b'\n',
+                b'r' => b'\r',
+                _ => return None,
+            },
+            byte => byte,
+        });
+    }
+    Some(path)
+}
+
+/// The SHA-256 hash of the contents of `path` in lowercase hex.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    sha256_reader(File::open(path)?)
+}
+
+/// The SHA-256 hash of everything `reader` yields in lowercase hex.
+pub fn sha256_reader(mut reader: impl Read) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buffer[..n]),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let mut hex = String::with_capacity(64);
+    for byte in hasher.finalize() {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    Ok(hex)
+}
+
+/// The raw bytes of `path`, lossily converted to UTF-8 on platforms other than Unix.
+#[cfg(unix)]
+pub fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+/// The path with the raw bytes written by [`path_to_bytes`].
+#[cfg(unix)]
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+pub fn path_to_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(not(unix))]
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The whole seconds since the epoch, rounded down for times before it, and the
+/// nanoseconds after them.
+pub fn epoch_parts(time: SystemTime) -> (i64, u32) {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => (
+            i64::try_from(since.as_secs()).unwrap_or(i64::MAX),
+            since.subsec_nanos(),
+        ),
+        Err(before) => {
+            let before = before.duration();
+            let secs = -i64::try_from(before.as_secs()).unwrap_or(i64::MAX);
+            match before.subsec_nanos() {
+                0 => (secs, 0),
+                nanos => (secs - 1, 1_000_000_000 - nanos),
+            }
+        }
+    }
+}
+
+/// The time written by [`epoch_parts`], `None` if it can't be represented.
+pub fn from_epoch_parts(secs: i64, nanos: u32) -> Option<SystemTime> {
+    let time = if secs >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(secs.unsigned_abs()))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(secs.unsigned_abs()))
+    };
+    time?.checked_add(Duration::from_nanos(u64::from(nanos)))
+}
+
+/// The `strftime` conversions understood by [`format_time`], each preceded by `%`.
+pub const TIME_CONVERSIONS: &str = "YymdejHMSFTs%";
+
+/// Write `time` in UTC following a `strftime` style `format`
+///
+/// Supports `%Y`, `%y`, `%m`, `%d`, `%e`, `%j`, `%H`, `%M`, `%S`, `%F` (`%Y-%m-%d`),
+/// `%T` (`%H:%M:%S`), `%s` (seconds since the epoch) and `%%`, other conversions are
+/// written as they are.
+pub fn format_time(time: SystemTime, format: &str) -> String {
+    // Times before the epoch are rounded down to the whole second before them.
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => i64::try_from(after.as_secs()).unwrap_or(i64::MAX),
+        Err(before) => {
+            let before = before.duration();
+            let secs = before.as_secs() + u64::from(before.subsec_nanos() > 0);
+            i64::try_from(secs).map_or(i64::MIN, |secs| -secs)
+        }
+    };
+    let days = secs.div_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    let secs_of_day = secs.rem_euclid(86_400);
+    let (hour, minute, second) = (secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60);
+
+    let mut out = String::with_capacity(format.len() + 16);
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let _ = match chars.next() {
+            Some('Y') => write!(out, "{year:04}"),
+            Some('y') => write!(out, "{:02}", year.rem_euclid(100)),
+            Some('m') => write!(out, "{month:02}"),
+            Some('d') => write!(out, "{day:02}"),
+            Some('e') => write!(out, "{day:2}"),
+            Some('j') => write!(out, "{:03}", days - days_from_civil(year, 1, 1) + 1),
+            Some('H') => write!(out, "{hour:02}"),
+            Some('M') => write!(out, "{minute:02}"),
+            Some('S') => write!(out, "{second:02}"),
+            Some('F') => write!(out, "{year:04}-{month:02}-{day:02}"),
+            Some('T') => write!(out, "{hour:02}:{minute:02}:{second:02}"),
+            Some('s') => write!(out, "{secs}"),
+            Some('%') | None => write!(out, "%"),
+            Some(other) => write!(out, "%{other}"),
+        };
+    }
+    out
+}
+
+/// Write `time` as an RFC 3339 timestamp in UTC, e.g. `2024-01-31T09:05:00Z`.
+pub fn format_rfc3339(time: SystemTime) -> String {
+    format_time(time, "%FT%TZ")
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+pub const fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The `(year, month, day)` of the given number of days since 1970-01-01, the inverse of
+/// [`days_from_civil`].
+pub const fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn file_name_from_path(path: &Path) -> &OsStr {
+    path.file_name().unwrap_or(path.as_os_str())
 }
 
 /// This function can be used to sort the given vector on basis of similarity between the input & the vector
 ///
+/// Works with anything that can be viewed as a path, e.g. `String`, `PathBuf` or `&Path`.
 /// ### Arguments
 /// * `&mut vector` - it needs a mutable reference to the vector
 /// ### Examples
@@ -80,43 +340,191 @@ fn file_name_from_path(path: &str) -> &str {
 ///
 /// search **with** similarity sort
 /// `["fly.txt", "flyer.txt", "afly.txt", "bfly.txt",]`
-pub fn similarity_sort(vector: &mut [String], input: &str) {
-    const PARALLEL_SORT_THRESHOLD: usize = 5000;
-    let input = input.to_lowercase();
+pub fn similarity_sort<T: AsRef<Path> + Sync>(vector: &mut [T], input: &str) {
+    similarity_sort_with(vector, input, &JaroWinkler, ScoreTarget::FileName);
+}
+
+/// Sort the given vector on basis of similarity with the input, using the given [`Scorer`]
+///
+/// ### Arguments
+/// * `&mut vector` - it needs a mutable reference to the vector
+/// * `input` - the input to compare against
+/// * `scorer` - the scoring algorithm, either one of the built-in scorers or a closure
+/// * `target` - whether to score the file name or the full path
+/// ### Examples
+/// ```rust
+/// use rust_search::{similarity_sort_with, Acronym, ScoreTarget};
+///
+/// let mut files = vec![
+///     "src/subscribe.rs".to_string(),
+///     "src/SearchBuilder.rs".to_string(),
+/// ];
+///
+/// similarity_sort_with(&mut files, "sb", &Acronym, ScoreTarget::FileName);
+/// assert_eq!(files[0], "src/SearchBuilder.rs");
+/// ```
+pub fn similarity_sort_with<T, S>(vector: &mut [T], input: &str, scorer: &S, target: ScoreTarget)
+where
+    T: AsRef<Path> + Sync,
+    S: Scorer,
+{
+    let input = prepare_input(scorer, input);
+    sort_by_score(vector, |path| {
+        let path = path.as_ref();
+        let candidate = match target {
+            ScoreTarget::FileName => file_name_from_path(path),
+            ScoreTarget::FullPath => path.as_os_str(),
+        };
+        scorer.score(&candidate.to_string_lossy(), &input)
+    });
+}
+
+/// Sort any slice on basis of similarity between the input & the key of each item
+///
+/// ### Arguments
+/// * `items` - the items to sort in place
+/// * `input` - the input to compare against
+/// * `key` - returns the part of the item to compare, usually a file name
+/// ### Examples
+/// ```rust
+/// use rust_search::similarity_sort_by_key;
+/// use std::path::PathBuf;
+///
+/// struct Record {
+///     id: u32,
+///     path: PathBuf,
+/// }
+///
+/// let mut records = vec![
+///     Record { id: 1, path: PathBuf::from("reports/flyer.txt") },
+///     Record { id: 2, path: PathBuf::from("reports/fly.txt") },
+/// ];
+///
+/// similarity_sort_by_key(&mut records, "fly", |r| r.path.file_name().unwrap());
+/// assert_eq!(records[0].id, 2);
+/// ```
+pub fn similarity_sort_by_key<T, F>(items: &mut [T], input: &str, key: F)
+where
+    T: Sync,
+    F: Fn(&T) -> &OsStr + Sync,
+{
+    similarity_sort_by_key_with(items, input, &JaroWinkler, key);
+}
+
+/// Sort any slice on basis of similarity between the input & the key of each item,
+/// using the given [`Scorer`]
+///
+/// ### Arguments
+/// * `items` - the items to sort in place
+/// * `input` - the input to compare against
+/// * `scorer` - the scoring algorithm, either one of the built-in scorers or a closure
+/// * `key` - returns the part of the item to compare, usually a file name
+/// ### Examples
+/// ```rust
+/// use rust_search::{similarity_sort_by_key_with, FzfV2};
+/// use std::path::PathBuf;
+///
+/// let mut paths = vec![PathBuf::from("src/main.rs"), PathBuf::from("src/builder.rs")];
+///
+/// similarity_sort_by_key_with(&mut paths, "build", &FzfV2, |p| p.as_os_str());
+/// assert_eq!(paths[0], PathBuf::from("src/builder.rs"));
+/// ```
+pub fn similarity_sort_by_key_with<T, S, F>(items: &mut [T], input: &str, scorer: &S, key: F)
+where
+    T: Sync,
+    S: Scorer,
+    F: Fn(&T) -> &OsStr + Sync,
+{
+    let input = prepare_input(scorer, input);
+    sort_by_score(items, |item| {
+        scorer.score(&key(item).to_string_lossy(), &input)
+    });
+}
+
+/// Sort search results on basis of similarity with the input, using the given [`Scorer`],
+/// and record the score of each result
+///
+/// ### Arguments
+/// * `results` - the results to sort in place
+/// * `input` - the input to compare against
+/// * `scorer` - the scoring algorithm, either one of the built-in scorers or a closure
+/// * `target` - whether to score the file name or the full path
+/// ### Examples
+/// ```rust
+/// use rust_search::{similarity_sort_results, JaroWinkler, ScoreTarget, SearchBuilder, SearchResult};
+///
+/// let mut results: Vec<SearchResult> = SearchBuilder::default()
+///     .location("src")
+///     .ext("rs")
+///     .build_results()
+///     .collect();
+///
+/// similarity_sort_results(&mut results, "lib", &JaroWinkler, ScoreTarget::FileName);
+/// assert!(results[0].score().is_some());
+/// ```
+pub fn similarity_sort_results<S: Scorer>(
+    results: &mut [SearchResult],
+    input: &str,
+    scorer: &S,
+    target: ScoreTarget,
+) {
+    let input = prepare_input(scorer, input);
+    let score_of = |result: &mut SearchResult| {
+        let candidate = match target {
+            ScoreTarget::FileName => file_name_from_path(result.path()),
+            ScoreTarget::FullPath => result.path().as_os_str(),
+        };
+        let score = scorer.score(&candidate.to_string_lossy(), &input);
+        result.set_score(score);
+    };
+    if results.len() >= PARALLEL_SORT_THRESHOLD {
+        results.par_iter_mut().for_each(score_of);
+    } else {
+        results.iter_mut().for_each(score_of);
+    }
+    results.sort_unstable_by(|a, b| b.score().partial_cmp(&a.score()).unwrap_or(Ordering::Equal));
+}
+
+/// Sorts `items` by descending score, computing each score only once.
+fn sort_by_score<T, F>(items: &mut [T], score_of: F)
+where
+    T: Sync,
+    F: Fn(&T) -> f64 + Sync,
+{
     // Schwartzian transform: precompute all scores, then sort by score.
     // Use parallel scoring only for large datasets where rayon overhead is worthwhile.
-    let mut scored: Vec<(usize, f64)> = if vector.len() >= PARALLEL_SORT_THRESHOLD {
-        vector
+    let mut ranked: Vec<(usize, f64)> = if items.len() >= PARALLEL_SORT_THRESHOLD {
+        items
             .par_iter()
             .enumerate()
-            .map(|(i, path)| {
-                let name = file_name_from_path(path).to_lowercase();
-                (i, jaro_winkler(&name, &input))
-            })
+            .map(|(i, item)| (i, score_of(item)))
             .collect()
     } else {
-        vector
+        items
             .iter()
             .enumerate()
-            .map(|(i, path)| {
-                let name = file_name_from_path(path).to_lowercase();
-                (i, jaro_winkler(&name, &input))
-            })
+            .map(|(i, item)| (i, score_of(item)))
             .collect()
     };
-    scored.sort_unstable_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    ranked.sort_unstable_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
 
-    // Reorder vector in-place according to the sorted indices.
-    let order: Vec<usize> = scored.into_iter().map(|(i, _)| i).collect();
-    apply_permutation(vector, order);
+    // Reorder items in-place according to the sorted indices.
+    let order: Vec<usize> = ranked.into_iter().map(|(i, _)| i).collect();
+    apply_permutation(items, order);
 }
 
+/// Moves the element at `order[i]` to position `i`, following each cycle of the permutation.
 fn apply_permutation<T>(v: &mut [T], mut order: Vec<usize>) {
     for i in 0..v.len() {
-        while order[i] != i {
-            let j = order[i];
-            v.swap(i, j);
-            order.swap(i, j);
+        let mut current = i;
+        loop {
+            let next = order[current];
+            order[current] = current;
+            if next == i {
+                break;
+            }
+            v.swap(current, next);
+            current = next;
         }
     }
 }
@@ -125,6 +533,16 @@ fn apply_permutation<T>(v: &mut [T], mut order: Vec<usize>) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn paths_escape_round_trip() {
+        let path = b"a\\b\tc\nd\re".as_slice();
+        let mut escaped = Vec::new();
+        escape_path(path, &mut escaped);
+        assert_eq!(escaped, b"a\\\\b\\tc\\nd\\re");
+        assert_eq!(unescape_path(&escaped).unwrap(), path);
+        assert_eq!(unescape_path(b"bad\\x"), None);
+    }
+
     #[test]
     fn build_regex_fuzzy_no_ext() {
         let re = build_regex_search_input(Some("hello"), None, false, false);
@@ -153,14 +571,66 @@ mod tests {
         assert!(re.is_match("anything.txt"));
     }
 
+    #[test]
+    fn build_bytes_regex_matches_invalid_utf8() {
+        let re = build_bytes_regex_search_input(Some("caf"), Some("txt"), false, false);
+        assert!(re.is_match(b"caf\xe9.txt"));
+        assert!(!re.is_match(b"caf\xe9.rs"));
+    }
+
+    #[test]
+    fn civil_from_days_known_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(19_753), (2024, 1, 31));
+    }
+
+    #[test]
+    fn format_rfc3339_utc() {
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1_706_691_907);
+        assert_eq!(format_rfc3339(time), "2024-01-31T09:05:07Z");
+        assert_eq!(format_rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn format_time_conversions() {
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1_706_691_907);
+        assert_eq!(format_time(time, "%Y-%m-%d"), "2024-01-31");
+        assert_eq!(format_time(time, "%y %e %j %T"), "24 31 031 09:05:07");
+        assert_eq!(format_time(time, "%s 100%% %q"), "1706691907 100% %q");
+    }
+
     #[test]
     fn file_name_from_path_normal() {
-        assert_eq!(file_name_from_path("/some/path/file.txt"), "file.txt");
+        assert_eq!(
+            file_name_from_path(Path::new("/some/path/file.txt")),
+            "file.txt"
+        );
     }
 
     #[test]
     fn file_name_from_path_no_extension() {
-        assert_eq!(file_name_from_path("/some/path/file"), "file");
+        assert_eq!(file_name_from_path(Path::new("/some/path/file")), "file");
+    }
+
+    #[test]
+    fn apply_permutation_gathers_by_index() {
+        let mut v = vec!["a", "b", "c", "d"];
+        apply_permutation(&mut v, vec![2, 0, 3, 1]);
+        assert_eq!(v, vec!["c", "a", "d", "b"]);
+    }
+
+    #[test]
+    fn relative_path_walks_up_and_down() {
+        assert_eq!(
+            relative_path(Path::new("/a/b/c.txt"), Path::new("/a/d/e")),
+            PathBuf::from("../../b/c.txt")
+        );
+        assert_eq!(
+            relative_path(Path::new("/a/b"), Path::new("/a/b")),
+            PathBuf::from(".")
+        );
     }
 
     #[test]
@@ -0,0 +1,491 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{self, File, Metadata},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+
+use crate::{
+    content::ContentMatcher,
+    filter::{FilterType, Stat},
+    query::Expr,
+    search::Matcher,
+    stats::Counters,
+    FileKind, LineMatch, PathStyle, SearchResult,
+};
+
+/// The files a search walks, the disk by default.
+///
+/// Set with [`SearchBuilder::file_system`](crate::SearchBuilder::file_system) to search
+/// something else, e.g. a [`MemoryFs`] in tests or a snapshot image. Symlinks are not
+/// followed, so `metadata` should describe a symlink itself rather than its target.
+/// ### Examples
+/// ```rust
+/// use rust_search::{FileSystem, FsMetadata, SearchBuilder};
+/// use std::{io, path::{Path, PathBuf}};
+///
+/// /// A flat directory of empty files.
+/// struct Names(Vec<&'static str>);
+///
+/// impl FileSystem for Names {
+///     fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+///         Ok(self.0.iter().map(|name| path.join(name)).collect())
+///     }
+///
+///     fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+///         Ok(if path == Path::new("/") { FsMetadata::dir() } else { FsMetadata::file(0) })
+///     }
+///
+///     fn open(&self, _: &Path) -> io::Result<Box<dyn io::Read + '_>> {
+///         Ok(Box::new(io::empty()))
+///     }
+/// }
+///
+/// let found: Vec<String> = SearchBuilder::default()
+///     .location("/")
+///     .ext("rs")
+///     .file_system(Names(vec!["main.rs", "notes.txt"]))
+///     .build()
+///     .collect();
+/// assert_eq!(found, ["/main.rs"]);
+/// ```
+pub trait FileSystem: Send + Sync {
+    /// The paths of the entries of the directory at `path`, in any order.
+    /// ### Errors
+    /// Returns an error if `path` is not a directory or can't be read.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// The kind, size and times of `path`, without following a symlink.
+    /// ### Errors
+    /// Returns an error if `path` doesn't exist.
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+
+    /// Open the file at `path` for reading.
+    /// ### Errors
+    /// Returns an error if `path` is not a file or can't be opened.
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>>;
+}
+
+impl fmt::Debug for dyn FileSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FileSystem")
+    }
+}
+
+/// The kind, size and times of a path in a [`FileSystem`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FsMetadata {
+    kind: FileKind,
+    len: u64,
+    modified: Option<SystemTime>,
+    created: Option<SystemTime>,
+}
+
+impl FsMetadata {
+    /// A file of `len` bytes, without times.
+    pub const fn file(len: u64) -> Self {
+        Self::new(FileKind::File, len)
+    }
+
+    /// A directory, without times.
+    pub const fn dir() -> Self {
+        Self::new(FileKind::Dir, 0)
+    }
+
+    /// An entry of any kind, without times.
+    pub const fn new(kind: FileKind, len: u64) -> Self {
+        Self {
+            kind,
+            len,
+            modified: None,
+            created: None,
+        }
+    }
+
+    /// Set the modification time.
+    pub const fn with_modified(mut self, time: SystemTime) -> Self {
+        self.modified = Some(time);
+        self
+    }
+
+    /// Set the creation time.
+    pub const fn with_created(mut self, time: SystemTime) -> Self {
+        self.created = Some(time);
+        self
+    }
+
+    /// What kind of entry the path is
+    pub const fn kind(&self) -> FileKind {
+        self.kind
+    }
+
+    /// The size in bytes
+    pub const fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the size is 0
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The modification time, if known
+    pub const fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// The creation time, if known
+    pub const fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+}
+
+impl From<&Metadata> for FsMetadata {
+    fn from(metadata: &Metadata) -> Self {
+        Self {
+            kind: FileKind::from_file_type(Some(metadata.file_type())),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            created: metadata.created().ok(),
+        }
+    }
+}
+
+impl Stat for FsMetadata {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+}
+
+/// The real disk as a [`FileSystem`]
+///
+/// Searching it through [`SearchBuilder::file_system`](crate::SearchBuilder::file_system)
+/// walks one directory at a time, the default search walks the disk in parallel.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskFs;
+
+impl FileSystem for DiskFs {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        Ok(FsMetadata::from(&fs::symlink_metadata(path)?))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(File::open(path)?))
+    }
+}
+
+/// A [`FileSystem`] held in memory, to search trees built in tests or read from an image
+///
+/// The parent directories of every path added are created along with it. Paths are
+/// compared by their components, so `a/b/` and `a/./b` are the same path.
+/// ### Examples
+/// ```rust
+/// use rust_search::{MemoryFs, SearchBuilder};
+/// use std::time::{Duration, SystemTime};
+///
+/// let day = Duration::from_secs(86_400);
+/// let fs = MemoryFs::default()
+///     .file("/proj/src/main.rs", "fn main() {}")
+///     .file("/proj/README.md", "# proj")
+///     .modified("/proj/src/main.rs", SystemTime::UNIX_EPOCH + day);
+///
+/// let found: Vec<String> = SearchBuilder::default()
+///     .location("/proj")
+///     .contains("fn main")
+///     .file_system(fs)
+///     .build()
+///     .collect();
+/// assert_eq!(found, ["/proj/src/main.rs"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    entries: BTreeMap<PathBuf, (FsMetadata, Arc<[u8]>)>,
+}
+
+impl MemoryFs {
+    /// Add a file with `contents`, replacing any entry at `path`.
+    pub fn file(mut self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> Self {
+        let path = self.add_parents(path.as_ref());
+        let contents: Arc<[u8]> = contents.into().into();
+        let metadata = FsMetadata::file(contents.len() as u64);
+        self.entries.insert(path, (metadata, contents));
+        self
+    }
+
+    /// Add an empty directory, keeping it if it already exists.
+    pub fn dir(mut self, path: impl AsRef<Path>) -> Self {
+        let path = self.add_parents(path.as_ref());
+        self.entries
+            .entry(path)
+            .or_insert_with(|| (FsMetadata::dir(), Arc::from([])));
+        self
+    }
+
+    /// Set the modification time of an entry that was added.
+    pub fn modified(mut self, path: impl AsRef<Path>, time: SystemTime) -> Self {
+        if let Some((metadata, _)) = self.entries.get_mut(&normalize(path.as_ref())) {
+            metadata.modified = Some(time);
+        }
+        self
+    }
+
+    /// Set the creation time of an entry that was added.
+    pub fn created(mut self, path: impl AsRef<Path>, time: SystemTime) -> Self {
+        if let Some((metadata, _)) = self.entries.get_mut(&normalize(path.as_ref())) {
+            metadata.created = Some(time);
+        }
+        self
+    }
+
+    /// Add the missing directories above `path` and return it normalized.
+    fn add_parents(&mut self, path: &Path) -> PathBuf {
+        let path = normalize(path);
+        for parent in path.ancestors().skip(1) {
+            if parent.as_os_str().is_empty() {
+                break;
+            }
+            self.entries
+                .entry(parent.to_path_buf())
+                .or_insert_with(|| (FsMetadata::dir(), Arc::from([])));
+        }
+        path
+    }
+
+    fn get(&self, path: &Path) -> io::Result<&(FsMetadata, Arc<[u8]>)> {
+        self.entries
+            .get(&normalize(path))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if self.get(path)?.0.kind != FileKind::Dir {
+            return Err(io::Error::other(format!(
+                "not a directory: {}",
+                path.display()
+            )));
+        }
+        let path = normalize(path);
+        Ok(self
+            .entries
+            .range(path.clone()..)
+            .skip(1)
+            .take_while(|(child, _)| child.starts_with(&path))
+            .filter(|(child, _)| child.parent() == Some(&path))
+            .map(|(child, _)| child.clone())
+            .collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        Ok(self.get(path)?.0)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        let (metadata, contents) = self.get(path)?;
+        if metadata.kind != FileKind::File {
+            return Err(io::Error::other(format!("not a file: {}", path.display())));
+        }
+        Ok(Box::new(&contents[..]))
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.components().collect()
+}
+
+/// A search over a [`FileSystem`], applying the same rules as the walk of the disk.
+pub struct Walk {
+    pub fs: Arc<dyn FileSystem>,
+    pub matcher: Matcher,
+    pub query: Option<Expr>,
+    pub content: Option<ContentMatcher>,
+    pub filters: Vec<FilterType>,
+    pub hidden: bool,
+    pub max_depth: Option<usize>,
+    pub path_style: PathStyle,
+    pub with_metadata: bool,
+    pub stats: Arc<Counters>,
+    pub detailed_stats: bool,
+}
+
+impl Walk {
+    /// Send the matches below `root` to `send` while it returns `true`.
+    ///
+    /// Like the walk of the disk, hidden entries, entries ignored by the `.ignore` files
+    /// and, inside git repositories, the `.gitignore` files, and entries that don't pass
+    /// the filters are skipped along with everything below them. Custom filters, which
+    /// need an entry on the disk, are not applied. `send` is also given the bytes the
+    /// result counts for in the stats. Returns `false` if `send` asked to stop.
+    pub fn run(&self, root: &Arc<Path>, send: &mut dyn FnMut(SearchResult, u64) -> bool) -> bool {
+        let Ok(metadata) = self.fs.metadata(root) else {
+            self.stats.error();
+            return true;
+        };
+        let in_repo = root
+            .ancestors()
+            .skip(1)
+            .any(|dir| self.fs.metadata(&dir.join(".git")).is_ok());
+        self.visit(root, metadata, root, 0, in_repo, &mut Vec::new(), send)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn visit(
+        &self,
+        path: &Path,
+        metadata: FsMetadata,
+        root: &Arc<Path>,
+        depth: usize,
+        in_repo: bool,
+        ignores: &mut Vec<Gitignore>,
+        send: &mut dyn FnMut(SearchResult, u64) -> bool,
+    ) -> bool {
+        let entered =
+            metadata.kind == FileKind::Dir && self.max_depth.is_none_or(|max| depth < max);
+        self.stats.entry(entered);
+        if let Some(lines) = self.matches(path, &metadata) {
+            let (shown, found) = self.path_style.apply_keeping(path.to_path_buf(), root);
+            let mut result =
+                SearchResult::new(shown, Arc::clone(root), lines, metadata.kind, depth)
+                    .found_at(found);
+            if self.with_metadata {
+                result = result.with_size_and_modified(metadata.len, metadata.modified);
+            }
+            let count_bytes = self.detailed_stats && metadata.kind == FileKind::File;
+            if !send(result, if count_bytes { metadata.len } else { 0 }) {
+                return false;
+            }
+        }
+        if !entered {
+            return true;
+        }
+        let Ok(mut children) = self.fs.read_dir(path) else {
+            self.stats.error();
+            return true;
+        };
+        children.sort();
+
+        let in_repo = in_repo || self.fs.metadata(&path.join(".git")).is_ok();
+        let ignore = self.ignore_file(path, in_repo);
+        let pushed = ignore.is_some();
+        ignores.extend(ignore);
+        let mut keep_going = true;
+        for child in children {
+            let Ok(metadata) = self.fs.metadata(&child) else {
+                self.stats.error();
+                continue;
+            };
+            if self.skips(&child, &metadata, ignores) {
+                continue;
+            }
+            keep_going = self.visit(&child, metadata, root, depth + 1, in_repo, ignores, send);
+            if !keep_going {
+                break;
+            }
+        }
+        if pushed {
+            ignores.pop();
+        }
+        keep_going
+    }
+
+    /// Whether `path` is left out of the walk along with everything below it.
+    fn skips(&self, path: &Path, metadata: &FsMetadata, ignores: &[Gitignore]) -> bool {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."));
+        if hidden && !self.hidden {
+            return true;
+        }
+        let is_dir = metadata.kind == FileKind::Dir;
+        let ignored_by_file = ignores
+            .iter()
+            .rev()
+            .map(|ignore| ignore.matched(path, is_dir))
+            .find(|m| !m.is_none())
+            .is_some_and(|m| matches!(m, Match::Ignore(_)));
+        if ignored_by_file {
+            if self.detailed_stats {
+                self.stats.ignored();
+            }
+            return true;
+        }
+        let filtered = !self
+            .filters
+            .iter()
+            .all(|filter| filter.apply_stat(metadata).unwrap_or(true));
+        if filtered {
+            self.stats.filtered();
+        }
+        filtered
+    }
+
+    /// The `.gitignore`, when in a git repository, and `.ignore` rules of `dir`, the
+    /// latter taking precedence.
+    fn ignore_file(&self, dir: &Path, in_repo: bool) -> Option<Gitignore> {
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut found = false;
+        let names: &[&str] = if in_repo {
+            &[".gitignore", ".ignore"]
+        } else {
+            &[".ignore"]
+        };
+        for name in names {
+            let Ok(mut file) = self.fs.open(&dir.join(name)) else {
+                continue;
+            };
+            let mut text = String::new();
+            if file.read_to_string(&mut text).is_err() {
+                continue;
+            }
+            for line in text.lines() {
+                found |= builder.add_line(None, line).is_ok();
+            }
+        }
+        found.then(|| builder.build().ok()).flatten()
+    }
+
+    /// Match an entry that was walked like the callback of the walk of the disk.
+    fn matches(&self, path: &Path, metadata: &FsMetadata) -> Option<Vec<LineMatch>> {
+        if !self
+            .matcher
+            .is_match_path(path, metadata.kind == FileKind::Dir)
+            || self
+                .query
+                .as_ref()
+                .is_some_and(|query| !query.matches_with(path, &|| Some(metadata)))
+        {
+            return None;
+        }
+        match &self.content {
+            Some(content) if metadata.kind == FileKind::File => {
+                let mut file = self.fs.open(path).ok()?;
+                content.search_read(path, &mut file, metadata.len)
+            }
+            Some(_) => None,
+            None => Some(Vec::new()),
+        }
+    }
+}
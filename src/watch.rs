@@ -0,0 +1,442 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::{search::build_walker, PathStyle, Search, SearchBuilder};
+
+/// How often the background thread checks for changes and for the watch being dropped.
+const TICK: Duration = Duration::from_millis(100);
+/// How often the locations are searched again when inotify is not available.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A change to the paths matching a [`Watch`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// A path started to match, because it was created, moved in or changed to match
+    Added(PathBuf),
+    /// A path stopped matching, because it was deleted, moved away or changed to no
+    /// longer match
+    Removed(PathBuf),
+    /// The size or modification time of a matching path changed
+    Modified(PathBuf),
+}
+
+impl WatchEvent {
+    /// The path the event is about
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Added(path) | Self::Removed(path) | Self::Modified(path) => path,
+        }
+    }
+}
+
+/// How a [`Watch`] learns about changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchBackend {
+    /// Notifications from the Linux kernel for every watched directory
+    Inotify,
+    /// Searching all locations again at the given interval
+    Polling(Duration),
+}
+
+/// The matches of a search, kept up to date as the disk changes
+///
+/// Created by [`SearchBuilder::watch`]. The matches at the start are available from
+/// [`initial`](Self::initial), iterating over the watch then blocks until the next
+/// change and yields it as a [`WatchEvent`]. Changes are checked against the same name,
+/// extension, hidden, ignore, depth and filter rules as the search, the limit is not
+/// applied. Paths are always written as they were found, see [`PathStyle::AsGiven`].
+///
+/// Watching stops when the watch is dropped.
+pub struct Watch {
+    initial: Vec<PathBuf>,
+    backend: WatchBackend,
+    rx: Receiver<WatchEvent>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Watch {
+    /// Search once, then watch for changes in the background.
+    pub(crate) fn new(options: &SearchBuilder, poll_interval: Option<Duration>) -> Self {
+        let mut options = options.clone();
+        options.limit = None;
+        options.path_style = PathStyle::AsGiven;
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut watcher = Watcher {
+            options,
+            matches: BTreeMap::new(),
+            tx,
+        };
+        // Watch before the first search, so nothing changing in between is missed.
+        let inotify = poll_interval
+            .is_none()
+            .then(|| inotify::Inotify::start(&watcher))
+            .flatten();
+        watcher.rescan_all();
+        let initial = watcher.matches.keys().cloned().collect();
+        // The first search only sent the initial matches.
+        while rx.try_recv().is_ok() {}
+
+        let thread_stop = Arc::clone(&stop);
+        let backend = if let Some(inotify) = inotify {
+            std::thread::spawn(move || inotify.run(watcher, &thread_stop));
+            WatchBackend::Inotify
+        } else {
+            let interval = poll_interval.unwrap_or(DEFAULT_POLL_INTERVAL);
+            std::thread::spawn(move || poll(watcher, interval, &thread_stop));
+            WatchBackend::Polling(interval)
+        };
+
+        Self {
+            initial,
+            backend,
+            rx,
+            stop,
+        }
+    }
+
+    /// The paths that matched when the watch started, sorted
+    pub fn initial(&self) -> &[PathBuf] {
+        &self.initial
+    }
+
+    /// How changes are noticed, inotify if it could watch every directory
+    pub const fn backend(&self) -> WatchBackend {
+        self.backend
+    }
+
+    /// The next change, or `None` if there was none within `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<WatchEvent> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+
+    /// The next change if one is waiting, without blocking
+    pub fn try_recv(&self) -> Option<WatchEvent> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl Iterator for Watch {
+    type Item = WatchEvent;
+
+    /// Block until the next change.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rx.recv().ok()
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The size and modification time of a match, to tell when it was modified.
+type Stamp = (Option<u64>, Option<SystemTime>);
+
+/// Keeps the matches of the search and sends the differences found by rescans.
+struct Watcher {
+    options: SearchBuilder,
+    matches: BTreeMap<PathBuf, Stamp>,
+    tx: Sender<WatchEvent>,
+}
+
+impl Watcher {
+    /// The locations of the search, which are at depth 0.
+    fn locations(&self) -> Vec<PathBuf> {
+        self.options.locations().map(Path::to_path_buf).collect()
+    }
+
+    /// The options for searching `dir` at `depth`, `levels` deep or as deep as allowed.
+    fn options_below(&self, dir: &Path, depth: usize, levels: Option<usize>) -> SearchBuilder {
+        let mut options = self.options.clone();
+        options.search_location = dir.to_path_buf();
+        options.more_locations = None;
+        let remaining = self.options.depth.map(|max| max.saturating_sub(depth));
+        options.depth = match (levels, remaining) {
+            (Some(levels), Some(remaining)) => Some(levels.min(remaining)),
+            (levels, remaining) => levels.or(remaining),
+        };
+        options
+    }
+
+    /// The directories below `dir` at `depth` whose contents can match, `dir` included.
+    fn dirs_below(&self, dir: &Path, depth: usize) -> Vec<(PathBuf, usize)> {
+        if self.options.depth.is_some_and(|max| depth >= max) {
+            return Vec::new();
+        }
+        let mut options = self.options_below(dir, depth, None);
+        options.depth = options.depth.map(|d| d.saturating_sub(1));
+        let (walker, _) = build_walker(&options, None);
+        walker
+            .build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_dir()))
+            .map(|entry| {
+                let depth = depth + entry.depth();
+                (entry.into_path(), depth)
+            })
+            .collect()
+    }
+
+    /// Search `dir` at `depth` again, only its direct contents unless `recursive`, and
+    /// send the differences with the known matches. Returns `false` once the watch is
+    /// dropped.
+    fn rescan(&mut self, dir: &Path, depth: usize, recursive: bool) -> bool {
+        let levels = if recursive { None } else { Some(1) };
+        let options = self.options_below(dir, depth, levels);
+        let with_dir = depth == 0;
+        let found: BTreeMap<PathBuf, Stamp> = Search::new(&options, true, true)
+            .filter(|result| with_dir || result.depth() > 0)
+            .map(|result| {
+                let stamp = (result.size(), result.modified());
+                (result.into_path(), stamp)
+            })
+            .collect();
+
+        let in_scope = |path: &Path| {
+            (with_dir || path != dir) && (recursive || path == dir || path.parent() == Some(dir))
+        };
+        let gone: Vec<PathBuf> = self
+            .matches
+            .range(dir.to_path_buf()..)
+            .take_while(|(path, _)| path.starts_with(dir))
+            .filter(|(path, _)| in_scope(path) && !found.contains_key(*path))
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in gone {
+            self.matches.remove(&path);
+            if self.tx.send(WatchEvent::Removed(path)).is_err() {
+                return false;
+            }
+        }
+        for (path, stamp) in found {
+            let event = match self.matches.insert(path.clone(), stamp) {
+                None => WatchEvent::Added(path),
+                Some(previous) if previous != stamp => WatchEvent::Modified(path),
+                Some(_) => continue,
+            };
+            if self.tx.send(event).is_err() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Search every location again.
+    fn rescan_all(&mut self) -> bool {
+        self.locations()
+            .iter()
+            .all(|location| self.rescan(location, 0, true))
+    }
+
+    /// Forget the matches at and below `path`, which is gone.
+    fn remove(&mut self, path: &Path) -> bool {
+        let gone: Vec<PathBuf> = self
+            .matches
+            .range(path.to_path_buf()..)
+            .take_while(|(p, _)| p.starts_with(path))
+            .map(|(p, _)| p.clone())
+            .collect();
+        for path in gone {
+            self.matches.remove(&path);
+            if self.tx.send(WatchEvent::Removed(path)).is_err() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Sleep for `duration` in ticks, returning `false` as soon as the watch is dropped.
+fn sleep(duration: Duration, stop: &AtomicBool) -> bool {
+    let until = Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) {
+        let now = Instant::now();
+        if now >= until {
+            return true;
+        }
+        std::thread::sleep(TICK.min(until - now));
+    }
+    false
+}
+
+fn poll(mut watcher: Watcher, interval: Duration, stop: &AtomicBool) {
+    while sleep(interval, stop) && watcher.rescan_all() {}
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        ffi::OsStr,
+        path::{Path, PathBuf},
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use inotify::{EventMask, WatchDescriptor, WatchMask};
+
+    use super::{sleep, Watcher, TICK};
+
+    /// The events that can change what matches in a directory.
+    const MASK: WatchMask = WatchMask::CREATE
+        .union(WatchMask::DELETE)
+        .union(WatchMask::MODIFY)
+        .union(WatchMask::ATTRIB)
+        .union(WatchMask::CLOSE_WRITE)
+        .union(WatchMask::MOVED_FROM)
+        .union(WatchMask::MOVED_TO)
+        .union(WatchMask::ONLYDIR);
+
+    /// An event read from inotify: its watched directory and depth, mask and file name.
+    type Pending = (Option<(PathBuf, usize)>, EventMask, Option<PathBuf>);
+
+    /// An inotify instance watching every directory that can hold matches.
+    pub struct Inotify {
+        inotify: inotify::Inotify,
+        dirs: HashMap<WatchDescriptor, (PathBuf, usize)>,
+    }
+
+    impl Inotify {
+        /// Watch the directories of the search, `None` if inotify is not available or
+        /// the limit on watches was reached.
+        pub fn start(watcher: &Watcher) -> Option<Self> {
+            let mut inotify = Self {
+                inotify: inotify::Inotify::init().ok()?,
+                dirs: HashMap::new(),
+            };
+            for location in watcher.locations() {
+                if !inotify.add(watcher, &location, 0) {
+                    return None;
+                }
+            }
+            Some(inotify)
+        }
+
+        /// Watch `dir` at `depth` and the directories below it.
+        fn add(&mut self, watcher: &Watcher, dir: &Path, depth: usize) -> bool {
+            for (dir, depth) in watcher.dirs_below(dir, depth) {
+                match self.inotify.watches().add(&dir, MASK) {
+                    Ok(wd) => {
+                        self.dirs.insert(wd, (dir, depth));
+                    }
+                    Err(_) if !dir.is_dir() => {}
+                    Err(_) => return false,
+                }
+            }
+            true
+        }
+
+        /// Stop watching `dir` and the directories below it.
+        fn remove(&mut self, dir: &Path) {
+            let below: Vec<WatchDescriptor> = self
+                .dirs
+                .iter()
+                .filter(|(_, (path, _))| path.starts_with(dir))
+                .map(|(wd, _)| wd.clone())
+                .collect();
+            for wd in below {
+                self.dirs.remove(&wd);
+                let _ = self.inotify.watches().remove(wd);
+            }
+        }
+
+        pub fn run(mut self, mut watcher: Watcher, stop: &AtomicBool) {
+            let mut buffer = vec![0; 64 * 1024];
+            while sleep(TICK, stop) {
+                // Directories whose direct contents are searched again, once per tick.
+                let mut dirty: BTreeMap<PathBuf, usize> = BTreeMap::new();
+                let mut overflow = false;
+                loop {
+                    let events: Vec<Pending> = match self.inotify.read_events(&mut buffer) {
+                        Ok(events) => events
+                            .map(|event| {
+                                (
+                                    self.dirs.get(&event.wd).cloned(),
+                                    event.mask,
+                                    event.name.map(|name: &OsStr| PathBuf::from(name)),
+                                )
+                            })
+                            .collect(),
+                        Err(_) => break,
+                    };
+                    if events.is_empty() {
+                        break;
+                    }
+                    for (dir, mask, name) in events {
+                        if mask.contains(EventMask::Q_OVERFLOW) {
+                            overflow = true;
+                            continue;
+                        }
+                        let (Some((dir, depth)), Some(name)) = (dir, name) else {
+                            continue;
+                        };
+                        let path = dir.join(name);
+                        let is_dir = mask.contains(EventMask::ISDIR);
+                        if is_dir && mask.intersects(EventMask::DELETE | EventMask::MOVED_FROM) {
+                            self.remove(&path);
+                            if !watcher.remove(&path) {
+                                return;
+                            }
+                        } else if is_dir && mask.intersects(EventMask::CREATE | EventMask::MOVED_TO)
+                        {
+                            self.add(&watcher, &path, depth + 1);
+                            if !watcher.rescan(&path, depth + 1, true) {
+                                return;
+                            }
+                        }
+                        dirty.insert(dir, depth);
+                    }
+                }
+
+                if overflow {
+                    for location in watcher.locations() {
+                        self.add(&watcher, &location, 0);
+                    }
+                    if !watcher.rescan_all() {
+                        return;
+                    }
+                    continue;
+                }
+                for (dir, depth) in dirty {
+                    if !watcher.rescan(&dir, depth, false) {
+                        return;
+                    }
+                }
+                if stop.load(Ordering::Relaxed) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Without inotify every watch polls.
+#[cfg(not(target_os = "linux"))]
+mod inotify {
+    use std::sync::atomic::AtomicBool;
+
+    use super::Watcher;
+
+    pub enum Inotify {}
+
+    impl Inotify {
+        pub const fn start(_watcher: &Watcher) -> Option<Self> {
+            None
+        }
+
+        pub fn run(self, _watcher: Watcher, _stop: &AtomicBool) {
+            match self {}
+        }
+    }
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "archives")]
+
+mod common;
+
+use common::temp_dir;
+use rust_search::{FileKind, FileSize, FilterExt, SearchBuilder};
+use std::fs::{self, File};
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(86_400);
+        header.set_cksum();
+        builder.append_data(&mut header, name, *contents).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, contents) in files {
+        writer
+            .start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// `hosts`, `backup.tar.gz` holding `etc/hosts` and `inner.zip` holding `conf/hosts`,
+/// and `logs.tar.zst` holding `app.log`.
+fn backups(dir: &Path) {
+    fs::write(dir.join("hosts"), "127.0.0.1 disk").unwrap();
+    let inner = zip(&[("conf/hosts", b"10.0.0.1 inner")]);
+    let outer = tar(&[
+        ("./etc/hosts", b"127.0.0.1 backup"),
+        ("etc/passwd", b"root:x:0:0"),
+        ("inner.zip", &inner),
+    ]);
+    fs::write(dir.join("backup.tar.gz"), gzip(&outer)).unwrap();
+    let logs = tar(&[("app.log", b"started\nfailed: disk full\n")]);
+    let logs =
+        ruzstd::encoding::compress_to_vec(&logs[..], ruzstd::encoding::CompressionLevel::Fastest);
+    File::create(dir.join("logs.tar.zst"))
+        .unwrap()
+        .write_all(&logs)
+        .unwrap();
+}
+
+fn found(builder: &SearchBuilder, dir: &Path) -> Vec<String> {
+    let mut paths: Vec<String> = builder
+        .build_paths()
+        .map(|path| {
+            path.strip_prefix(dir)
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn members_are_found_as_virtual_paths() {
+    let dir = temp_dir("archives_members");
+    backups(&dir);
+    let search = SearchBuilder::default()
+        .location(&dir)
+        .search_input("^hosts$");
+
+    assert_eq!(found(&search.clone(), &dir), ["hosts"]);
+    assert_eq!(
+        found(&search.clone().archives(1), &dir),
+        ["backup.tar.gz!/etc/hosts", "hosts"]
+    );
+    assert_eq!(
+        found(&search.archives(2), &dir),
+        [
+            "backup.tar.gz!/etc/hosts",
+            "backup.tar.gz!/inner.zip!/conf/hosts",
+            "hosts"
+        ]
+    );
+}
+
+#[test]
+fn members_follow_the_extension_size_and_content_rules() {
+    let dir = temp_dir("archives_rules");
+    backups(&dir);
+    let search = SearchBuilder::default().location(&dir).archives(1);
+
+    assert_eq!(
+        found(&search.clone().ext("log"), &dir),
+        ["logs.tar.zst!/app.log"]
+    );
+    assert_eq!(
+        found(
+            &search
+                .clone()
+                .search_input("hosts|passwd")
+                .file_size_smaller(FileSize::Byte(12)),
+            &dir
+        ),
+        ["backup.tar.gz!/etc/passwd"]
+    );
+
+    let results: Vec<_> = search
+        .contains("disk full")
+        .line_matches()
+        .build_results()
+        .collect();
+    assert_eq!(results.len(), 1);
+    let result = &results[0];
+    assert!(result.path().ends_with("logs.tar.zst!/app.log"));
+    assert_eq!(result.kind(), FileKind::File);
+    assert_eq!(result.depth(), 2);
+    assert_eq!(result.lines()[0].line_number(), 2);
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{hash_results, HashAlgorithm, Manifest, PathStyle, SearchBuilder, SearchResult};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn results_get_hashes_attached() {
+    let dir = temp_dir("checksum_results");
+    fs::create_dir(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub/a.txt"), "abc").unwrap();
+
+    let mut results: Vec<SearchResult> = SearchBuilder::default()
+        .location(&dir)
+        .build_results()
+        .collect();
+    hash_results(&mut results, HashAlgorithm::Sha256);
+    for result in &results {
+        if result.path().ends_with("a.txt") {
+            assert_eq!(
+                result.hash(),
+                Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+            );
+        } else {
+            assert_eq!(result.hash(), None);
+        }
+    }
+}
+
+#[test]
+fn relative_results_are_hashed_from_their_location() {
+    let dir = temp_dir("checksum_relative");
+    fs::create_dir(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub/a.txt"), "abc").unwrap();
+
+    let mut results: Vec<SearchResult> = SearchBuilder::default()
+        .location(&dir)
+        .ext("txt")
+        .output_paths(PathStyle::Relative)
+        .build_results()
+        .collect();
+    hash_results(&mut results, HashAlgorithm::Sha256);
+    assert_eq!(results[0].path(), Path::new("sub/a.txt"));
+    assert_eq!(
+        results[0].hash(),
+        Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+    );
+}
+
+#[test]
+fn manifests_report_missing_changed_and_extra_files() {
+    let dir = temp_dir("checksum_manifest");
+    let dist = dir.join("dist");
+    fs::create_dir_all(dist.join("linux")).unwrap();
+    for name in ["app.tar.gz", "linux/app.tar.gz", "old.tar.gz", "notes.txt"] {
+        fs::write(dist.join(name), name).unwrap();
+    }
+    let artifacts = SearchBuilder::default().location(&dist).ext("tar.gz");
+    let sums = dir.join("SHA256SUMS");
+
+    let manifest = Manifest::create(artifacts.build_results(), HashAlgorithm::Sha256);
+    assert_eq!(manifest.len(), 3);
+    manifest.save(&sums).unwrap();
+    let line = fs::read_to_string(&sums).unwrap();
+    let first = line.lines().next().unwrap();
+    assert_eq!(first.len(), 64 + 2 + "app.tar.gz".len());
+    assert!(first.ends_with("  app.tar.gz"));
+
+    let loaded = Manifest::load(&sums, HashAlgorithm::Sha256).unwrap();
+    assert_eq!(loaded, manifest);
+    assert!(loaded.verify(artifacts.build_results()).is_ok());
+
+    fs::write(dist.join("linux/app.tar.gz"), "tampered").unwrap();
+    fs::remove_file(dist.join("old.tar.gz")).unwrap();
+    fs::write(dist.join("new.tar.gz"), "new").unwrap();
+    let verification = loaded.verify(artifacts.build_results());
+    assert!(!verification.is_ok());
+    assert_eq!(verification.ok(), [Path::new("app.tar.gz")]);
+    assert_eq!(verification.changed(), [Path::new("linux/app.tar.gz")]);
+    assert_eq!(verification.missing(), [Path::new("old.tar.gz")]);
+    assert_eq!(verification.extra(), [Path::new("new.tar.gz")]);
+
+    assert!(Manifest::load(&sums, HashAlgorithm::Xxh3).is_err());
+}
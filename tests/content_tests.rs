@@ -0,0 +1,98 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{FileSize, SearchBuilder, SearchResult};
+use std::fs;
+use std::path::PathBuf;
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+#[test]
+fn contains_literal_keeps_matching_files() {
+    let results: Vec<PathBuf> = SearchBuilder::default()
+        .location(fixtures_dir())
+        .contains("fn nested()")
+        .build_paths()
+        .collect();
+    assert_eq!(results, vec![fixtures_dir().join("subdir/nested.rs")]);
+}
+
+#[test]
+fn contains_literal_escapes_regex_characters() {
+    let results: Vec<PathBuf> = SearchBuilder::default()
+        .location(fixtures_dir())
+        .contains("fn .*()")
+        .build_paths()
+        .collect();
+    assert!(results.is_empty(), "{:?}", results);
+}
+
+#[test]
+fn contains_regex_runs_after_extension_matching() {
+    let results: Vec<PathBuf> = SearchBuilder::default()
+        .location(fixtures_dir())
+        .ext("rs")
+        .contains_regex(r"^fn (main|deep)\(")
+        .unwrap()
+        .build_paths()
+        .collect();
+    assert_eq!(results.len(), 2, "{:?}", results);
+    assert!(results.iter().all(|p| p.extension().unwrap() == "rs"));
+}
+
+#[test]
+fn contains_regex_rejects_an_invalid_pattern() {
+    assert!(SearchBuilder::default().contains_regex("fn (").is_err());
+}
+
+#[test]
+fn line_matches_collects_numbers_and_lines() {
+    let dir = temp_dir("line_matches");
+    fs::write(
+        dir.join("notes.txt"),
+        "first\nTODO: one\nmiddle\r\nTODO: two\n",
+    )
+    .unwrap();
+
+    let results: Vec<SearchResult> = SearchBuilder::default()
+        .location(&dir)
+        .contains("TODO")
+        .line_matches()
+        .build_results()
+        .collect();
+
+    assert_eq!(results.len(), 1);
+    let lines: Vec<(u64, &str)> = results[0]
+        .lines()
+        .iter()
+        .map(|l| (l.line_number(), l.line()))
+        .collect();
+    assert_eq!(lines, vec![(2, "TODO: one"), (4, "TODO: two")]);
+}
+
+#[test]
+fn contains_skips_binary_and_large_files() {
+    let dir = temp_dir("binary");
+    fs::write(dir.join("data.bin"), b"needle\0\x01\x02").unwrap();
+    fs::write(dir.join("big.txt"), "needle\n".repeat(1000)).unwrap();
+    fs::write(dir.join("small.txt"), "needle\n").unwrap();
+
+    let search = |builder: SearchBuilder| -> Vec<String> {
+        let mut names: Vec<String> = builder
+            .location(&dir)
+            .contains("needle")
+            .max_file_size(FileSize::Kilobyte(1.0))
+            .build_paths()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+    let text_only = search(SearchBuilder::default());
+    let with_binary = search(SearchBuilder::default().include_binary());
+
+    assert_eq!(text_only, vec!["small.txt"]);
+    assert_eq!(with_binary, vec!["data.bin", "small.txt"]);
+}
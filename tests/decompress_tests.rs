@@ -0,0 +1,104 @@
+#![cfg(feature = "compressed")]
+
+mod common;
+
+use common::temp_dir;
+use rust_search::SearchBuilder;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+fn names(builder: &SearchBuilder) -> Vec<String> {
+    let mut names: Vec<String> = builder
+        .build_paths()
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Rotated logs: `app.log`, `app.log.1.gz`, `app.log.2.xz`, `app.log.3.zst`,
+/// `app.log.4.bz2` and `app.log.5`, which is gzip without the extension.
+fn rotated_logs(dir: &Path) {
+    let line = |n: u32| format!("line\nerror: disk full on rotation {n}\n");
+    fs::write(dir.join("app.log"), "all good\n").unwrap();
+
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    gzip.write_all(line(1).as_bytes()).unwrap();
+    fs::write(dir.join("app.log.1.gz"), gzip.finish().unwrap()).unwrap();
+
+    let mut xz = liblzma::write::XzEncoder::new(Vec::new(), 1);
+    xz.write_all(line(2).as_bytes()).unwrap();
+    fs::write(dir.join("app.log.2.xz"), xz.finish().unwrap()).unwrap();
+
+    let zstd = ruzstd::encoding::compress_to_vec(
+        line(3).as_bytes(),
+        ruzstd::encoding::CompressionLevel::Fastest,
+    );
+    fs::write(dir.join("app.log.3.zst"), zstd).unwrap();
+
+    let mut bzip2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::fast());
+    bzip2.write_all(line(4).as_bytes()).unwrap();
+    fs::write(dir.join("app.log.4.bz2"), bzip2.finish().unwrap()).unwrap();
+
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    gzip.write_all(line(5).as_bytes()).unwrap();
+    fs::write(dir.join("app.log.5"), gzip.finish().unwrap()).unwrap();
+}
+
+#[test]
+fn contents_of_compressed_files_are_searched() {
+    let dir = temp_dir("decompress_contents");
+    rotated_logs(&dir);
+    let search = SearchBuilder::default()
+        .location(&dir)
+        .search_input("^app")
+        .contains("disk full");
+
+    assert!(names(&search).is_empty());
+    assert_eq!(
+        names(&search.clone().decompress()),
+        [
+            "app.log.1.gz",
+            "app.log.2.xz",
+            "app.log.3.zst",
+            "app.log.4.bz2",
+            "app.log.5"
+        ]
+    );
+
+    let results: Vec<_> = search
+        .decompress()
+        .line_matches()
+        .search_input("^app.log.2")
+        .build_results()
+        .collect();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].lines()[0].line_number(), 2);
+    assert_eq!(
+        results[0].lines()[0].line(),
+        "error: disk full on rotation 2"
+    );
+}
+
+#[test]
+fn truncated_files_keep_the_lines_found_before_the_error() {
+    let dir = temp_dir("decompress_truncated");
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    gzip.write_all(b"error: disk full\n").unwrap();
+    for n in 0..100_000u32 {
+        writeln!(gzip, "{:x}", n.wrapping_mul(2_654_435_761)).unwrap();
+    }
+    let compressed = gzip.finish().unwrap();
+    fs::write(dir.join("cut.log.gz"), &compressed[..compressed.len() / 2]).unwrap();
+
+    let results: Vec<_> = SearchBuilder::default()
+        .location(&dir)
+        .contains("disk full")
+        .line_matches()
+        .decompress()
+        .build_results()
+        .collect();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].lines()[0].line_number(), 1);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{find_duplicates, PathStyle, SearchBuilder};
+use std::fs;
+use std::path::PathBuf;
+
+// Hard links are only told apart from copies on Unix.
+#[cfg(unix)]
+#[test]
+fn duplicates_are_grouped_by_contents() {
+    let dir = temp_dir("duplicates");
+    fs::create_dir_all(dir.join("backup")).unwrap();
+    // Large enough for the full hash to decide, with the same first and last blocks.
+    let mut big = vec![b'x'; 20_000];
+    fs::write(dir.join("data.bin"), &big).unwrap();
+    fs::write(dir.join("backup/data.bin"), &big).unwrap();
+    fs::write(dir.join("backup/data-2.bin"), &big).unwrap();
+    big[10_000] = b'y';
+    fs::write(dir.join("almost.bin"), &big).unwrap();
+
+    fs::write(dir.join("notes.txt"), "same notes").unwrap();
+    fs::write(dir.join("backup/notes.txt"), "same notes").unwrap();
+    fs::write(dir.join("other.txt"), "different!").unwrap();
+    fs::write(dir.join("empty-a.txt"), "").unwrap();
+    fs::write(dir.join("empty-b.txt"), "").unwrap();
+    fs::hard_link(dir.join("notes.txt"), dir.join("notes-link.txt")).unwrap();
+
+    let duplicates = find_duplicates(SearchBuilder::default().location(&dir).build());
+    let groups: Vec<(u64, &[PathBuf])> = duplicates
+        .groups()
+        .iter()
+        .map(|group| (group.size(), group.files()))
+        .collect();
+    assert_eq!(
+        groups,
+        [
+            (
+                20_000,
+                [
+                    dir.join("backup/data-2.bin"),
+                    dir.join("backup/data.bin"),
+                    dir.join("data.bin")
+                ]
+                .as_slice()
+            ),
+            (
+                10,
+                [dir.join("backup/notes.txt"), dir.join("notes-link.txt")].as_slice()
+            ),
+        ]
+    );
+    assert_eq!(duplicates.groups()[0].reclaimable(), 40_000);
+    assert_eq!(duplicates.reclaimable(), 40_010);
+
+    assert_eq!(duplicates.hard_links().len(), 1);
+    assert_eq!(duplicates.hard_links()[0].size(), 10);
+    assert_eq!(
+        duplicates.hard_links()[0].paths(),
+        [dir.join("notes-link.txt"), dir.join("notes.txt")]
+    );
+}
+
+#[test]
+fn results_are_read_where_they_were_found() {
+    let dir = temp_dir("duplicates_relative");
+    fs::write(dir.join("a.txt"), "same").unwrap();
+    fs::write(dir.join("b.txt"), "same").unwrap();
+
+    let search = SearchBuilder::default()
+        .location(&dir)
+        .output_paths(PathStyle::Relative)
+        .build_results();
+    let duplicates = find_duplicates(search);
+    assert_eq!(duplicates.groups().len(), 1);
+    assert_eq!(
+        duplicates.groups()[0].files(),
+        [dir.join("a.txt"), dir.join("b.txt")]
+    );
+}
@@ -0,0 +1,117 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::temp_dir;
+use rust_search::{Exec, PathStyle, SearchBuilder};
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn exec_runs_a_command_per_path() {
+    let dir = temp_dir("exec_each");
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        fs::write(dir.join(name), name).unwrap();
+    }
+    let report = SearchBuilder::default()
+        .location(&dir)
+        .ext("txt")
+        .build()
+        .exec(&Exec::new("cp {} {.}.bak").unwrap().jobs(2));
+    assert!(report.success());
+    assert_eq!(report.commands().len(), 3);
+    for name in ["a", "b", "c"] {
+        assert_eq!(
+            fs::read_to_string(dir.join(format!("{name}.bak"))).unwrap(),
+            format!("{name}.txt")
+        );
+    }
+}
+
+#[test]
+fn exec_collects_output_in_path_order() {
+    let paths = ["one", "two", "three", "four"];
+    let exec = Exec::new(r#"sh -c 'echo "out $1"; echo "err $1" >&2; test "$1" != three' sh"#)
+        .unwrap()
+        .jobs(3);
+    let report = exec.run(paths);
+    assert_eq!(report.commands().len(), 4);
+    for (command, path) in report.commands().iter().zip(paths) {
+        assert_eq!(command.paths(), [PathBuf::from(path)]);
+        assert_eq!(command.stdout(), format!("out {path}\n").as_bytes());
+        assert_eq!(command.stderr(), format!("err {path}\n").as_bytes());
+        assert_eq!(command.success(), path != "three");
+    }
+    let failed: Vec<_> = report.failed().collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].status().unwrap().code(), Some(1));
+    assert!(!report.stopped());
+
+    let report = Exec::new("no-such-command-rust-search").unwrap().run(["x"]);
+    assert!(report.commands()[0].status().is_err());
+}
+
+#[test]
+fn exec_stops_on_first_failure() {
+    let exec = Exec::new(r#"sh -c 'test "$1" != 2' sh"#)
+        .unwrap()
+        .jobs(1)
+        .stop_on_failure();
+    let report = exec.run(["1", "2", "3", "4"]);
+    assert!(report.stopped());
+    let ran: Vec<&[PathBuf]> = report.commands().iter().map(|c| c.paths()).collect();
+    assert_eq!(ran, [[PathBuf::from("1")], [PathBuf::from("2")]]);
+}
+
+#[test]
+fn exec_batches_paths() {
+    let paths: Vec<String> = (0..20_000).map(|i| format!("path-{i:05}")).collect();
+    let report = Exec::new("echo {}").unwrap().batch().run(&paths);
+    assert!(report.success());
+    // 20000 paths of about 10 bytes plus a pointer each don't fit in one batch.
+    assert!(report.commands().len() > 1);
+    let echoed: Vec<String> = report
+        .commands()
+        .iter()
+        .flat_map(|c| {
+            String::from_utf8(c.stdout().to_vec())
+                .unwrap()
+                .split_whitespace()
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(echoed, paths);
+    let batched: usize = report.commands().iter().map(|c| c.paths().len()).sum();
+    assert_eq!(batched, paths.len());
+}
+
+#[test]
+fn exec_runs_on_results_where_they_were_found() {
+    let dir = temp_dir("exec_relative");
+    fs::write(dir.join("a.txt"), "a").unwrap();
+
+    let search = SearchBuilder::default()
+        .location(&dir)
+        .ext("txt")
+        .output_paths(PathStyle::Relative)
+        .build_results();
+    let report = Exec::new("test -f {}").unwrap().run(search);
+    assert!(report.success());
+    assert_eq!(report.commands()[0].paths(), [dir.join("a.txt")]);
+}
+
+#[test]
+fn search_exec_runs_on_the_disk_path() {
+    let dir = temp_dir("exec_search_styled");
+    fs::write(dir.join("a.txt"), "a").unwrap();
+
+    let report = SearchBuilder::default()
+        .location(&dir)
+        .ext("txt")
+        .output_paths(PathStyle::RelativeTo("/".into()))
+        .build_results()
+        .exec(&Exec::new("test -f {}").unwrap());
+    assert!(report.success());
+    assert_eq!(report.commands().len(), 1);
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{Conflict, FileOpStatus, FileOps, Layout, PathStyle, SearchBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `data/a.log`, `data/x/b.log` and `data/y/b.log` below `dir`, returning `data`.
+fn logs(dir: &Path) -> PathBuf {
+    let data = dir.join("data");
+    fs::create_dir_all(data.join("x")).unwrap();
+    fs::create_dir_all(data.join("y")).unwrap();
+    fs::write(data.join("a.log"), "a").unwrap();
+    fs::write(data.join("x/b.log"), "xb").unwrap();
+    fs::write(data.join("y/b.log"), "yb").unwrap();
+    data
+}
+
+fn targets(report: &rust_search::FileOpsReport) -> Vec<Option<&Path>> {
+    report
+        .entries()
+        .iter()
+        .map(|entry| entry.target())
+        .collect()
+}
+
+#[test]
+fn dry_run_plans_without_touching_anything() {
+    let dir = temp_dir("fileops_dry_run");
+    let data = logs(&dir);
+    let out = dir.join("out");
+    let search = SearchBuilder::default().location(&data).ext("log");
+
+    let plan = FileOps::move_to(&out).dry_run().run(search.build_results());
+    assert_eq!(
+        targets(&plan),
+        [
+            Some(out.join("a.log").as_path()),
+            Some(out.join("x/b.log").as_path()),
+            Some(out.join("y/b.log").as_path())
+        ]
+    );
+    assert_eq!(plan.succeeded().count(), 3);
+    assert!(matches!(plan.entries()[0].status(), FileOpStatus::Planned));
+
+    let plan = FileOps::delete().dry_run().run(search.build_results());
+    assert_eq!(plan.succeeded().count(), 3);
+    assert!(!out.exists());
+    assert!(data.join("x/b.log").exists());
+}
+
+#[test]
+fn copy_keeps_or_flattens_the_structure() {
+    let dir = temp_dir("fileops_copy");
+    let data = logs(&dir);
+    let search = SearchBuilder::default().location(&data).ext("log");
+
+    let kept = dir.join("kept");
+    let report = FileOps::copy_to(&kept).run(search.build_results());
+    assert_eq!(report.succeeded().count(), 3);
+    assert_eq!(fs::read_to_string(kept.join("y/b.log")).unwrap(), "yb");
+    assert!(data.join("y/b.log").exists());
+
+    let flat = dir.join("flat");
+    let report = FileOps::copy_to(&flat)
+        .layout(Layout::Flatten)
+        .conflict(Conflict::Rename)
+        .run(search.build_results());
+    assert_eq!(
+        targets(&report),
+        [
+            Some(flat.join("a.log").as_path()),
+            Some(flat.join("b.log").as_path()),
+            Some(flat.join("b (1).log").as_path())
+        ]
+    );
+    assert_eq!(fs::read_to_string(flat.join("b (1).log")).unwrap(), "yb");
+
+    // Flattened names collide with each other, and now with the copies on disk too.
+    let report = FileOps::copy_to(&flat)
+        .layout(Layout::Flatten)
+        .run(search.build_results());
+    assert_eq!(report.skipped().count(), 3);
+}
+
+#[test]
+fn move_and_trash_handle_conflicts() {
+    let dir = temp_dir("fileops_move");
+    let data = logs(&dir);
+    let out = dir.join("out");
+    fs::create_dir_all(out.join("x")).unwrap();
+    fs::write(out.join("x/b.log"), "old").unwrap();
+    let search = SearchBuilder::default().location(&data).ext("log");
+
+    let report = FileOps::move_to(&out).run(search.build_results());
+    assert_eq!(report.succeeded().count(), 2);
+    let skipped: Vec<&Path> = report.skipped().map(|entry| entry.source()).collect();
+    assert_eq!(skipped, [data.join("x/b.log")]);
+    assert_eq!(fs::read_to_string(out.join("x/b.log")).unwrap(), "old");
+    assert!(!data.join("a.log").exists());
+
+    let report = FileOps::move_to(&out)
+        .conflict(Conflict::Overwrite)
+        .run(search.build_results());
+    assert_eq!(report.succeeded().count(), 1);
+    assert_eq!(fs::read_to_string(out.join("x/b.log")).unwrap(), "xb");
+
+    let trash = dir.join("trash");
+    fs::write(data.join("a.log"), "again").unwrap();
+    fs::create_dir_all(&trash).unwrap();
+    fs::write(trash.join("a.log"), "trashed before").unwrap();
+    let report = FileOps::trash(&trash).run(search.build_results());
+    assert_eq!(targets(&report), [Some(trash.join("a (1).log").as_path())]);
+    assert_eq!(
+        fs::read_to_string(trash.join("a (1).log")).unwrap(),
+        "again"
+    );
+}
+
+#[test]
+fn deleting_a_directory_covers_everything_below() {
+    let dir = temp_dir("fileops_delete");
+    let data = logs(&dir);
+    let report = FileOps::delete().run(
+        SearchBuilder::default()
+            .location(&data)
+            .search_input("^(x|b)")
+            .build_results(),
+    );
+    let deleted: Vec<&Path> = report.succeeded().map(|entry| entry.source()).collect();
+    assert_eq!(deleted, [data.join("x"), data.join("y/b.log")]);
+    assert_eq!(report.failed().count(), 0);
+    assert!(!data.join("x").exists());
+    assert!(data.join("y").exists());
+    assert!(data.join("a.log").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn chmod_sets_permission_bits() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = temp_dir("fileops_chmod");
+    let data = logs(&dir);
+    let report = FileOps::chmod(0o600).run(
+        SearchBuilder::default()
+            .location(&data)
+            .ext("log")
+            .build_results(),
+    );
+    assert_eq!(report.succeeded().count(), 3);
+    let mode = fs::metadata(data.join("x/b.log"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn relative_paths_are_resolved_against_their_location() {
+    let dir = temp_dir("fileops_relative");
+    let data = logs(&dir);
+    let out = dir.join("out");
+    let search = SearchBuilder::default()
+        .location(&data)
+        .ext("log")
+        .output_paths(PathStyle::Relative);
+
+    let report = FileOps::copy_to(&out).run(search.build_results());
+    assert_eq!(report.failed().count(), 0);
+    assert_eq!(fs::read_to_string(out.join("x/b.log")).unwrap(), "xb");
+
+    let search = search.output_paths(PathStyle::RelativeTo(dir.join("out")));
+    let report = FileOps::delete().run(search.build_results());
+    assert_eq!(report.succeeded().count(), 3);
+    assert!(!data.join("a.log").exists());
+    assert!(out.join("a.log").exists());
+}
+
+#[test]
+fn copy_into_the_directory_itself_is_refused() {
+    let dir = temp_dir("fileops_into_itself");
+    let data = logs(&dir);
+    let search = SearchBuilder::default()
+        .location(&dir)
+        .search_input("^data$")
+        .strict();
+
+    let report = FileOps::copy_to(data.join("x/backup")).run(search.build_results());
+    assert_eq!(report.failed().count(), 1);
+    assert!(!data.join("x/backup").exists());
+}
+
+#[test]
+fn moving_or_copying_onto_the_source_is_refused() {
+    let dir = temp_dir("fileops_onto_source");
+    let data = logs(&dir);
+    let search = SearchBuilder::default().location(&data).ext("log");
+
+    for ops in [FileOps::move_to(&data), FileOps::copy_to(&data)] {
+        let report = ops
+            .layout(Layout::Keep)
+            .conflict(Conflict::Overwrite)
+            .run(search.build_results());
+        assert_eq!(report.failed().count(), 3);
+        assert_eq!(fs::read_to_string(data.join("a.log")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(data.join("x/b.log")).unwrap(), "xb");
+    }
+}
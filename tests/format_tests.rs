@@ -0,0 +1,72 @@
+use rust_search::{PathStyle, ResultFormat, SearchBuilder, SearchResult};
+use std::fs;
+
+fn hello() -> SearchResult {
+    SearchBuilder::default()
+        .location("tests/fixtures")
+        .search_input("hello")
+        .ext("rs")
+        .build_results()
+        .next()
+        .unwrap()
+}
+
+#[test]
+fn renders_path_pieces() {
+    let format =
+        ResultFormat::parse("{name}|{stem}|{ext}|{parent}|{relpath}|{root}|{depth}|{kind}")
+            .unwrap();
+    assert_eq!(
+        format.render(&hello()),
+        "hello.rs|hello|rs|tests/fixtures|hello.rs|tests/fixtures|1|file"
+    );
+}
+
+#[test]
+fn renders_size_and_time() {
+    let size = fs::metadata("tests/fixtures/hello.rs").unwrap().len();
+    let format = ResultFormat::parse("{size}\t{size:h}\t{mtime:%Y}").unwrap();
+    let rendered = format.render(&hello());
+    let fields: Vec<&str> = rendered.split('\t').collect();
+
+    assert_eq!(fields[0], size.to_string());
+    assert_eq!(fields[1], size.to_string());
+    assert_eq!(fields[2].len(), 4);
+}
+
+#[test]
+fn relpath_follows_absolute_paths() {
+    let result = SearchBuilder::default()
+        .location("tests/fixtures")
+        .search_input("nested")
+        .output_paths(PathStyle::Absolute)
+        .build_results()
+        .next()
+        .unwrap();
+    let format = ResultFormat::parse("{relpath}").unwrap();
+    assert_eq!(format.render(&result), "subdir/nested.rs");
+}
+
+#[cfg(unix)]
+#[test]
+fn renders_permissions_and_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata("tests/fixtures/hello.rs")
+        .unwrap()
+        .permissions()
+        .mode();
+    let format = ResultFormat::parse("{perms:o} {perms} {owner}").unwrap();
+    let rendered = format.render(&hello());
+    let fields: Vec<&str> = rendered.split(' ').collect();
+
+    assert_eq!(fields[0], format!("{:o}", mode & 0o7777));
+    assert!(fields[1].starts_with("-r"), "{rendered}");
+    assert!(!fields[2].is_empty());
+}
+
+#[test]
+fn unknown_values_render_empty() {
+    let format = ResultFormat::parse("[{score}]").unwrap();
+    assert_eq!(format.render(&hello()), "[]");
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{FileKind, FileSize, FilterExt, Index, Query, SearchBuilder, SearchResult};
+use std::fs;
+use std::path::PathBuf;
+
+fn sorted(mut paths: Vec<String>) -> Vec<String> {
+    paths.sort();
+    paths
+}
+
+#[test]
+fn index_answers_like_the_walk() {
+    let index = Index::build(["tests/fixtures"]).unwrap();
+    let builders = [
+        SearchBuilder::default().location("tests/fixtures"),
+        SearchBuilder::default()
+            .location("tests/fixtures")
+            .ext("rs"),
+        SearchBuilder::default()
+            .location("tests/fixtures")
+            .search_input("HELLO")
+            .ignore_case(),
+        SearchBuilder::default().location("tests/fixtures").depth(1),
+        SearchBuilder::default().location("tests/fixtures").hidden(),
+        SearchBuilder::default().location("tests/fixtures/subdir"),
+        SearchBuilder::default()
+            .location("tests/fixtures")
+            .file_size_greater(FileSize::Byte(0)),
+        SearchBuilder::default()
+            .location("tests/fixtures")
+            .contains("fn"),
+        Query::parse("-name:nested ext:rs,txt")
+            .unwrap()
+            .apply(SearchBuilder::default().location("tests/fixtures")),
+    ];
+
+    for builder in builders {
+        let walked = sorted(builder.build().collect());
+        let indexed = sorted(index.search(&builder).collect());
+        assert!(!walked.is_empty(), "{builder:?}");
+        assert_eq!(indexed, walked, "{builder:?}");
+    }
+}
+
+#[test]
+fn index_results_carry_recorded_metadata() {
+    let index = Index::build(["tests/fixtures"]).unwrap();
+    let results: Vec<SearchResult> = index
+        .search_results(
+            &SearchBuilder::default()
+                .location("tests/fixtures")
+                .search_input("nested")
+                .ext("rs"),
+        )
+        .collect();
+
+    assert_eq!(results.len(), 1);
+    let metadata = fs::metadata("tests/fixtures/subdir/nested.rs").unwrap();
+    assert_eq!(results[0].kind(), FileKind::File);
+    assert_eq!(results[0].depth(), 2);
+    assert_eq!(results[0].size(), Some(metadata.len()));
+    assert_eq!(results[0].modified(), metadata.modified().ok());
+    assert_eq!(results[0].root(), std::path::Path::new("tests/fixtures"));
+}
+
+#[test]
+fn saved_index_is_a_snapshot() {
+    let dir = temp_dir("index_snapshot");
+    let data = dir.join("data");
+    fs::create_dir_all(data.join("docs")).unwrap();
+    fs::write(data.join("docs/report.pdf"), "pdf").unwrap();
+    fs::write(data.join("notes.txt"), "notes").unwrap();
+
+    let index = Index::build([&data]).unwrap();
+    let db = dir.join("cache/index.db");
+    index.save(&db).unwrap();
+    fs::write(data.join("later.pdf"), "pdf").unwrap();
+
+    let loaded = Index::load(&db).unwrap();
+    assert_eq!(loaded, index);
+    assert_eq!(loaded.len(), 4);
+    assert!(loaded.age() < std::time::Duration::from_secs(60));
+    assert!(loaded.covers(data.join("docs")));
+    assert!(!loaded.covers(&dir));
+
+    let found: Vec<PathBuf> = loaded
+        .search_paths(&SearchBuilder::default().location(&data).ext("pdf"))
+        .collect();
+    assert_eq!(found, vec![data.join("docs/report.pdf")]);
+    let outside: Vec<PathBuf> = loaded
+        .search_paths(&SearchBuilder::default().location(&dir))
+        .collect();
+    assert!(outside.is_empty());
+
+    fs::write(&db, b"garbage").unwrap();
+    assert!(Index::load(&db).is_err());
+}
+
+#[test]
+fn refresh_reads_only_changed_directories() {
+    let dir = temp_dir("index_refresh");
+    for sub in ["keep/inner", "edit", "gone/deeper"] {
+        fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    fs::write(dir.join("keep/inner/a.txt"), "a").unwrap();
+    fs::write(dir.join("edit/b.txt"), "b").unwrap();
+    fs::write(dir.join("edit/c.txt"), "c").unwrap();
+    fs::write(dir.join("gone/deeper/d.txt"), "d").unwrap();
+    let mut index = Index::build([&dir]).unwrap();
+
+    fs::write(dir.join("edit/new.txt"), "new").unwrap();
+    fs::remove_file(dir.join("edit/c.txt")).unwrap();
+    fs::write(dir.join("edit/b.txt"), "bigger").unwrap();
+    fs::create_dir_all(dir.join("edit/fresh")).unwrap();
+    fs::write(dir.join("edit/fresh/e.txt"), "e").unwrap();
+    fs::remove_dir_all(dir.join("gone")).unwrap();
+
+    let changes = index.refresh();
+    assert_eq!(
+        changes.added(),
+        [
+            dir.join("edit/fresh"),
+            dir.join("edit/fresh/e.txt"),
+            dir.join("edit/new.txt")
+        ]
+    );
+    assert_eq!(
+        changes.removed(),
+        [
+            dir.join("edit/c.txt"),
+            dir.join("gone"),
+            dir.join("gone/deeper"),
+            dir.join("gone/deeper/d.txt")
+        ]
+    );
+    assert_eq!(changes.changed(), [dir.join("edit/b.txt")]);
+    // The root, edit and the new fresh directory, but not keep or keep/inner.
+    assert_eq!(changes.dirs_read(), 3);
+
+    let everything = SearchBuilder::default().location(&dir).hidden();
+    let rebuilt = Index::build([&dir]).unwrap();
+    assert_eq!(
+        sorted(index.search(&everything).collect()),
+        sorted(rebuilt.search(&everything).collect())
+    );
+
+    let changes = index.refresh();
+    assert!(changes.is_empty());
+    assert_eq!(changes.dirs_read(), 0);
+}
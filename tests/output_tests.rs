@@ -0,0 +1,122 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{
+    similarity_sort_results, write_csv, Column, FileKind, JaroWinkler, ScoreTarget, SearchBuilder,
+    SearchResult,
+};
+use std::fs;
+
+#[test]
+fn results_carry_metadata() {
+    let results: Vec<SearchResult> = SearchBuilder::default()
+        .location("tests/fixtures")
+        .search_input("hello")
+        .ext("rs")
+        .build_results()
+        .collect();
+
+    assert_eq!(results.len(), 1);
+    let result = &results[0];
+    assert_eq!(result.kind(), FileKind::File);
+    assert_eq!(result.depth(), 1);
+    assert_eq!(
+        result.size(),
+        Some(fs::metadata("tests/fixtures/hello.rs").unwrap().len())
+    );
+    assert!(result.modified().is_some());
+    assert_eq!(result.score(), None);
+}
+
+#[test]
+fn sort_results_records_scores() {
+    let mut results: Vec<SearchResult> = SearchBuilder::default()
+        .location("tests/fixtures")
+        .ext("rs")
+        .build_results()
+        .collect();
+
+    similarity_sort_results(&mut results, "hello", &JaroWinkler, ScoreTarget::FileName);
+
+    assert!(results[0].path().ends_with("hello.rs"));
+    assert!(results.windows(2).all(|w| w[0].score() >= w[1].score()));
+}
+
+#[test]
+fn csv_escapes_commas_and_quotes() {
+    let dir = temp_dir("output_csv");
+    fs::write(dir.join("a,b \"c\".txt"), "12345").unwrap();
+
+    let results: Vec<SearchResult> = SearchBuilder::default()
+        .location(&dir)
+        .ext("txt")
+        .build_results()
+        .collect();
+    let mut csv = Vec::new();
+    write_csv(
+        &mut csv,
+        &results,
+        &[Column::Path, Column::Size, Column::Kind],
+    )
+    .unwrap();
+
+    let csv = String::from_utf8(csv).unwrap();
+    let path = dir.join("a,b \"c\".txt").display().to_string();
+    assert_eq!(
+        csv,
+        format!("path,size,kind\n\"{}\",5,file\n", path.replace('"', "\"\""))
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn ndjson_writes_one_object_per_line() {
+    use rust_search::write_ndjson;
+
+    let results = SearchBuilder::default()
+        .location("tests/fixtures")
+        .ext("rs")
+        .build_results();
+    let mut ndjson = Vec::new();
+    write_ndjson(&mut ndjson, results).unwrap();
+
+    let ndjson = String::from_utf8(ndjson).unwrap();
+    assert_eq!(ndjson.lines().count(), 3);
+    for line in ndjson.lines() {
+        let value: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(value["kind"], "file");
+        assert!(value["size"].is_u64());
+        assert!(value["mtime"].as_str().unwrap().ends_with('Z'));
+        assert!(value.get("lines").is_none());
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_writes_an_array() {
+    use rust_search::write_json;
+
+    let results: Vec<SearchResult> = SearchBuilder::default()
+        .location("tests/fixtures")
+        .search_input("hello")
+        .ext("rs")
+        .contains("main")
+        .line_matches()
+        .build_results()
+        .collect();
+    let mut json = Vec::new();
+    write_json(&mut json, &results).unwrap();
+
+    let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    let array = value.as_array().unwrap();
+    assert_eq!(array.len(), 1);
+    assert_eq!(array[0]["depth"], 1);
+    assert_eq!(array[0]["lines"][0]["line_number"], 1);
+
+    let mut empty = Vec::new();
+    write_json(&mut empty, Vec::<SearchResult>::new()).unwrap();
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&empty).unwrap(),
+        serde_json::json!([])
+    );
+}
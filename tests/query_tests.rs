@@ -0,0 +1,99 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{Query, SearchBuilder};
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn search(query: &str) -> Vec<String> {
+    let mut names: Vec<String> = Query::parse(query)
+        .unwrap()
+        .apply(SearchBuilder::default().location(fixtures_dir()))
+        .build_paths()
+        .filter(|p| p.is_file())
+        .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn query_name_and_ext() {
+    assert_eq!(search("HELLO ext:rs"), vec!["hello.rs"]);
+}
+
+#[test]
+fn query_multiple_extensions() {
+    assert_eq!(search("ext:txt,toml"), vec!["Cargo.toml", "world.txt"]);
+}
+
+#[test]
+fn query_negation_and_or() {
+    assert_eq!(search("ext:rs -path:subdir"), vec!["hello.rs"]);
+    assert_eq!(
+        search("(name:deep OR name:nested) ext:rs"),
+        vec!["deep_file.rs", "nested.rs"]
+    );
+}
+
+#[test]
+fn query_settings_map_onto_builder() {
+    assert_eq!(search("ext:rs depth:1"), vec!["hello.rs"]);
+    assert!(search("hidden:yes name:hidden").contains(&".hidden_file".to_string()));
+}
+
+#[test]
+fn query_size_filter() {
+    assert!(search("size:>1MB").is_empty());
+    assert!(!search("size:<1MB").is_empty());
+}
+
+#[test]
+fn query_time_and_size_terms_look_inside_other_directories() {
+    let dir = temp_dir("query_old_dir");
+    fs::create_dir_all(dir.join("old")).unwrap();
+    fs::write(dir.join("old/recent.txt"), "new").unwrap();
+    let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_836_800);
+    File::open(dir.join("old"))
+        .unwrap()
+        .set_modified(old)
+        .unwrap();
+
+    for query in ["modified:<30d", "size:<1KB ext:txt"] {
+        let found: Vec<PathBuf> = Query::parse(query)
+            .unwrap()
+            .apply(SearchBuilder::default().location(&dir))
+            .build_paths()
+            .collect();
+        assert!(found.contains(&dir.join("old/recent.txt")), "{query}");
+        assert!(!found.contains(&dir.join("old")), "{query}");
+    }
+}
+
+#[test]
+fn query_extensions_ignore_case() {
+    let dir = temp_dir("query_ext_case");
+    fs::write(dir.join("REPORT.PDF"), "").unwrap();
+    fs::write(dir.join("notes.txt"), "").unwrap();
+
+    for query in ["ext:pdf", "ext:pdf,docx"] {
+        let found: Vec<PathBuf> = Query::parse(query)
+            .unwrap()
+            .apply(SearchBuilder::default().location(&dir))
+            .build_paths()
+            .collect();
+        assert_eq!(found, [dir.join("REPORT.PDF")], "{query}");
+    }
+}
+
+#[test]
+fn query_error_has_position() {
+    let error = Query::parse("ext:rs (name:a OR").unwrap_err();
+    assert_eq!(error.position(), 17);
+    assert_eq!(error.to_string(), "expected a search term at position 17");
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{PathStyle, RenameConflictKind, RenameError, RenamePlan, Renamer, SearchBuilder};
+use std::fs;
+use std::path::Path;
+
+fn names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn renames_with_captures_and_undoes_them() {
+    let dir = temp_dir("rename_captures");
+    for name in ["IMG_0002.JPG", "IMG_0001.JPG", "notes.txt"] {
+        fs::write(dir.join(name), name).unwrap();
+    }
+    let journal = dir.join("renames.journal");
+    let search = SearchBuilder::default().location(&dir).depth(1);
+
+    let plan = Renamer::new(r"IMG_(\d+)\.JPG", "{n:2}-photo-$1.jpg")
+        .unwrap()
+        .journal(&journal)
+        .run(search.build())
+        .unwrap();
+    assert_eq!(plan.renames().len(), 2);
+    assert_eq!(
+        names(&dir),
+        [
+            "01-photo-0001.jpg",
+            "02-photo-0002.jpg",
+            "notes.txt",
+            "renames.journal"
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("02-photo-0002.jpg")).unwrap(),
+        "IMG_0002.JPG"
+    );
+
+    RenamePlan::undo(&journal).unwrap().apply().unwrap();
+    assert_eq!(
+        names(&dir),
+        [
+            "IMG_0001.JPG",
+            "IMG_0002.JPG",
+            "notes.txt",
+            "renames.journal"
+        ]
+    );
+}
+
+#[test]
+fn conflicts_stop_the_whole_batch() {
+    let dir = temp_dir("rename_conflicts");
+    for name in ["a1.txt", "a2.txt", "b.txt", "keep.log"] {
+        fs::write(dir.join(name), name).unwrap();
+    }
+    let files = || {
+        ["a1.txt", "a2.txt", "b.txt"]
+            .map(|name| dir.join(name))
+            .into_iter()
+    };
+
+    let plan = Renamer::new(r"a\d", "same").unwrap().plan(files());
+    let kinds: Vec<RenameConflictKind> = plan.conflicts().iter().map(|c| c.kind()).collect();
+    assert_eq!(kinds, [RenameConflictKind::SameTarget; 2]);
+    assert!(matches!(plan.apply(), Err(RenameError::Conflicts(c)) if c.len() == 2));
+
+    let plan = Renamer::new(r"^b\.txt$", "keep.log").unwrap().plan(files());
+    assert_eq!(plan.conflicts()[0].kind(), RenameConflictKind::Exists);
+
+    let plan = Renamer::new(r"\.txt$", "/x").unwrap().plan(files());
+    assert_eq!(plan.conflicts()[0].kind(), RenameConflictKind::InvalidName);
+
+    assert_eq!(names(&dir), ["a1.txt", "a2.txt", "b.txt", "keep.log"]);
+}
+
+#[test]
+fn names_can_be_rotated() {
+    let dir = temp_dir("rename_cycles");
+    for n in 1..=3 {
+        fs::write(dir.join(format!("{n}.txt")), n.to_string()).unwrap();
+    }
+    let journal = dir.join("rotate.journal");
+    let mut lines = String::from("rust_search rename journal 1\n");
+    for (from, to) in [("2", "1"), ("3", "2"), ("1", "3")] {
+        let path = |n: &str| dir.join(format!("{n}.txt")).display().to_string();
+        lines.push_str(&format!("{}\t{}\n", path(from), path(to)));
+    }
+    fs::write(&journal, lines).unwrap();
+
+    // Undoing the journal renames 1 to 2, 2 to 3 and 3 to 1.
+    let plan = RenamePlan::undo(&journal).unwrap();
+    assert_eq!(plan.cycles().len(), 1);
+    assert_eq!(plan.cycles()[0].len(), 3);
+    assert!(plan.conflicts().is_empty());
+    plan.apply().unwrap();
+    for (name, content) in [("1.txt", "3"), ("2.txt", "1"), ("3.txt", "2")] {
+        assert_eq!(fs::read_to_string(dir.join(name)).unwrap(), content);
+    }
+}
+
+#[test]
+fn apply_checks_the_disk_again() {
+    let dir = temp_dir("rename_recheck");
+    fs::write(dir.join("a.txt"), "a").unwrap();
+    let pid = std::process::id();
+    fs::write(dir.join(format!(".rsearch-rename-{pid}-0")), "squatter").unwrap();
+
+    let plan = Renamer::new("a", "b").unwrap().plan([dir.join("a.txt")]);
+    assert!(plan.conflicts().is_empty());
+    fs::write(dir.join("b.txt"), "created since").unwrap();
+    assert!(matches!(plan.apply(), Err(RenameError::Conflicts(c)) if c.len() == 1));
+    assert_eq!(
+        fs::read_to_string(dir.join("b.txt")).unwrap(),
+        "created since"
+    );
+
+    fs::remove_file(dir.join("b.txt")).unwrap();
+    plan.apply().unwrap();
+    assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "a");
+    let squatter = dir.join(format!(".rsearch-rename-{pid}-0"));
+    assert_eq!(fs::read_to_string(squatter).unwrap(), "squatter");
+}
+
+#[test]
+fn results_are_renamed_where_they_were_found() {
+    let dir = temp_dir("rename_relative");
+    fs::write(dir.join("a.txt"), "a").unwrap();
+
+    let search = SearchBuilder::default()
+        .location(&dir)
+        .output_paths(PathStyle::Relative)
+        .build_results();
+    let plan = Renamer::new(r"\.txt$", ".md").unwrap().plan(search);
+    assert_eq!(plan.renames().len(), 1);
+    plan.apply().unwrap();
+    assert_eq!(names(&dir), ["a.md"]);
+}
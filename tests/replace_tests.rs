@@ -0,0 +1,117 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{PathStyle, Replacer, SearchBuilder};
+use std::fs;
+
+#[test]
+fn dry_run_previews_without_writing() {
+    let dir = temp_dir("replace_dry_run");
+    let file = dir.join("a.txt");
+    fs::write(&file, "foo1\nbar\nfoo2\n").unwrap();
+
+    let search = SearchBuilder::default()
+        .location(&dir)
+        .contains("foo")
+        .build();
+    let report = Replacer::new(r"foo(\d)", "baz$1")
+        .unwrap()
+        .dry_run()
+        .run(search);
+    let contents = fs::read_to_string(&file).unwrap();
+
+    assert_eq!(contents, "foo1\nbar\nfoo2\n", "dry run must not write");
+    assert_eq!(report.total_replacements(), 2);
+    let diff = report.files()[0].diff().unwrap();
+    assert!(
+        diff.contains("-foo1\n+baz1\n bar\n-foo2\n+baz2\n"),
+        "{diff}"
+    );
+}
+
+#[test]
+fn run_writes_and_keeps_permissions() {
+    let dir = temp_dir("replace_write");
+    let file = dir.join("script.sh");
+    fs::write(&file, "echo old\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o750)).unwrap();
+    }
+
+    let report = Replacer::new("old", "new").unwrap().run([&file]);
+    let contents = fs::read_to_string(&file).unwrap();
+    #[cfg(unix)]
+    let mode = {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(&file).unwrap().permissions().mode() & 0o777
+    };
+    let leftovers = fs::read_dir(&dir).unwrap().count();
+
+    assert_eq!(contents, "echo new\n");
+    assert_eq!(report.files()[0].replacements(), 1);
+    assert!(report.files()[0].diff().is_none());
+    assert_eq!(leftovers, 1, "the temporary file should be renamed away");
+    #[cfg(unix)]
+    assert_eq!(mode, 0o750);
+}
+
+#[test]
+fn errors_are_collected_per_file() {
+    let dir = temp_dir("replace_errors");
+    let file = dir.join("ok.txt");
+    fs::write(&file, "old").unwrap();
+
+    let report = Replacer::new("old", "new")
+        .unwrap()
+        .run([dir.join("missing.txt"), file.clone()]);
+    let contents = fs::read_to_string(&file).unwrap();
+
+    assert_eq!(report.errors().len(), 1);
+    assert_eq!(report.errors()[0].0, dir.join("missing.txt"));
+    assert_eq!(contents, "new", "later files are still replaced");
+}
+
+#[test]
+fn invalid_pattern_is_an_error() {
+    assert!(Replacer::new("(", "x").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn run_keeps_symlinks_and_hard_links() {
+    let dir = temp_dir("replace_links");
+    let target = dir.join("target.txt");
+    let link = dir.join("link.txt");
+    let shared = dir.join("shared.txt");
+    let other = dir.join("other.txt");
+    fs::write(&target, "old\n").unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+    fs::write(&shared, "old\n").unwrap();
+    fs::hard_link(&shared, &other).unwrap();
+
+    let report = Replacer::new("old", "new").unwrap().run([&link, &shared]);
+
+    assert_eq!(report.total_replacements(), 2);
+    assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "new\n");
+    assert_eq!(fs::read_to_string(&other).unwrap(), "new\n");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+}
+
+#[test]
+fn results_are_written_where_they_were_found() {
+    let dir = temp_dir("replace_relative");
+    let file = dir.join("a.txt");
+    fs::write(&file, "old\n").unwrap();
+
+    let search = SearchBuilder::default()
+        .location(&dir)
+        .output_paths(PathStyle::Relative)
+        .build_results();
+    let report = Replacer::new("old", "new").unwrap().run(search);
+
+    assert_eq!(report.total_replacements(), 1);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "new\n");
+}
@@ -1,5 +1,5 @@
-use rust_search::SearchBuilder;
-use std::path::PathBuf;
+use rust_search::{PathStyle, SearchBuilder, SearchResult};
+use std::path::{Path, PathBuf};
 
 fn fixtures_dir() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
@@ -185,3 +185,115 @@ fn search_chained_options() {
     assert!(!results.is_empty(), "Chained options should find nested.rs");
     assert!(results.iter().any(|r| r.contains("nested.rs")));
 }
+
+#[test]
+fn search_build_paths_returns_path_bufs() {
+    let results: Vec<PathBuf> = SearchBuilder::default()
+        .location(fixtures_path())
+        .search_input("hello")
+        .ext("rs")
+        .build_paths()
+        .collect();
+    assert_eq!(results, vec![fixtures_dir().join("hello.rs")]);
+}
+
+#[cfg(unix)]
+#[test]
+fn search_build_paths_keeps_non_utf8_names() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = std::env::temp_dir().join(format!("rust_search_non_utf8_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let latin1 = dir.join(OsStr::from_bytes(b"caf\xe9.txt"));
+    if std::fs::write(&latin1, b"").is_err() {
+        // Some filesystems only accept UTF-8 names.
+        std::fs::remove_dir_all(&dir).unwrap();
+        return;
+    }
+
+    let results: Vec<PathBuf> = SearchBuilder::default()
+        .location(&dir)
+        .search_input("caf")
+        .ext("txt")
+        .build_paths()
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(results, vec![latin1]);
+}
+
+#[test]
+fn search_output_paths_relative() {
+    let results: Vec<PathBuf> = SearchBuilder::default()
+        .location(fixtures_path())
+        .search_input("nested")
+        .ext("rs")
+        .output_paths(PathStyle::Relative)
+        .build_paths()
+        .collect();
+    assert_eq!(results, vec![PathBuf::from("subdir/nested.rs")]);
+}
+
+#[test]
+fn search_output_paths_relative_to_base() {
+    let results: Vec<PathBuf> = SearchBuilder::default()
+        .location(fixtures_path())
+        .search_input("hello")
+        .ext("rs")
+        .output_paths(PathStyle::RelativeTo(fixtures_dir().join("subdir")))
+        .build_paths()
+        .collect();
+    assert_eq!(results, vec![PathBuf::from("../hello.rs")]);
+}
+
+#[test]
+fn search_output_paths_canonical() {
+    let results: Vec<PathBuf> = SearchBuilder::default()
+        .location("tests/fixtures/subdir/..")
+        .search_input("hello")
+        .ext("rs")
+        .output_paths(PathStyle::Canonical)
+        .build_paths()
+        .collect();
+    assert_eq!(
+        results,
+        vec![fixtures_dir().join("hello.rs").canonicalize().unwrap()]
+    );
+}
+
+#[test]
+fn search_results_record_their_root() {
+    let subdir = fixtures_dir().join("subdir");
+    let results: Vec<SearchResult> = SearchBuilder::default()
+        .location(fixtures_path())
+        .more_locations(vec![&subdir])
+        .search_input("nested")
+        .ext("rs")
+        .output_paths(PathStyle::Relative)
+        .build_results()
+        .collect();
+    assert_eq!(results.len(), 2, "{:?}", results);
+    assert!(results
+        .iter()
+        .any(|r| r.root() == fixtures_dir() && r.path() == Path::new("subdir/nested.rs")));
+    assert!(results
+        .iter()
+        .any(|r| r.root() == subdir && r.path() == Path::new("nested.rs")));
+}
+
+#[test]
+fn cloned_builder_searches_the_same() {
+    let builder = SearchBuilder::default()
+        .location(fixtures_path())
+        .ext("rs")
+        .strict();
+    let narrowed = builder.clone().search_input("hello");
+
+    let mut all: Vec<String> = builder.build().collect();
+    all.sort();
+    let narrowed: Vec<String> = narrowed.build().collect();
+    assert_eq!(all.len(), 3, "{all:?}");
+    assert_eq!(narrowed.len(), 1, "{narrowed:?}");
+    assert!(format!("{builder:?}").contains("strict: true"));
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{PathStyle, SearchBuilder, Snapshot};
+use std::fs;
+
+#[test]
+fn diff_reports_every_kind_of_change() {
+    let dir = temp_dir("snapshot_diff");
+    fs::create_dir_all(dir.join("docs")).unwrap();
+    fs::write(dir.join("docs/keep.txt"), "keep").unwrap();
+    fs::write(dir.join("edit.txt"), "edit").unwrap();
+    fs::write(dir.join("gone.txt"), "gone").unwrap();
+    fs::write(dir.join("moved.txt"), "moved").unwrap();
+    fs::write(dir.join("copied.txt"), "copied contents").unwrap();
+    let search = SearchBuilder::default().location(&dir).ext("txt");
+    let before = Snapshot::take_hashed(search.build_results());
+    assert_eq!(before.len(), 5);
+    assert!(before.get(dir.join("edit.txt")).unwrap().hash().is_some());
+
+    let file = dir.join("before.snapshot");
+    before.save(&file).unwrap();
+    let before = Snapshot::load(&file).unwrap();
+    assert_eq!(before.len(), 5);
+
+    fs::write(dir.join("edit.txt"), "edited").unwrap();
+    fs::remove_file(dir.join("gone.txt")).unwrap();
+    fs::write(dir.join("new.txt"), "new").unwrap();
+    fs::rename(dir.join("moved.txt"), dir.join("docs/moved.txt")).unwrap();
+    // A copy gets a new inode, so only the hash pairs it up.
+    fs::copy(dir.join("copied.txt"), dir.join("docs/copied.txt")).unwrap();
+    fs::remove_file(dir.join("copied.txt")).unwrap();
+
+    let after = Snapshot::take_hashed(search.build_results());
+    let diff = before.diff(&after);
+    assert_eq!(diff.added(), [dir.join("new.txt")]);
+    assert_eq!(diff.deleted(), [dir.join("gone.txt")]);
+    assert_eq!(diff.modified(), [dir.join("edit.txt")]);
+    assert_eq!(
+        diff.renamed(),
+        [
+            (dir.join("copied.txt"), dir.join("docs/copied.txt")),
+            (dir.join("moved.txt"), dir.join("docs/moved.txt")),
+        ]
+    );
+    assert!(after.diff(&after).is_empty());
+
+    fs::write(&file, "not a snapshot").unwrap();
+    assert!(Snapshot::load(&file).is_err());
+}
+
+#[test]
+fn unhashed_snapshots_compare_size_and_mtime() {
+    let dir = temp_dir("snapshot_plain");
+    fs::write(dir.join("a.log"), "a").unwrap();
+    fs::write(dir.join("b.log"), "b").unwrap();
+    let search = SearchBuilder::default().location(&dir).ext("log");
+    let before = Snapshot::take(search.build_results());
+    assert_eq!(before.get(dir.join("a.log")).unwrap().hash(), None);
+    assert_eq!(before.get(dir.join("a.log")).unwrap().size(), Some(1));
+
+    fs::write(dir.join("a.log"), "longer").unwrap();
+    fs::rename(dir.join("b.log"), dir.join("c.log")).unwrap();
+    let diff = before.diff(&Snapshot::take(search.build_results()));
+    assert_eq!(diff.modified(), [dir.join("a.log")]);
+    if cfg!(unix) {
+        assert_eq!(diff.renamed(), [(dir.join("b.log"), dir.join("c.log"))]);
+    } else {
+        assert_eq!(diff.added(), [dir.join("c.log")]);
+        assert_eq!(diff.deleted(), [dir.join("b.log")]);
+    }
+}
+
+#[test]
+fn hashed_snapshots_read_files_where_they_were_found() {
+    let dir = temp_dir("snapshot_relative");
+    fs::write(dir.join("a.log"), "a").unwrap();
+    let search = SearchBuilder::default()
+        .location(&dir)
+        .ext("log")
+        .output_paths(PathStyle::Relative);
+    let snapshot = Snapshot::take_hashed(search.build_results());
+    let entry = snapshot.get("a.log").unwrap();
+    assert_eq!(
+        entry.hash(),
+        Some("ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb")
+    );
+}
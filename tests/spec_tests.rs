@@ -0,0 +1,180 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use common::temp_dir;
+use rust_search::{SavedSearches, SearchSpec, SpecError};
+use std::fs;
+
+fn sorted(search: impl Iterator<Item = String>) -> Vec<String> {
+    let mut paths: Vec<String> = search.collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn toml_spec_builds_search() {
+    let spec = SearchSpec::from_toml(
+        r#"
+        locations = ["tests/fixtures"]
+        extensions = ["rs"]
+        size = ["<1MB"]
+        modified = [">2000-01-01"]
+        "#,
+    )
+    .unwrap();
+
+    let paths = sorted(spec.into_builder().unwrap().build());
+    assert_eq!(
+        paths,
+        [
+            "tests/fixtures/hello.rs",
+            "tests/fixtures/subdir/deep/deep_file.rs",
+            "tests/fixtures/subdir/nested.rs",
+        ]
+    );
+}
+
+#[test]
+fn size_smaller_than_a_directory_still_searches_subdirectories() {
+    // What `rsearch --size '<2KB' -e rs tests/fixtures` builds.
+    let spec = SearchSpec {
+        locations: vec!["tests/fixtures".into()],
+        extensions: vec!["rs".into()],
+        size: vec!["<2KB".into()],
+        ..SearchSpec::default()
+    };
+
+    let paths = sorted(spec.into_builder().unwrap().build());
+    assert_eq!(
+        paths,
+        [
+            "tests/fixtures/hello.rs",
+            "tests/fixtures/subdir/deep/deep_file.rs",
+            "tests/fixtures/subdir/nested.rs",
+        ]
+    );
+}
+
+#[test]
+fn several_extensions_match_any() {
+    let spec = SearchSpec {
+        locations: vec!["tests/fixtures".into()],
+        extensions: vec!["txt".into(), "toml".into()],
+        depth: Some(1),
+        ..SearchSpec::default()
+    };
+
+    let paths = sorted(spec.into_builder().unwrap().build());
+    assert_eq!(
+        paths,
+        ["tests/fixtures/Cargo.toml", "tests/fixtures/world.txt"]
+    );
+}
+
+#[test]
+fn extensions_ignore_case() {
+    let dir = temp_dir("spec_ext_case");
+    fs::write(dir.join("REPORT.PDF"), "").unwrap();
+    fs::write(dir.join("notes.txt"), "").unwrap();
+
+    for extensions in [vec![".pdf".into()], vec!["pdf".into(), "docx".into()]] {
+        let spec = SearchSpec {
+            locations: vec![dir.to_path_buf()],
+            extensions,
+            ..SearchSpec::default()
+        };
+        let paths = sorted(spec.into_builder().unwrap().build());
+        assert_eq!(paths, [dir.join("REPORT.PDF").to_string_lossy()]);
+    }
+}
+
+#[test]
+fn json_and_toml_round_trip() {
+    let spec = SearchSpec {
+        locations: vec!["/var/log".into()],
+        input: Some("error".into()),
+        extensions: vec!["log".into()],
+        hidden: true,
+        modified: vec![">90d".into()],
+        ..SearchSpec::default()
+    };
+
+    assert_eq!(
+        SearchSpec::from_toml(&spec.to_toml().unwrap()).unwrap(),
+        spec
+    );
+    assert_eq!(
+        SearchSpec::from_json(&spec.to_json().unwrap()).unwrap(),
+        spec
+    );
+    assert!(
+        !spec.to_toml().unwrap().contains("strict"),
+        "defaults are left out"
+    );
+}
+
+#[test]
+fn invalid_values_name_the_field() {
+    let unknown = SearchSpec::from_toml("colour = \"red\"").unwrap_err();
+    assert!(matches!(unknown, SpecError::Toml(_)), "{unknown}");
+
+    let spec = SearchSpec {
+        size: vec![">2XB".into()],
+        ..SearchSpec::default()
+    };
+    let error = spec.into_builder().unwrap_err();
+    assert!(
+        matches!(error, SpecError::Invalid { field: "size", .. }),
+        "{error}"
+    );
+
+    // Values are parsed on their own, not as query text.
+    let spec = SearchSpec {
+        modified: vec![">2000-01-01 OR name:x".into()],
+        ..SearchSpec::default()
+    };
+    let error = spec.into_builder().unwrap_err();
+    assert!(
+        matches!(
+            error,
+            SpecError::Invalid {
+                field: "modified",
+                ..
+            }
+        ),
+        "{error}"
+    );
+
+    let spec = SearchSpec {
+        contains_regex: Some("(".into()),
+        ..SearchSpec::default()
+    };
+    assert!(spec.into_builder().is_err());
+}
+
+#[test]
+fn saved_searches_load_and_save() {
+    let dir = temp_dir("saved_searches");
+    let path = dir.join("config").join("searches.toml");
+
+    let mut saved = SavedSearches::load(&path).unwrap();
+    assert_eq!(saved.names().count(), 0, "a missing file holds no searches");
+    saved.insert(
+        "fixtures",
+        SearchSpec {
+            locations: vec!["tests/fixtures".into()],
+            input: Some("hello".into()),
+            ..SearchSpec::default()
+        },
+    );
+    saved.save(&path).unwrap();
+    let loaded = SavedSearches::load(&path).unwrap();
+    let text = fs::read_to_string(&path).unwrap();
+
+    assert!(text.starts_with("[searches.fixtures]"), "{text}");
+    assert_eq!(loaded, saved);
+    let paths = sorted(loaded.builder("fixtures").unwrap().unwrap().build());
+    assert_eq!(paths, ["tests/fixtures/hello.rs"]);
+    assert!(loaded.builder("missing").is_none());
+}
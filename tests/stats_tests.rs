@@ -0,0 +1,84 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{FileSize, FilterExt, MemoryFs, SearchBuilder, SearchStats};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[test]
+fn stats_count_the_walk_of_the_disk() {
+    let dir = temp_dir("stats_disk");
+    fs::create_dir_all(dir.join(".git")).unwrap();
+    fs::create_dir_all(dir.join("src/deep")).unwrap();
+    fs::create_dir_all(dir.join("target")).unwrap();
+    fs::write(dir.join(".gitignore"), "target/\n*.tmp\n").unwrap();
+    fs::write(dir.join("src/a.rs"), "0123456789").unwrap();
+    fs::write(dir.join("src/deep/b.rs"), "01234").unwrap();
+    fs::write(dir.join("src/big.txt"), vec![b'x'; 20_000]).unwrap();
+    fs::write(dir.join("scratch.tmp"), "").unwrap();
+    fs::write(dir.join("target/out.rs"), "").unwrap();
+
+    let mut search = SearchBuilder::default()
+        .location(&dir)
+        .search_input(r"\.rs$")
+        .file_size_smaller(FileSize::Kilobyte(10.0))
+        .detailed_stats()
+        .build();
+    let found = search.by_ref().count();
+    let stats = search.stats();
+
+    assert_eq!(found, 2);
+    assert!(stats.is_finished());
+    assert_eq!(stats.matches(), 2);
+    assert_eq!(stats.bytes_matched(), 15);
+    // The location, src, a.rs, deep and b.rs, hidden entries are not walked.
+    assert_eq!(stats.entries(), 5);
+    assert_eq!(stats.dirs(), 3);
+    assert_eq!(stats.filtered(), 1);
+    assert_eq!(stats.ignored(), 2);
+    assert_eq!(stats.errors(), 0);
+
+    let limited = SearchBuilder::default()
+        .location(&dir)
+        .search_input(r"\.rs$")
+        .limit(1)
+        .build_paths();
+    assert_eq!(limited.count(), 1);
+}
+
+#[test]
+fn progress_gets_the_final_stats_before_the_last_result() {
+    let fs = MemoryFs::default()
+        .file("/logs/app.log", "ok\nerror: disk full\n")
+        .file("/logs/old/app.log.1", "ok\n")
+        .file("/logs/.gitignore", "*.1\n")
+        .dir("/logs/.git");
+    let reports = Arc::new(Mutex::new(Vec::<SearchStats>::new()));
+    let sink = Arc::clone(&reports);
+
+    let found: Vec<String> = SearchBuilder::default()
+        .location("/logs")
+        .search_input("app")
+        .contains("error")
+        .detailed_stats()
+        .file_system(fs)
+        .progress(Duration::from_secs(60), move |stats| {
+            sink.lock().unwrap().push(*stats);
+        })
+        .build()
+        .collect();
+
+    assert_eq!(found, ["/logs/app.log"]);
+    let reports = reports.lock().unwrap();
+    let last = reports.last().unwrap();
+    assert!(last.is_finished());
+    assert_eq!(last.matches(), 1);
+    assert_eq!(last.bytes_matched(), 20);
+    assert_eq!(last.dirs(), 2);
+    assert_eq!(last.entries(), 3);
+    assert_eq!(last.ignored(), 1);
+    assert!(last
+        .to_string()
+        .starts_with("3 entries in 2 dirs, 1 matches (20),"));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::SearchBuilder;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn disk_usage_rolls_up_to_the_depth() {
+    let dir = temp_dir("disk_usage");
+    fs::create_dir_all(dir.join("sub/deep")).unwrap();
+    fs::create_dir_all(dir.join(".hidden")).unwrap();
+    fs::write(dir.join("a.txt"), [0; 10]).unwrap();
+    fs::write(dir.join("b.log"), [0; 5]).unwrap();
+    fs::write(dir.join("sub/c.txt"), [0; 100]).unwrap();
+    fs::write(dir.join("sub/deep/d.txt"), [0; 1000]).unwrap();
+    fs::write(dir.join("sub/deep/f"), [0; 3]).unwrap();
+    fs::write(dir.join(".hidden/e.txt"), [0; 7]).unwrap();
+
+    let usage = SearchBuilder::default().location(&dir).disk_usage(1);
+    assert_eq!(usage.total().size(), 1118);
+    assert_eq!(usage.total().files(), 5);
+    let dirs: Vec<(&Path, usize, u64, u64)> = usage
+        .dirs()
+        .iter()
+        .map(|d| (d.path(), d.depth(), d.size(), d.files()))
+        .collect();
+    assert_eq!(
+        dirs,
+        [(&*dir, 0, 1118, 5), (dir.join("sub").as_path(), 1, 1103, 3)]
+    );
+    assert_eq!(
+        usage.dirs()[1].largest(),
+        Some((dir.join("sub/deep/d.txt").as_path(), 1000))
+    );
+    let txt = usage.by_extension()["txt"];
+    assert_eq!((txt.size(), txt.files()), (1110, 3));
+    assert_eq!(usage.by_extension()[""].files(), 1);
+    if cfg!(unix) {
+        let owned: u64 = usage.by_owner().values().map(|u| u.files()).sum();
+        assert_eq!(owned, 5);
+    }
+
+    let usage = SearchBuilder::default()
+        .location(&dir)
+        .hidden()
+        .ext("txt")
+        .disk_usage(2);
+    assert_eq!(usage.total().files(), 4);
+    let dirs: Vec<&Path> = usage.dirs().iter().map(|d| d.path()).collect();
+    assert_eq!(
+        dirs,
+        [
+            &*dir,
+            dir.join(".hidden").as_path(),
+            dir.join("sub").as_path(),
+            dir.join("sub/deep").as_path()
+        ]
+    );
+    assert_eq!(usage.by_extension().len(), 1);
+}
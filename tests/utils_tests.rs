@@ -1,4 +1,9 @@
-use rust_search::similarity_sort;
+use rust_search::{
+    similarity_sort, similarity_sort_by_key, similarity_sort_with, Acronym, FzfV2, Levenshtein,
+    ScoreTarget,
+};
+use std::ffi::OsStr;
+use std::path::PathBuf;
 
 #[test]
 fn similarity_sort_basic() {
@@ -35,3 +40,76 @@ fn similarity_sort_single_element() {
     similarity_sort(&mut v, "only");
     assert_eq!(v[0], "only.txt");
 }
+
+#[test]
+fn similarity_sort_with_acronym_scorer() {
+    let mut v = vec![
+        "src/subscribe.rs".to_string(),
+        "src/search_builder.rs".to_string(),
+        "src/SearchBuilder.rs".to_string(),
+    ];
+    similarity_sort_with(&mut v, "sb", &Acronym, ScoreTarget::FileName);
+    assert_eq!(v[2], "src/subscribe.rs", "acronyms should win: {:?}", v);
+}
+
+#[test]
+fn similarity_sort_with_full_path() {
+    let mut v = vec!["docs/readme.md".to_string(), "src/readme.md".to_string()];
+    similarity_sort_with(&mut v, "src/readme.md", &Levenshtein, ScoreTarget::FullPath);
+    assert_eq!(v[0], "src/readme.md");
+}
+
+#[test]
+fn similarity_sort_with_closure_scorer() {
+    let mut v = vec!["a.txt".to_string(), "abc.txt".to_string()];
+    similarity_sort_with(
+        &mut v,
+        "",
+        &|candidate: &str, _: &str| candidate.len() as f64,
+        ScoreTarget::FileName,
+    );
+    assert_eq!(v[0], "abc.txt");
+}
+
+#[test]
+fn similarity_sort_with_fzf() {
+    let mut v = vec![
+        "bxuxixlxd.rs".to_string(),
+        "main.rs".to_string(),
+        "builder.rs".to_string(),
+    ];
+    similarity_sort_with(&mut v, "build", &FzfV2, ScoreTarget::FileName);
+    assert_eq!(v[0], "builder.rs");
+    assert_eq!(v[2], "main.rs");
+}
+
+#[test]
+fn similarity_sort_paths() {
+    let mut v = vec![
+        PathBuf::from("/tmp/afly.txt"),
+        PathBuf::from("/tmp/fly.txt"),
+        PathBuf::from("/tmp/flyer.txt"),
+    ];
+    similarity_sort(&mut v, "fly");
+    assert_eq!(v[0], PathBuf::from("/tmp/fly.txt"));
+}
+
+#[test]
+fn similarity_sort_by_key_records() {
+    struct Record {
+        name: String,
+        size: u64,
+    }
+    let mut v = vec![
+        Record {
+            name: "afly.txt".to_string(),
+            size: 1,
+        },
+        Record {
+            name: "fly.txt".to_string(),
+            size: 2,
+        },
+    ];
+    similarity_sort_by_key(&mut v, "fly", |r| OsStr::new(&r.name));
+    assert_eq!(v[0].size, 2);
+}
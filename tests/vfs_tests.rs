@@ -0,0 +1,132 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{DiskFs, FileKind, FileSize, FilterExt, MemoryFs, PathStyle, SearchBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+fn days(n: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(n * 86_400)
+}
+
+fn tree() -> MemoryFs {
+    MemoryFs::default()
+        .file("/proj/src/main.rs", "fn main() {\n    run();\n}\n")
+        .file("/proj/src/lib.rs", "pub fn run() {}\n")
+        .file("/proj/notes.txt", "call run() later\n")
+        .file("/proj/.hidden.rs", "")
+        .dir("/proj/empty")
+        .modified("/proj/src", days(40))
+        .modified("/proj/src/main.rs", days(10))
+        .modified("/proj/src/lib.rs", days(20))
+        .modified("/proj/notes.txt", days(30))
+}
+
+#[test]
+fn memory_fs_is_searched_with_matchers_and_filters() {
+    let search = |builder: SearchBuilder| -> Vec<String> {
+        builder
+            .location("/proj")
+            .file_system(tree())
+            .build()
+            .collect()
+    };
+
+    assert_eq!(
+        search(SearchBuilder::default().ext("rs")),
+        ["/proj/src/lib.rs", "/proj/src/main.rs"]
+    );
+    assert_eq!(
+        search(SearchBuilder::default().ext("rs").hidden()),
+        ["/proj/.hidden.rs", "/proj/src/lib.rs", "/proj/src/main.rs"]
+    );
+    assert_eq!(
+        search(SearchBuilder::default().contains("run();")),
+        ["/proj/src/main.rs"]
+    );
+    assert_eq!(
+        search(SearchBuilder::default().ext("rs").modified_after(days(15))),
+        ["/proj/src/lib.rs"]
+    );
+    assert_eq!(
+        search(
+            SearchBuilder::default()
+                .search_input("^notes")
+                .file_size_greater(FileSize::Byte(10))
+        ),
+        ["/proj/notes.txt"]
+    );
+    assert_eq!(
+        search(SearchBuilder::default().ext("rs").depth(1)),
+        Vec::<String>::new()
+    );
+    assert_eq!(search(SearchBuilder::default().ext("rs").limit(1)).len(), 1);
+}
+
+#[test]
+fn memory_fs_results_record_metadata_and_lines() {
+    let results: Vec<_> = SearchBuilder::default()
+        .location("/proj")
+        .contains("run")
+        .line_matches()
+        .output_paths(PathStyle::Relative)
+        .file_system(tree())
+        .build_results()
+        .collect();
+
+    let main = results
+        .iter()
+        .find(|r| r.path() == Path::new("src/main.rs"))
+        .unwrap();
+    assert_eq!(main.kind(), FileKind::File);
+    assert_eq!(main.depth(), 2);
+    assert_eq!(main.modified(), Some(days(10)));
+    assert_eq!(main.lines().len(), 1);
+    assert_eq!(main.lines()[0].line_number(), 2);
+
+    let dirs: Vec<PathBuf> = SearchBuilder::default()
+        .location("/proj")
+        .search_input("empty")
+        .file_system(tree())
+        .build_paths()
+        .collect();
+    assert_eq!(dirs, [PathBuf::from("/proj/empty")]);
+}
+
+#[test]
+fn ignore_files_apply_in_git_repositories() {
+    let fs = tree()
+        .file("/proj/.gitignore", "*.txt\n")
+        .file("/proj/src/.ignore", "lib.rs\n");
+    let search = |fs: MemoryFs| -> Vec<String> {
+        SearchBuilder::default()
+            .location("/proj")
+            .search_input(r"\.(rs|txt)$")
+            .file_system(fs)
+            .build()
+            .collect()
+    };
+
+    assert_eq!(search(fs.clone()), ["/proj/notes.txt", "/proj/src/main.rs"]);
+    assert_eq!(search(fs.dir("/proj/.git")), ["/proj/src/main.rs"]);
+}
+
+#[test]
+fn disk_fs_finds_what_the_default_walk_finds() {
+    let dir = temp_dir("vfs_disk");
+    fs::create_dir_all(dir.join("a/b")).unwrap();
+    fs::write(dir.join("a/one.txt"), "hello").unwrap();
+    fs::write(dir.join("a/b/two.txt"), "hello world").unwrap();
+    fs::write(dir.join("a/b/three.log"), "hello").unwrap();
+
+    let builder = SearchBuilder::default()
+        .location(&dir)
+        .ext("txt")
+        .contains("world");
+    let mut expected: Vec<String> = builder.build().collect();
+    expected.sort();
+    let found: Vec<String> = builder.file_system(DiskFs).build().collect();
+    assert_eq!(found, expected);
+    assert_eq!(found.len(), 1);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::temp_dir;
+use rust_search::{SearchBuilder, Watch, WatchBackend, WatchEvent};
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// Wait up to a few seconds for `expected`, skipping other events.
+fn expect(watch: &Watch, expected: &WatchEvent) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        if watch.recv_timeout(left).as_ref() == Some(expected) {
+            return;
+        }
+    }
+    panic!("no {expected:?}");
+}
+
+fn check_events(watch: &Watch, dir: &std::path::Path) {
+    assert_eq!(watch.initial(), [dir.join("a.log")]);
+
+    fs::write(dir.join("b.log"), "b").unwrap();
+    expect(watch, &WatchEvent::Added(dir.join("b.log")));
+
+    fs::write(dir.join("a.log"), "longer").unwrap();
+    expect(watch, &WatchEvent::Modified(dir.join("a.log")));
+
+    fs::remove_file(dir.join("b.log")).unwrap();
+    expect(watch, &WatchEvent::Removed(dir.join("b.log")));
+
+    fs::create_dir_all(dir.join("new/deeper")).unwrap();
+    fs::write(dir.join("new/deeper/c.log"), "c").unwrap();
+    expect(watch, &WatchEvent::Added(dir.join("new/deeper/c.log")));
+
+    fs::write(dir.join("ignored.txt"), "txt").unwrap();
+    fs::remove_dir_all(dir.join("new")).unwrap();
+    expect(watch, &WatchEvent::Removed(dir.join("new/deeper/c.log")));
+    assert_eq!(watch.try_recv(), None);
+}
+
+#[test]
+fn watch_reports_changes() {
+    let dir = temp_dir("watch_inotify");
+    fs::write(dir.join("a.log"), "a").unwrap();
+    fs::write(dir.join("notes.txt"), "notes").unwrap();
+
+    let watch = SearchBuilder::default().location(&dir).ext("log").watch();
+    if cfg!(target_os = "linux") {
+        assert_eq!(watch.backend(), WatchBackend::Inotify);
+    }
+    check_events(&watch, &dir);
+    drop(watch);
+}
+
+#[test]
+fn watch_polling_reports_changes() {
+    let dir = temp_dir("watch_polling");
+    fs::write(dir.join("a.log"), "a").unwrap();
+
+    let interval = Duration::from_millis(50);
+    let watch = SearchBuilder::default()
+        .location(&dir)
+        .ext("log")
+        .watch_polling(interval);
+    assert_eq!(watch.backend(), WatchBackend::Polling(interval));
+    check_events(&watch, &dir);
+    drop(watch);
+}